@@ -34,11 +34,41 @@ pub struct LocalizationContribution {
     pub direction: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeybindingContribution {
+    pub key: String,
+    pub mac: Option<String>,
+    pub linux: Option<String>,
+    pub win: Option<String>,
+    pub command: String,
+    pub args: Option<serde_json::Value>,
+    /// A JSON Logic rule the context must satisfy for the binding to apply.
+    pub when: Option<serde_json::Value>,
+}
+
+/// An item added to a menu. Addons cannot contribute commands, the item runs one that is
+/// already registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuContribution {
+    /// The namespace of the menu, e.g. `menubar.file`.
+    pub menu: String,
+    pub command: String,
+    pub title: String,
+    pub group: Option<String>,
+    pub order: Option<i64>,
+    /// A JSON Logic rule the context must satisfy for the item to be shown.
+    pub when: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AddonContributions {
     pub themes: Option<Vec<ThemeContribution>>,
     pub localizations: Option<Vec<LocalizationContribution>>,
+    pub keybindings: Option<Vec<KeybindingContribution>>,
+    pub menus: Option<Vec<MenuContribution>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
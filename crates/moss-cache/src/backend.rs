@@ -1,12 +1,53 @@
+pub mod disk;
 pub mod moka;
+pub mod tiered;
 
+use crate::key::{CachePolicy, RawKey};
 use crate::CacheError;
+use serde::{de::DeserializeOwned, Serialize};
 use std::any::Any;
 use std::sync::Arc;
 
+/// Why an entry was removed from the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalCause {
+    /// The entry outlived its TTL.
+    Expired,
+    /// The entry was evicted to respect the capacity of the cache or of its key.
+    Size,
+    /// The entry was deleted or invalidated.
+    Explicit,
+}
+
+pub type RemovalListener = Arc<dyn Fn(&RawKey, RemovalCause) + Send + Sync>;
+
 pub trait CacheBackend {
-    fn insert<T: Any + Send + Sync>(&self, key: &str, val: T);
-    fn get<T: Any + Send + Sync>(&self, key: &str) -> Result<Arc<T>, CacheError>;
-    fn delete(&self, key: &str);
-    fn contains(&self, key: &str) -> bool;
+    fn insert<T: Any + Send + Sync>(&self, key: &RawKey, val: T, policy: &CachePolicy);
+    fn get<T: Any + Send + Sync>(&self, key: &RawKey) -> Result<Arc<T>, CacheError>;
+    fn delete(&self, key: &RawKey);
+    fn contains(&self, key: &RawKey) -> bool;
+
+    /// Drops the entry to make room for others. Unlike `delete`, the backend may keep
+    /// the entry in a slower storage.
+    fn evict(&self, key: &RawKey) {
+        self.delete(key);
+    }
+
+    /// Sets the listener the backend calls for the entries it removes on its own, because
+    /// they expired or to respect its capacity. Removals requested through `delete` and
+    /// `evict` are not reported, the cache reports them itself.
+    fn set_removal_listener(&self, _listener: RemovalListener) {}
+}
+
+/// A backend that stores serialized values, so that they outlive the process.
+pub trait PersistentBackend {
+    fn insert<T: Serialize>(
+        &self,
+        key: &RawKey,
+        val: &T,
+        policy: &CachePolicy,
+    ) -> Result<(), CacheError>;
+    fn get<T: DeserializeOwned>(&self, key: &RawKey) -> Result<T, CacheError>;
+    fn delete(&self, key: &RawKey);
+    fn contains(&self, key: &RawKey) -> bool;
 }
@@ -0,0 +1,479 @@
+use fnv::FnvHasher;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::any::type_name;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::backend::PersistentBackend;
+use crate::key::{CachePolicy, RawKey};
+use crate::CacheError;
+
+/// The version of the entry layout. Entries written with another version are discarded.
+pub const DISK_FORMAT_VERSION: u32 = 2;
+
+const ENTRY_EXTENSION: &str = "entry";
+const TMP_EXTENSION: &str = "tmp";
+
+/// Precedes the serialized value in an entry file, on a line of its own.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryHeader {
+    format: u32,
+    key: String,
+    type_name: String,
+    checksum: u64,
+    /// Seconds since the Unix epoch.
+    expires_at: Option<u64>,
+}
+
+struct IndexEntry {
+    size: u64,
+    last_access: u64,
+}
+
+/// The entries on the disk with their sizes and the order they were used in.
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, IndexEntry>,
+    total_size: u64,
+    clock: u64,
+}
+
+impl Index {
+    fn touch(&mut self, file_name: &str) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(file_name) {
+            entry.last_access = self.clock;
+        }
+    }
+
+    fn insert(&mut self, file_name: String, size: u64) {
+        self.clock += 1;
+        let entry = IndexEntry {
+            size,
+            last_access: self.clock,
+        };
+        if let Some(previous) = self.entries.insert(file_name, entry) {
+            self.total_size -= previous.size;
+        }
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, file_name: &str) {
+        if let Some(previous) = self.entries.remove(file_name) {
+            self.total_size -= previous.size;
+        }
+    }
+
+    fn least_recently_used(&self) -> Option<String> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_access)
+            .map(|(file_name, _)| file_name.clone())
+    }
+}
+
+/// Stores values as files in a directory, within a size budget.
+///
+/// Each entry is checksummed and tagged with the format version and the type of the value,
+/// entries that fail any of the checks are treated as missing and removed. When the budget is
+/// exceeded, the least recently used entries are removed first.
+pub struct DiskBackend {
+    dir: PathBuf,
+    budget: u64,
+    index: Mutex<Index>,
+}
+
+impl DiskBackend {
+    /// Opens the cache in the directory, picking up the entries written by previous runs.
+    /// The directory is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>, budget: u64) -> Self {
+        let dir = dir.into();
+        let index = match scan_dir(&dir) {
+            Ok(index) => index,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Index::default(),
+            Err(err) => {
+                warn!(
+                    "Failed to read the cache directory '{}': {err}",
+                    dir.display()
+                );
+                Index::default()
+            }
+        };
+
+        Self {
+            dir,
+            budget,
+            index: Mutex::new(index),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The total size of the entries on the disk, in bytes.
+    pub fn size(&self) -> u64 {
+        self.index.lock().unwrap().total_size
+    }
+
+    fn path(&self, file_name: &str) -> PathBuf {
+        self.dir.join(file_name).with_extension(ENTRY_EXTENSION)
+    }
+
+    fn remove_file(&self, file_name: &str) {
+        self.index.lock().unwrap().remove(file_name);
+        if let Err(err) = fs::remove_file(self.path(file_name)) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to remove the cache entry '{file_name}': {err}");
+            }
+        }
+    }
+
+    fn read(&self, key: &RawKey, file_name: &str, type_name: &str) -> Result<Vec<u8>, CacheError> {
+        let mut content = match fs::read(self.path(file_name)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.index.lock().unwrap().remove(file_name);
+                return Err(CacheError::NonexistentKey {
+                    key: key.to_string(),
+                });
+            }
+            Err(err) => return Err(persist_failed(key, err)),
+        };
+
+        let invalid = |reason: String| CacheError::InvalidEntry {
+            key: key.to_string(),
+            reason,
+        };
+        let separator = content
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| invalid("the header is missing".to_string()))?;
+        let header: EntryHeader = serde_json::from_slice(&content[..separator])
+            .map_err(|err| invalid(format!("the header is malformed: {err}")))?;
+        let payload = &content[separator + 1..];
+
+        if header.format != DISK_FORMAT_VERSION {
+            return Err(invalid(format!(
+                "the format version {} is not supported",
+                header.format
+            )));
+        }
+        if header.key != key.stable_repr() {
+            return Err(invalid(format!(
+                "the entry belongs to the key '{}'",
+                header.key
+            )));
+        }
+        if header.type_name != type_name {
+            return Err(invalid(format!(
+                "the value has the type name of '{}'",
+                header.type_name
+            )));
+        }
+        if header.checksum != checksum(payload) {
+            return Err(invalid("the checksum does not match".to_string()));
+        }
+        if header
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now())
+        {
+            return Err(CacheError::NonexistentKey {
+                key: key.to_string(),
+            });
+        }
+
+        Ok(content.split_off(separator + 1))
+    }
+
+    fn evict_over_budget(&self) {
+        loop {
+            let file_name = {
+                let index = self.index.lock().unwrap();
+                if index.total_size <= self.budget {
+                    return;
+                }
+                match index.least_recently_used() {
+                    Some(file_name) => file_name,
+                    None => return,
+                }
+            };
+
+            trace!("Evicting the cache entry '{file_name}' from the disk");
+            self.remove_file(&file_name);
+        }
+    }
+}
+
+impl PersistentBackend for DiskBackend {
+    fn insert<T: Serialize>(
+        &self,
+        key: &RawKey,
+        val: &T,
+        policy: &CachePolicy,
+    ) -> Result<(), CacheError> {
+        let payload = serde_json::to_vec(val).map_err(|err| persist_failed(key, err))?;
+        let header = EntryHeader {
+            format: DISK_FORMAT_VERSION,
+            key: key.stable_repr(),
+            type_name: type_name::<T>().to_string(),
+            checksum: checksum(&payload),
+            expires_at: policy.ttl.map(|ttl| now() + ttl.as_secs()),
+        };
+        let mut content = serde_json::to_vec(&header).map_err(|err| persist_failed(key, err))?;
+        content.push(b'\n');
+        content.extend_from_slice(&payload);
+
+        let file_name = file_name(key);
+        if content.len() as u64 > self.budget {
+            // The entry would evict everything else and itself.
+            self.remove_file(&file_name);
+            return Ok(());
+        }
+
+        let path = self.path(&file_name);
+        let tmp_path = path.with_extension(TMP_EXTENSION);
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp_path, &content))
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|err| persist_failed(key, err))?;
+
+        self.index
+            .lock()
+            .unwrap()
+            .insert(file_name, content.len() as u64);
+        self.evict_over_budget();
+
+        Ok(())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &RawKey) -> Result<T, CacheError> {
+        let file_name = file_name(key);
+        let result = self
+            .read(key, &file_name, type_name::<T>())
+            .and_then(|payload| {
+                serde_json::from_slice::<T>(&payload).map_err(|err| CacheError::InvalidEntry {
+                    key: key.to_string(),
+                    reason: format!("the value is not a valid '{}': {err}", type_name::<T>()),
+                })
+            });
+
+        match result {
+            Ok(value) => {
+                self.index.lock().unwrap().touch(&file_name);
+                // Keep the order of use for the next runs, it is restored from the modification times.
+                if let Err(err) = File::options()
+                    .write(true)
+                    .open(self.path(&file_name))
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+                {
+                    trace!("Failed to update the cache entry '{file_name}': {err}");
+                }
+                Ok(value)
+            }
+            Err(CacheError::InvalidEntry { key, reason }) => {
+                self.remove_file(&file_name);
+                Err(CacheError::InvalidEntry { key, reason })
+            }
+            Err(CacheError::NonexistentKey { key }) => {
+                self.remove_file(&file_name);
+                Err(CacheError::NonexistentKey { key })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn delete(&self, key: &RawKey) {
+        self.remove_file(&file_name(key));
+    }
+
+    fn contains(&self, key: &RawKey) -> bool {
+        self.index
+            .lock()
+            .unwrap()
+            .entries
+            .contains_key(&file_name(key))
+    }
+}
+
+fn scan_dir(dir: &Path) -> io::Result<Index> {
+    let mut files = Vec::new();
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(ENTRY_EXTENSION) => {}
+            Some(TMP_EXTENSION) => {
+                // Left by a write that was interrupted.
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            _ => continue,
+        }
+        let Some(file_name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        files.push((file_name.to_string(), metadata.len(), modified));
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut index = Index::default();
+    for (file_name, size, _) in files {
+        index.insert(file_name, size);
+    }
+
+    Ok(index)
+}
+
+fn file_name(key: &RawKey) -> String {
+    let mut hasher = FnvHasher::default();
+    hasher.write(key.stable_repr().as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+fn checksum(payload: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(payload);
+    hasher.finish()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn persist_failed(
+    key: &RawKey,
+    err: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> CacheError {
+    CacheError::PersistFailed {
+        key: key.to_string(),
+        source: Arc::from(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::key::CacheKey;
+    use std::time::Duration;
+
+    const CK_TEXT: CacheKey<String, u32> = CacheKey::new("text").with_namespace("test");
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("moss-cache-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_disk_backend_survives_reopening() {
+        let dir = TestDir::new("reopen");
+        let key = CK_TEXT.raw(1);
+        DiskBackend::new(&dir.0, 1024)
+            .insert(&key, &"value".to_string(), &CachePolicy::default())
+            .unwrap();
+
+        let backend = DiskBackend::new(&dir.0, 1024);
+        assert!(backend.contains(&key));
+        assert_eq!(backend.get::<String>(&key).unwrap(), "value");
+    }
+
+    #[test]
+    fn test_disk_backend_names_files_by_serialized_key() {
+        const CK_PAIR: CacheKey<String, (String, u32)> =
+            CacheKey::new("pair").with_namespace("test");
+
+        let key = CK_PAIR.raw(("a".to_string(), 1));
+        assert_eq!(key.stable_repr(), r#"test.pair:["a",1]"#);
+        assert_eq!(
+            file_name(&key),
+            file_name(&CK_PAIR.raw(("a".to_string(), 1)))
+        );
+        assert_ne!(
+            file_name(&key),
+            file_name(&CK_PAIR.raw(("a".to_string(), 2)))
+        );
+    }
+
+    #[test]
+    fn test_disk_backend_discards_corrupted_entry() {
+        let dir = TestDir::new("corrupted");
+        let key = CK_TEXT.raw(1);
+        let backend = DiskBackend::new(&dir.0, 1024);
+        backend
+            .insert(&key, &"value".to_string(), &CachePolicy::default())
+            .unwrap();
+
+        let path = backend.path(&file_name(&key));
+        let mut content = fs::read(&path).unwrap();
+        *content.last_mut().unwrap() = b'X';
+        fs::write(&path, content).unwrap();
+
+        assert!(matches!(
+            backend.get::<String>(&key),
+            Err(CacheError::InvalidEntry { .. })
+        ));
+        assert!(!backend.contains(&key));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_disk_backend_evicts_least_recently_used() {
+        let dir = TestDir::new("budget");
+        let backend = DiskBackend::new(&dir.0, 1024);
+        let value = "x".repeat(300);
+        backend
+            .insert(&CK_TEXT.raw(1), &value, &CachePolicy::default())
+            .unwrap();
+        backend
+            .insert(&CK_TEXT.raw(2), &value, &CachePolicy::default())
+            .unwrap();
+        backend.get::<String>(&CK_TEXT.raw(1)).unwrap();
+        backend
+            .insert(&CK_TEXT.raw(3), &value, &CachePolicy::default())
+            .unwrap();
+
+        assert!(backend.contains(&CK_TEXT.raw(1)));
+        assert!(!backend.contains(&CK_TEXT.raw(2)));
+        assert!(backend.contains(&CK_TEXT.raw(3)));
+        assert!(backend.size() <= 1024);
+    }
+
+    #[test]
+    fn test_disk_backend_expired_entry() {
+        let dir = TestDir::new("expired");
+        let key = CK_TEXT.raw(1);
+        let backend = DiskBackend::new(&dir.0, 1024);
+        let policy = CachePolicy {
+            ttl: Some(Duration::ZERO),
+            capacity: None,
+        };
+        backend.insert(&key, &"value".to_string(), &policy).unwrap();
+
+        assert!(matches!(
+            backend.get::<String>(&key),
+            Err(CacheError::NonexistentKey { .. })
+        ));
+    }
+}
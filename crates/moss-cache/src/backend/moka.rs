@@ -1,29 +1,83 @@
+use moka::notification::RemovalCause as MokaRemovalCause;
 use moka::sync::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder};
+use moka::Expiry;
 use std::any::Any;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+use crate::backend::{RemovalCause, RemovalListener};
+use crate::key::{CachePolicy, RawKey};
 use crate::{CacheBackend, CacheError, DynType};
 
+/// Expires the entries inserted with a TTL policy of their own.
+struct PolicyExpiry;
+
+impl Expiry<RawKey, DynType> for PolicyExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &RawKey,
+        value: &DynType,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        value.ttl
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &RawKey,
+        value: &DynType,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        value.ttl
+    }
+}
+
 pub struct MokaBackend {
-    cache: MokaCache<String, DynType>,
+    cache: MokaCache<RawKey, DynType>,
+    listener: Arc<RwLock<Option<RemovalListener>>>,
 }
 
 impl MokaBackend {
     pub fn new(max_capacity: u64, ttl: Duration) -> Self {
+        let listener: Arc<RwLock<Option<RemovalListener>>> = Arc::default();
+        let eviction_listener = {
+            let listener = Arc::clone(&listener);
+            move |key: Arc<RawKey>, _value: DynType, cause: MokaRemovalCause| {
+                let cause = match cause {
+                    MokaRemovalCause::Expired => RemovalCause::Expired,
+                    MokaRemovalCause::Size => RemovalCause::Size,
+                    MokaRemovalCause::Explicit | MokaRemovalCause::Replaced => return,
+                };
+                if let Some(listener) = listener.read().unwrap().as_ref() {
+                    listener(&key, cause);
+                }
+            }
+        };
+
         Self {
             cache: MokaCacheBuilder::new(max_capacity)
                 .time_to_live(ttl)
+                .expire_after(PolicyExpiry)
+                .eviction_listener(eviction_listener)
                 .build(),
+            listener,
         }
     }
+
+    /// Runs the pending maintenance of the cache, such as the removal of the expired entries,
+    /// which otherwise happens gradually as the cache is used.
+    pub fn run_pending_tasks(&self) {
+        self.cache.run_pending_tasks();
+    }
 }
 
 impl CacheBackend for MokaBackend {
-    fn insert<T: Any + Send + Sync>(&self, key: &str, val: T) {
-        self.cache.insert(key.to_string(), DynType::new(val));
+    fn insert<T: Any + Send + Sync>(&self, key: &RawKey, val: T, policy: &CachePolicy) {
+        self.cache
+            .insert(key.clone(), DynType::new(val).with_ttl(policy.ttl));
     }
-    fn get<T: Any + Send + Sync>(&self, key: &str) -> Result<Arc<T>, CacheError> {
+    fn get<T: Any + Send + Sync>(&self, key: &RawKey) -> Result<Arc<T>, CacheError> {
         match self.cache.get(key) {
             Some(dyn_item) => dyn_item.get_concrete::<T>(key),
             None => Err(CacheError::NonexistentKey {
@@ -31,11 +85,15 @@ impl CacheBackend for MokaBackend {
             }),
         }
     }
-    fn delete(&self, key: &str) {
+    fn delete(&self, key: &RawKey) {
         self.cache.remove(key);
     }
 
-    fn contains(&self, key: &str) -> bool {
+    fn contains(&self, key: &RawKey) -> bool {
         self.cache.contains_key(key)
     }
+
+    fn set_removal_listener(&self, listener: RemovalListener) {
+        *self.listener.write().unwrap() = Some(listener);
+    }
 }
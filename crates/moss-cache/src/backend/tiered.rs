@@ -0,0 +1,62 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::backend::{CacheBackend, PersistentBackend, RemovalListener};
+use crate::key::{CachePolicy, RawKey};
+use crate::CacheError;
+
+/// Keeps the values in memory and, for the entries loaded with
+/// [`CacheEntry::get_or_load_persistent`](crate::CacheEntry::get_or_load_persistent),
+/// on a persistent backend the memory tier falls back to.
+pub struct TieredBackend<M, D> {
+    memory: M,
+    persistent: Arc<D>,
+}
+
+impl<M, D> TieredBackend<M, D> {
+    pub fn new(memory: M, persistent: D) -> Self {
+        Self {
+            memory,
+            persistent: Arc::new(persistent),
+        }
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    pub fn persistent(&self) -> &Arc<D> {
+        &self.persistent
+    }
+}
+
+impl<M, D> CacheBackend for TieredBackend<M, D>
+where
+    M: CacheBackend,
+    D: PersistentBackend,
+{
+    fn insert<T: Any + Send + Sync>(&self, key: &RawKey, val: T, policy: &CachePolicy) {
+        self.memory.insert(key, val, policy);
+    }
+
+    fn get<T: Any + Send + Sync>(&self, key: &RawKey) -> Result<Arc<T>, CacheError> {
+        self.memory.get(key)
+    }
+
+    fn delete(&self, key: &RawKey) {
+        self.memory.delete(key);
+        self.persistent.delete(key);
+    }
+
+    fn contains(&self, key: &RawKey) -> bool {
+        self.memory.contains(key)
+    }
+
+    fn evict(&self, key: &RawKey) {
+        self.memory.evict(key);
+    }
+
+    fn set_removal_listener(&self, listener: RemovalListener) {
+        self.memory.set_removal_listener(listener);
+    }
+}
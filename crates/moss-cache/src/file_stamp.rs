@@ -0,0 +1,28 @@
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// The modification time and the size of a file, as a key part for the values read from
+/// the file. The stamp changes when the file does, so a changed file maps to a new entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+
+    /// The tag of the entries read from the file, see [`Cache::stamp_file`](crate::Cache::stamp_file).
+    pub fn tag(path: impl AsRef<Path>) -> String {
+        format!("file:{}", path.as_ref().display())
+    }
+}
@@ -0,0 +1,227 @@
+use serde::Serialize;
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A value that identifies an entry among the entries of the same key,
+/// compared and hashed by its structure.
+pub trait KeyPart: Any + Send + Sync + fmt::Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn dyn_eq(&self, other: &dyn KeyPart) -> bool;
+    fn dyn_hash(&self, state: &mut dyn Hasher);
+    /// The serialized part, which stays the same across runs and releases, unlike its
+    /// `Debug` output and its hash.
+    fn stable_repr(&self) -> String;
+}
+
+impl<T> KeyPart for T
+where
+    T: Any + Send + Sync + fmt::Debug + Hash + Eq + Serialize,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &dyn KeyPart) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<T>()
+            .is_some_and(|other| self == other)
+    }
+
+    fn dyn_hash(&self, mut state: &mut dyn Hasher) {
+        TypeId::of::<T>().hash(&mut state);
+        self.hash(&mut state);
+    }
+
+    fn stable_repr(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|err| {
+            warn!("Falling back to the debug output of the key part {self:?}: {err}");
+            format!("{self:?}")
+        })
+    }
+}
+
+/// How long the entries of a key live and how many of them are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// Entries expire once this time has passed since they were inserted,
+    /// in addition to the expiration configured for the backend.
+    pub ttl: Option<Duration>,
+    /// The maximum number of entries of the key, the least recently used are evicted first.
+    pub capacity: Option<usize>,
+}
+
+/// A key declared together with the type of its value.
+///
+/// The key can hold many entries, each identified by a part of the type `P`:
+///
+/// ```ignore
+/// const CK_TRANSLATIONS: CacheKey<Translations, (String, String)> =
+///     CacheKey::new("translations").with_namespace("locale").with_capacity(8);
+///
+/// cache.entry(&CK_TRANSLATIONS, (language, namespace)).get()
+/// ```
+pub struct CacheKey<T, P = ()> {
+    namespace: &'static str,
+    name: &'static str,
+    policy: CachePolicy,
+    _marker: PhantomData<fn() -> (T, P)>,
+}
+
+impl<T, P> CacheKey<T, P> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            namespace: "",
+            name,
+            policy: CachePolicy {
+                ttl: None,
+                capacity: None,
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn with_namespace(mut self, namespace: &'static str) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    pub const fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.policy.ttl = Some(ttl);
+        self
+    }
+
+    pub const fn with_capacity(mut self, capacity: usize) -> Self {
+        self.policy.capacity = Some(capacity);
+        self
+    }
+
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+
+    /// See [`RawKey::qualified_name`].
+    pub fn qualified_name(&self) -> String {
+        qualify(self.namespace, self.name)
+    }
+
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    pub(crate) fn raw(&self, part: P) -> RawKey
+    where
+        P: KeyPart,
+    {
+        RawKey {
+            namespace: self.namespace,
+            name: self.name,
+            part: Arc::new(part),
+        }
+    }
+}
+
+/// The untyped key the backends store entries under.
+#[derive(Clone)]
+pub struct RawKey {
+    namespace: &'static str,
+    name: &'static str,
+    part: Arc<dyn KeyPart>,
+}
+
+impl RawKey {
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The name the statistics of the key are grouped by: `namespace.name` for the keys
+    /// of a `CacheKey`, the key itself for untyped keys.
+    pub fn qualified_name(&self) -> String {
+        match self.part.as_any().downcast_ref::<String>() {
+            Some(key) if self.name.is_empty() => key.clone(),
+            _ => qualify(self.namespace, self.name),
+        }
+    }
+
+    /// Identifies the key across runs and releases, for the entries persisted on the disk.
+    pub fn stable_repr(&self) -> String {
+        format!(
+            "{}:{}",
+            qualify(self.namespace, self.name),
+            self.part.stable_repr()
+        )
+    }
+
+    /// Keys sharing the namespace and the name belong to the same `CacheKey`.
+    pub(crate) fn family(&self) -> (&'static str, &'static str) {
+        (self.namespace, self.name)
+    }
+}
+
+impl From<&str> for RawKey {
+    fn from(key: &str) -> Self {
+        Self {
+            namespace: "",
+            name: "",
+            part: Arc::new(key.to_string()),
+        }
+    }
+}
+
+impl PartialEq for RawKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.namespace == other.namespace
+            && self.name == other.name
+            && self.part.dyn_eq(&*other.part)
+    }
+}
+
+impl Eq for RawKey {}
+
+impl Hash for RawKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.namespace.hash(state);
+        self.name.hash(state);
+        self.part.dyn_hash(state);
+    }
+}
+
+impl fmt::Display for RawKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(key) = self.part.as_any().downcast_ref::<String>() {
+            if self.name.is_empty() {
+                return write!(f, "{key}");
+            }
+        }
+
+        write!(f, "{}", qualify(self.namespace, self.name))?;
+
+        if self.part.as_any().downcast_ref::<()>().is_none() {
+            write!(f, "{:?}", self.part)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RawKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn qualify(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{namespace}.{name}")
+    }
+}
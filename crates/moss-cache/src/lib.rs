@@ -1,11 +1,36 @@
 pub mod backend;
+pub mod file_stamp;
+pub mod key;
+pub mod stats;
+
+mod tags;
+
+#[macro_use]
+extern crate tracing;
 
 use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::{de::DeserializeOwned, Serialize};
 use std::any::{type_name, Any};
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use thiserror::Error;
 
-use crate::backend::CacheBackend;
+use crate::backend::tiered::TieredBackend;
+use crate::backend::{CacheBackend, PersistentBackend, RemovalListener};
+use crate::stats::{Event, Metrics};
+use crate::tags::TagIndex;
+
+pub use crate::backend::RemovalCause;
+pub use crate::file_stamp::FileStamp;
+pub use crate::key::{CacheKey, CachePolicy, KeyPart, RawKey};
+pub use crate::stats::CacheStats;
 
 #[derive(Debug, Error)]
 pub enum CacheError {
@@ -17,12 +42,35 @@ pub enum CacheError {
 
     #[error("The key '{key}' does not exist")]
     NonexistentKey { key: String },
+
+    #[error(
+        "The value of key '{key}' is retrieved asynchronously and must be read with `get_async`"
+    )]
+    AsyncRetriever { key: String },
+
+    #[error("Failed to load the value of key '{key}': {source}")]
+    LoadFailed {
+        key: String,
+        #[source]
+        source: Arc<dyn StdError + Send + Sync>,
+    },
+
+    #[error("Failed to persist the value of key '{key}': {source}")]
+    PersistFailed {
+        key: String,
+        #[source]
+        source: Arc<dyn StdError + Send + Sync>,
+    },
+
+    #[error("The persisted value of key '{key}' is invalid: {reason}")]
+    InvalidEntry { key: String, reason: String },
 }
 
 #[derive(Clone, Debug)]
 struct DynType {
     content: Arc<dyn Any + Send + Sync>,
     type_name: &'static str,
+    ttl: Option<Duration>,
 }
 
 impl DynType {
@@ -33,10 +81,16 @@ impl DynType {
         Self {
             content: Arc::new(content),
             type_name: type_name::<T>(),
+            ttl: None,
         }
     }
 
-    fn get_concrete<T: Any + Send + Sync>(&self, key: &str) -> Result<Arc<T>, CacheError> {
+    fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn get_concrete<T: Any + Send + Sync>(&self, key: &RawKey) -> Result<Arc<T>, CacheError> {
         match self.content.clone().downcast::<T>() {
             Ok(result) => Ok(result),
             Err(_) => Err(CacheError::TypeMismatch {
@@ -49,8 +103,17 @@ impl DynType {
 
 type RetrieverCallback = Arc<dyn Fn() -> DynType + Send + Sync>;
 
+type LoadResult = Result<DynType, Arc<dyn StdError + Send + Sync>>;
+type AsyncRetrieverCallback = Arc<dyn Fn() -> BoxFuture<'static, LoadResult> + Send + Sync>;
+
+#[derive(Clone)]
+enum Retriever {
+    Sync(RetrieverCallback),
+    Async(AsyncRetrieverCallback),
+}
+
 struct Retrievers {
-    funcs: DashMap<String, RetrieverCallback>,
+    funcs: DashMap<RawKey, Retriever>,
 }
 
 impl Retrievers {
@@ -60,11 +123,11 @@ impl Retrievers {
         }
     }
 
-    fn register(&self, key: &str, updater: RetrieverCallback) {
-        self.funcs.insert(key.to_string(), updater);
+    fn register(&self, key: RawKey, updater: Retriever) {
+        self.funcs.insert(key, updater);
     }
 
-    fn deregister(&self, key: &str) -> Result<(), CacheError> {
+    fn deregister(&self, key: &RawKey) -> Result<(), CacheError> {
         match self.funcs.remove(key) {
             Some(_) => Ok(()),
             None => Err(CacheError::NonexistentKey {
@@ -73,7 +136,7 @@ impl Retrievers {
         }
     }
 
-    fn get(&self, key: &str) -> Result<RetrieverCallback, CacheError> {
+    fn get(&self, key: &RawKey) -> Result<Retriever, CacheError> {
         self.funcs
             .get(key)
             .map(|f| f.value().clone())
@@ -83,16 +146,77 @@ impl Retrievers {
     }
 }
 
+fn load_future<T, Fut, E>(future: Fut) -> BoxFuture<'static, LoadResult>
+where
+    T: Any + Send + Sync,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    async move {
+        future
+            .await
+            .map(DynType::new)
+            .map_err(|err| Arc::from(err.into()))
+    }
+    .boxed()
+}
+
+/// The state shared with the removal listener of the backend.
+#[derive(Default)]
+struct Observers {
+    metrics: Metrics,
+    tags: TagIndex,
+    listeners: RwLock<Vec<RemovalListener>>,
+    /// The entries of the keys with a limited capacity, from the least to the most recently used.
+    partitions: DashMap<(&'static str, &'static str), VecDeque<RawKey>>,
+}
+
+impl Observers {
+    fn notify_removal(&self, key: &RawKey, cause: RemovalCause) {
+        // The entries the backend removes on its own leave their partition as well, so that
+        // they are not evicted, and reported, once more.
+        self.forget_partition_entry(key);
+        if cause != RemovalCause::Explicit {
+            self.metrics.record(key, Event::Eviction);
+        }
+        self.tags.untag(key);
+
+        for listener in self.listeners.read().unwrap().iter() {
+            listener(key, cause);
+        }
+    }
+
+    fn forget_partition_entry(&self, key: &RawKey) {
+        if let Some(mut partition) = self.partitions.get_mut(&key.family()) {
+            partition.retain(|entry| entry != key);
+        }
+    }
+}
+
 pub struct Cache<B: CacheBackend> {
     backend: B,
     retrievers: Retrievers,
+    /// Loads that are in progress, shared by all the callers waiting for the same key.
+    inflight: DashMap<RawKey, Shared<BoxFuture<'static, LoadResult>>>,
+    observers: Arc<Observers>,
+    /// The last seen stamps of the files passed to `stamp_file`.
+    file_stamps: DashMap<PathBuf, FileStamp>,
 }
 
 impl<B: CacheBackend> Cache<B> {
     pub fn new(backend: B) -> Self {
+        let observers = Arc::new(Observers::default());
+        backend.set_removal_listener({
+            let observers = Arc::clone(&observers);
+            Arc::new(move |key, cause| observers.notify_removal(key, cause))
+        });
+
         Self {
             backend,
             retrievers: Retrievers::new(),
+            inflight: DashMap::new(),
+            observers,
+            file_stamps: DashMap::new(),
         }
     }
 }
@@ -106,60 +230,177 @@ where
         key: &str,
         updater: impl Fn() -> T + Send + Sync + 'static,
     ) -> Arc<T> {
+        let key = RawKey::from(key);
         let initial_val = updater();
         let retriever = {
             let updater = Arc::new(updater);
             Arc::new(move || DynType::new(updater()))
         };
-        self.retrievers.register(key, retriever);
-        self.backend.insert(key, initial_val);
+        self.retrievers
+            .register(key.clone(), Retriever::Sync(retriever));
         self.backend
-            .get::<T>(key)
+            .insert(&key, initial_val, &CachePolicy::default());
+        self.backend
+            .get::<T>(&key)
             .expect("Just inserted value must be retrievable")
     }
 
+    /// Registers an async loader for the key. Unlike `register`, the value is not loaded
+    /// until it is requested with `get_async`.
+    pub fn register_async<T, F, Fut, E>(&self, key: &str, loader: F)
+    where
+        T: Any + Send + Sync,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let retriever = Arc::new(move || load_future(loader()));
+        self.retrievers
+            .register(RawKey::from(key), Retriever::Async(retriever));
+    }
+
     pub fn deregister(&self, key: &str) -> Result<(), CacheError> {
-        self.backend.delete(key);
-        self.retrievers.deregister(key)
+        let key = RawKey::from(key);
+        self.delete_raw(&key);
+        self.retrievers.deregister(&key)
+    }
+
+    /// Returns a handle to the entry of a typed key.
+    pub fn entry<T, P: KeyPart>(&self, key: &CacheKey<T, P>, part: P) -> CacheEntry<'_, B, T> {
+        CacheEntry {
+            cache: self,
+            key: key.raw(part),
+            policy: *key.policy(),
+            tags: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Calls the listener for every entry removed from the cache, with the cause of the removal.
+    pub fn on_removal(&self, listener: impl Fn(&RawKey, RemovalCause) + Send + Sync + 'static) {
+        self.observers
+            .listeners
+            .write()
+            .unwrap()
+            .push(Arc::new(listener));
+    }
+
+    /// Deletes all the entries tagged with the tag, returning how many there were.
+    pub fn invalidate_tag(&self, tag: &str) -> usize {
+        let keys = self.observers.tags.take(tag);
+        for key in &keys {
+            self.delete_raw(key);
+        }
+        keys.len()
+    }
+
+    /// Waits for the loads of the keys of the namespace that are in progress, so that the
+    /// values they persist are written before the cache goes away.
+    pub async fn wait_for_loads(&self, namespace: &str) {
+        let loads = self
+            .inflight
+            .iter()
+            .filter(|load| load.key().namespace() == namespace)
+            .map(|load| load.value().clone())
+            .collect::<Vec<_>>();
+
+        // The failures are reported to the callers waiting for the loads.
+        futures::future::join_all(loads).await;
+    }
+
+    /// Returns the current stamp of the file. If the file changed since the previous call,
+    /// the entries tagged with the [`FileStamp::tag`] of the file are invalidated first.
+    pub fn stamp_file(&self, path: &Path) -> io::Result<FileStamp> {
+        let stamp = FileStamp::read(path);
+        let previous = match &stamp {
+            Ok(stamp) => self.file_stamps.insert(path.to_path_buf(), *stamp),
+            Err(_) => self.file_stamps.remove(path).map(|(_, stamp)| stamp),
+        };
+
+        if previous.is_some() && previous != stamp.as_ref().ok().copied() {
+            let count = self.invalidate_tag(&FileStamp::tag(path));
+            trace!(
+                "The file '{}' changed, invalidated {count} entries",
+                path.display()
+            );
+        }
+
+        stamp
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.observers.metrics.total()
+    }
+
+    pub fn key_stats<T, P>(&self, key: &CacheKey<T, P>) -> CacheStats {
+        self.observers.metrics.get(&key.qualified_name())
+    }
+
+    /// The statistics of every key that was used, by the qualified name of the key.
+    pub fn stats_by_key(&self) -> Vec<(String, CacheStats)> {
+        self.observers.metrics.by_key()
     }
 
     pub fn insert<T>(&self, key: &str, val: T)
     where
         T: Any + Send + Sync + Clone,
     {
-        self.backend.insert(key, val);
+        self.insert_raw(&RawKey::from(key), val, &CachePolicy::default());
     }
 
     pub fn get<T>(&self, key: &str) -> Result<Arc<T>, CacheError>
     where
         T: Any + Send + Sync + Clone,
     {
-        match self.backend.get::<T>(key) {
-            Ok(val) => Ok(val),
-            Err(CacheError::TypeMismatch { key, type_name }) => {
-                Err(CacheError::TypeMismatch { key, type_name })
+        self.get_raw(&RawKey::from(key), &CachePolicy::default())
+    }
+
+    /// Returns the value of the key, running its retriever on a miss.
+    ///
+    /// Concurrent calls for a key that is being loaded by an async retriever
+    /// wait for the same load instead of starting their own.
+    pub async fn get_async<T>(&self, key: &str) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        let key = RawKey::from(key);
+        match self.lookup::<T>(&key, &CachePolicy::default()) {
+            Err(CacheError::NonexistentKey { .. }) => {}
+            result => return result,
+        }
+
+        match self.retrievers.get(&key)? {
+            Retriever::Sync(retriever) => {
+                self.retrieve::<T>(&key, &CachePolicy::default(), retriever)
             }
-            Err(CacheError::NonexistentKey { key: _ }) => {
-                // The value is missing in the backend, try to retrieve it using the retriever
-                let retriever = self.retrievers.get(key)?;
-                let new_val_dyn = retriever();
-
-                // Here we are confident that if the retriever returns a DynType, it is the correct type we expect.
-                let new_val = new_val_dyn
-                    .get_concrete::<T>(key)
-                    .expect("The retriever must return the correct type");
-                let extracted = match Arc::try_unwrap(new_val) {
-                    Ok(val) => val,
-                    Err(arc_val) => (*arc_val).clone(),
-                };
-                self.backend.insert(key, extracted);
-                self.backend.get::<T>(key)
+            Retriever::Async(retriever) => {
+                self.load::<T>(&key, &CachePolicy::default(), move || retriever())
+                    .await
             }
         }
     }
 
+    /// Returns the value of the key, loading it with the given loader on a miss.
+    ///
+    /// Concurrent calls for the same key wait for the load started by the first
+    /// of them, the loaders of the others are not called.
+    pub async fn get_or_load<T, F, Fut, E>(
+        &self,
+        key: &str,
+        loader: F,
+    ) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        self.get_or_load_raw(&RawKey::from(key), &CachePolicy::default(), loader)
+            .await
+    }
+
     pub fn delete(&self, key: &str) {
-        self.backend.delete(key);
+        self.delete_raw(&RawKey::from(key));
     }
 
     pub fn take<T>(&self, key: &str) -> Result<T, CacheError>
@@ -175,7 +416,276 @@ where
     }
 
     pub fn contains(&self, key: &str) -> bool {
-        self.backend.contains(key)
+        self.backend.contains(&RawKey::from(key))
+    }
+
+    fn insert_raw<T>(&self, key: &RawKey, val: T, policy: &CachePolicy)
+    where
+        T: Any + Send + Sync,
+    {
+        self.backend.insert(key, val, policy);
+        self.touch(key, policy);
+    }
+
+    fn get_raw<T>(&self, key: &RawKey, policy: &CachePolicy) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        match self.lookup::<T>(key, policy) {
+            Err(CacheError::NonexistentKey { key: _ }) => {
+                // The value is missing in the backend, try to retrieve it using the retriever
+                let Retriever::Sync(retriever) = self.retrievers.get(key)? else {
+                    return Err(CacheError::AsyncRetriever {
+                        key: key.to_string(),
+                    });
+                };
+                self.retrieve::<T>(key, policy, retriever)
+            }
+            result => result,
+        }
+    }
+
+    /// Gets the value from the backend, recording a hit or a miss.
+    fn lookup<T>(&self, key: &RawKey, policy: &CachePolicy) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync,
+    {
+        let result = self.backend.get::<T>(key);
+        match &result {
+            Ok(_) => {
+                self.observers.metrics.record(key, Event::Hit);
+                self.touch(key, policy);
+            }
+            Err(CacheError::NonexistentKey { .. }) => {
+                self.observers.metrics.record(key, Event::Miss);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn retrieve<T>(
+        &self,
+        key: &RawKey,
+        policy: &CachePolicy,
+        retriever: RetrieverCallback,
+    ) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        self.observers.metrics.record(key, Event::Load);
+        let new_val_dyn = retriever();
+
+        // Here we are confident that if the retriever returns a DynType, it is the correct type we expect.
+        let new_val = new_val_dyn
+            .get_concrete::<T>(key)
+            .expect("The retriever must return the correct type");
+        let extracted = match Arc::try_unwrap(new_val) {
+            Ok(val) => val,
+            Err(arc_val) => (*arc_val).clone(),
+        };
+        self.insert_raw(key, extracted, policy);
+        self.backend.get::<T>(key)
+    }
+
+    async fn get_or_load_raw<T, F, Fut, E>(
+        &self,
+        key: &RawKey,
+        policy: &CachePolicy,
+        loader: F,
+    ) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        match self.lookup::<T>(key, policy) {
+            Err(CacheError::NonexistentKey { .. }) => {}
+            result => return result,
+        }
+
+        self.load::<T>(key, policy, move || load_future(loader()))
+            .await
+    }
+
+    async fn load<T>(
+        &self,
+        key: &RawKey,
+        policy: &CachePolicy,
+        start: impl FnOnce() -> BoxFuture<'static, LoadResult>,
+    ) -> Result<Arc<T>, CacheError>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        let future = self
+            .inflight
+            .entry(key.clone())
+            .or_insert_with(|| {
+                self.observers.metrics.record(key, Event::Load);
+                start().shared()
+            })
+            .clone();
+
+        let result = future.clone().await;
+
+        // Only the callers of the load that is still registered store its value,
+        // so that a stale load cannot overwrite the value of a newer one.
+        let is_current = self
+            .inflight
+            .get(key)
+            .is_some_and(|inflight| inflight.ptr_eq(&future));
+
+        let value = match result {
+            Ok(value) => value.get_concrete::<T>(key),
+            Err(source) => Err(CacheError::LoadFailed {
+                key: key.to_string(),
+                source,
+            }),
+        };
+
+        if is_current {
+            match &value {
+                Ok(value) => self.insert_raw(key, (**value).clone(), policy),
+                Err(_) => self.observers.metrics.record(key, Event::LoadFailure),
+            }
+            self.inflight
+                .remove_if(key, |_, inflight| inflight.ptr_eq(&future));
+        }
+
+        value
+    }
+
+    fn delete_raw(&self, key: &RawKey) {
+        let existed = self.backend.contains(key);
+        self.backend.delete(key);
+        if existed {
+            self.observers.notify_removal(key, RemovalCause::Explicit);
+        } else {
+            self.observers.forget_partition_entry(key);
+        }
+    }
+
+    /// Marks the entry as the most recently used one of its key and evicts
+    /// the least recently used entries beyond the capacity of the key.
+    fn touch(&self, key: &RawKey, policy: &CachePolicy) {
+        let Some(capacity) = policy.capacity else {
+            return;
+        };
+
+        let evicted = {
+            let mut partition = self.observers.partitions.entry(key.family()).or_default();
+            partition.retain(|entry| entry != key);
+            partition.push_back(key.clone());
+
+            let excess = partition.len().saturating_sub(capacity);
+            partition.drain(..excess).collect::<Vec<_>>()
+        };
+
+        for key in evicted {
+            // An entry the backend expired in the meantime was reported then.
+            if self.backend.contains(&key) {
+                self.backend.evict(&key);
+                self.observers.notify_removal(&key, RemovalCause::Size);
+            }
+        }
+    }
+}
+
+/// A handle to an entry of a typed key, see [`Cache::entry`].
+pub struct CacheEntry<'a, B: CacheBackend, T> {
+    cache: &'a Cache<B>,
+    key: RawKey,
+    policy: CachePolicy,
+    tags: Vec<String>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<B, T> CacheEntry<'_, B, T>
+where
+    B: CacheBackend,
+    T: Any + Send + Sync + Clone,
+{
+    pub fn key(&self) -> &RawKey {
+        &self.key
+    }
+
+    /// Tags the entry when its value is stored, see [`Cache::invalidate_tag`].
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn insert(&self, val: T) {
+        self.cache.insert_raw(&self.key, val, &self.policy);
+        self.cache.observers.tags.tag(&self.key, &self.tags);
+    }
+
+    pub fn get(&self) -> Result<Arc<T>, CacheError> {
+        self.cache.get_raw(&self.key, &self.policy)
+    }
+
+    /// Returns the value of the entry, loading it with the given loader on a miss.
+    /// Concurrent loads of the same entry are deduplicated.
+    pub async fn get_or_load<F, Fut, E>(&self, loader: F) -> Result<Arc<T>, CacheError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let value = self
+            .cache
+            .get_or_load_raw(&self.key, &self.policy, loader)
+            .await?;
+        self.cache.observers.tags.tag(&self.key, &self.tags);
+        Ok(value)
+    }
+
+    pub fn delete(&self) {
+        self.cache.delete_raw(&self.key);
+    }
+
+    pub fn contains(&self) -> bool {
+        self.cache.backend.contains(&self.key)
+    }
+}
+
+impl<M, D, T> CacheEntry<'_, TieredBackend<M, D>, T>
+where
+    M: CacheBackend,
+    D: PersistentBackend + Send + Sync + 'static,
+    T: Any + Send + Sync + Clone + Serialize + DeserializeOwned,
+{
+    /// Like `get_or_load`, but on a miss the value is looked up on the persistent tier
+    /// before calling the loader, and the loaded value is stored there as well.
+    pub async fn get_or_load_persistent<F, Fut, E>(&self, loader: F) -> Result<Arc<T>, CacheError>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let persistent = Arc::clone(self.cache.backend.persistent());
+        let (key, policy) = (self.key.clone(), self.policy);
+
+        let value = self
+            .cache
+            .get_or_load_raw(&self.key, &self.policy, move || async move {
+                match persistent.get::<T>(&key) {
+                    Ok(value) => return Ok(value),
+                    Err(CacheError::NonexistentKey { .. }) => {}
+                    Err(err) => warn!("Discarding the persisted value: {err}"),
+                }
+
+                let value = loader().await.map_err(Into::into)?;
+                if let Err(err) = persistent.insert(&key, &value, &policy) {
+                    warn!("{err}");
+                }
+
+                Ok::<_, Box<dyn StdError + Send + Sync>>(value)
+            })
+            .await?;
+        self.cache.observers.tags.tag(&self.key, &self.tags);
+        Ok(value)
     }
 }
 
@@ -188,9 +698,10 @@ mod test {
     };
 
     struct MockBackend {
-        cache: DashMap<String, DynType>,
-        entry_timestamp: DashMap<String, Instant>,
+        cache: DashMap<RawKey, DynType>,
+        entry_timestamp: DashMap<RawKey, Instant>,
         ttl: Duration,
+        listener: RwLock<Option<RemovalListener>>,
     }
 
     impl MockBackend {
@@ -199,43 +710,57 @@ mod test {
                 cache: DashMap::new(),
                 entry_timestamp: DashMap::new(),
                 ttl,
+                listener: RwLock::new(None),
             }
         }
+
+        fn is_expired(&self, key: &RawKey) -> bool {
+            let Some(entry_time) = self.entry_timestamp.get(key) else {
+                return true;
+            };
+            let ttl = self
+                .cache
+                .get(key)
+                .and_then(|item| item.ttl)
+                .map_or(self.ttl, |ttl| ttl.min(self.ttl));
+            Instant::now() - *entry_time.value() > ttl
+        }
     }
 
     impl CacheBackend for MockBackend {
-        fn insert<T: Any + Send + Sync>(&self, key: &str, val: T) {
-            self.cache.insert(key.to_string(), DynType::new(val));
-            self.entry_timestamp.insert(key.to_string(), Instant::now());
+        fn insert<T: Any + Send + Sync>(&self, key: &RawKey, val: T, policy: &CachePolicy) {
+            self.cache
+                .insert(key.clone(), DynType::new(val).with_ttl(policy.ttl));
+            self.entry_timestamp.insert(key.clone(), Instant::now());
         }
 
-        fn get<T: Any + Send + Sync>(&self, key: &str) -> Result<Arc<T>, CacheError> {
-            match self.entry_timestamp.get(key) {
-                Some(entry_time) => {
-                    if Instant::now() - *entry_time.value() > self.ttl {
-                        Err(CacheError::NonexistentKey {
-                            key: key.to_string(),
-                        })
-                    } else {
-                        self.cache.get(key).unwrap().get_concrete::<T>(key)
+        fn get<T: Any + Send + Sync>(&self, key: &RawKey) -> Result<Arc<T>, CacheError> {
+            if self.is_expired(key) {
+                if self.cache.remove(key).is_some() {
+                    self.entry_timestamp.remove(key);
+                    if let Some(listener) = self.listener.read().unwrap().as_ref() {
+                        listener(key, RemovalCause::Expired);
                     }
                 }
-                None => Err(CacheError::NonexistentKey {
+                Err(CacheError::NonexistentKey {
                     key: key.to_string(),
-                }),
+                })
+            } else {
+                self.cache.get(key).unwrap().get_concrete::<T>(key)
             }
         }
 
-        fn delete(&self, key: &str) {
+        fn delete(&self, key: &RawKey) {
             self.cache.remove(key);
             self.entry_timestamp.remove(key);
         }
 
-        fn contains(&self, key: &str) -> bool {
-            match self.entry_timestamp.get(key) {
-                Some(entry_time) => Instant::now() - *entry_time.value() < self.ttl,
-                None => false,
-            }
+        fn contains(&self, key: &RawKey) -> bool {
+            !self.is_expired(key)
+        }
+
+        fn set_removal_listener(&self, listener: RemovalListener) {
+            *self.listener.write().unwrap() = Some(listener);
         }
     }
 
@@ -392,4 +917,373 @@ mod test {
         assert_eq!(*cache.get::<i32>("i32").unwrap(), 32);
         assert!(cache.contains("i32"));
     }
+
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.0 {
+                return std::task::Poll::Ready(());
+            }
+
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            std::task::Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_cache_register_async_is_lazy() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let loads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&loads);
+        cache.register_async::<i32, _, _, CacheError>("i32", move || {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Ok(32) }
+        });
+
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 0);
+        assert!(!cache.contains("i32"));
+        assert_eq!(
+            *futures::executor::block_on(cache.get_async::<i32>("i32")).unwrap(),
+            32
+        );
+        assert!(cache.contains("i32"));
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_cache_get_on_async_retriever() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        cache.register_async::<i32, _, _, CacheError>("i32", || async { Ok(32) });
+
+        assert!(matches!(
+            cache.get::<i32>("i32"),
+            Err(CacheError::AsyncRetriever { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cache_get_async_single_flight() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let loads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&loads);
+        cache.register_async::<i32, _, _, CacheError>("i32", move || {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async {
+                YieldNow(false).await;
+                Ok(32)
+            }
+        });
+
+        let (first, second) = futures::executor::block_on(async {
+            futures::join!(cache.get_async::<i32>("i32"), cache.get_async::<i32>("i32"))
+        });
+
+        assert_eq!(*first.unwrap(), 32);
+        assert_eq!(*second.unwrap(), 32);
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_cache_get_or_load_failure_is_not_cached() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+
+        let result =
+            futures::executor::block_on(cache.get_or_load::<i32, _, _, _>("i32", || async {
+                Err(CacheError::NonexistentKey {
+                    key: "source".to_string(),
+                })
+            }));
+        assert!(matches!(result, Err(CacheError::LoadFailed { .. })));
+        assert!(!cache.contains("i32"));
+
+        let result = futures::executor::block_on(
+            cache.get_or_load::<i32, _, _, CacheError>("i32", || async { Ok(32) }),
+        );
+        assert_eq!(*result.unwrap(), 32);
+        assert!(cache.contains("i32"));
+    }
+
+    const CK_NUMBER: CacheKey<i32> = CacheKey::new("number").with_namespace("test");
+    const CK_PAIR: CacheKey<String, (String, u32)> = CacheKey::new("pair").with_namespace("test");
+
+    #[test]
+    fn test_cache_entry_insert_and_get() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        cache.entry(&CK_NUMBER, ()).insert(32);
+
+        assert_eq!(*cache.entry(&CK_NUMBER, ()).get().unwrap(), 32);
+        assert_eq!(cache.entry(&CK_NUMBER, ()).key().to_string(), "test.number");
+        assert!(!cache.contains("number"));
+    }
+
+    #[test]
+    fn test_cache_entry_composite_parts() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        cache
+            .entry(&CK_PAIR, ("en".to_string(), 1))
+            .insert("first".to_string());
+        cache
+            .entry(&CK_PAIR, ("en".to_string(), 2))
+            .insert("second".to_string());
+
+        assert_eq!(
+            *cache.entry(&CK_PAIR, ("en".to_string(), 1)).get().unwrap(),
+            "first"
+        );
+        assert_eq!(
+            *cache.entry(&CK_PAIR, ("en".to_string(), 2)).get().unwrap(),
+            "second"
+        );
+        assert!(!cache.entry(&CK_PAIR, ("de".to_string(), 1)).contains());
+    }
+
+    #[test]
+    fn test_cache_entry_capacity_evicts_least_recently_used() {
+        const CK_LIMITED: CacheKey<i32, u32> = CacheKey::new("limited").with_capacity(2);
+
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        cache.entry(&CK_LIMITED, 1).insert(1);
+        cache.entry(&CK_LIMITED, 2).insert(2);
+        cache.entry(&CK_LIMITED, 1).get().unwrap();
+        cache.entry(&CK_LIMITED, 3).insert(3);
+
+        assert!(cache.entry(&CK_LIMITED, 1).contains());
+        assert!(!cache.entry(&CK_LIMITED, 2).contains());
+        assert!(cache.entry(&CK_LIMITED, 3).contains());
+    }
+
+    #[test]
+    fn test_cache_entry_ttl() {
+        const CK_SHORT: CacheKey<i32> = CacheKey::new("short").with_ttl(Duration::from_millis(50));
+
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        cache.entry(&CK_SHORT, ()).insert(32);
+        cache.entry(&CK_NUMBER, ()).insert(32);
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(!cache.entry(&CK_SHORT, ()).contains());
+        assert!(cache.entry(&CK_NUMBER, ()).contains());
+    }
+
+    #[test]
+    fn test_cache_tiered_get_or_load_persistent() {
+        use crate::backend::disk::DiskBackend;
+
+        let dir = std::env::temp_dir().join(format!("moss-cache-tiered-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let new_cache = || {
+            Cache::new(TieredBackend::new(
+                MockBackend::new(Duration::from_millis(1000)),
+                DiskBackend::new(&dir, 1024),
+            ))
+        };
+
+        let cache = new_cache();
+        let result = futures::executor::block_on(
+            cache
+                .entry(&CK_PAIR, ("en".to_string(), 1))
+                .get_or_load_persistent(|| async { Ok::<_, CacheError>("loaded".to_string()) }),
+        );
+        assert_eq!(*result.unwrap(), "loaded");
+
+        // A new cache over the same directory starts warm.
+        let cache = new_cache();
+        let result = futures::executor::block_on(
+            cache
+                .entry(&CK_PAIR, ("en".to_string(), 1))
+                .get_or_load_persistent(|| async {
+                    Err::<String, _>(CacheError::NonexistentKey {
+                        key: "source".to_string(),
+                    })
+                }),
+        );
+        assert_eq!(*result.unwrap(), "loaded");
+        assert!(cache.entry(&CK_PAIR, ("en".to_string(), 1)).contains());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cache_wait_for_loads() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let (sender, receiver) = futures::channel::oneshot::channel::<i32>();
+        let loaded = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let entry = cache.entry(&CK_NUMBER, ());
+        futures::executor::block_on(async {
+            let load = entry.get_or_load({
+                let loaded = Arc::clone(&loaded);
+                move || async move {
+                    let value = receiver.await.map_err(|_| CacheError::NonexistentKey {
+                        key: "source".to_string(),
+                    })?;
+                    loaded.store(true, std::sync::atomic::Ordering::SeqCst);
+                    Ok::<_, CacheError>(value)
+                }
+            });
+            let wait = async {
+                cache.wait_for_loads("other").await;
+                assert!(!loaded.load(std::sync::atomic::Ordering::SeqCst));
+
+                cache.wait_for_loads("test").await;
+                assert!(loaded.load(std::sync::atomic::Ordering::SeqCst));
+            };
+            let send = async { sender.send(42).unwrap() };
+
+            let (value, _, _) = futures::join!(load, wait, send);
+            assert_eq!(*value.unwrap(), 42);
+        });
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let load = || async { Ok::<_, CacheError>(32) };
+        futures::executor::block_on(cache.entry(&CK_NUMBER, ()).get_or_load(load)).unwrap();
+        futures::executor::block_on(cache.entry(&CK_NUMBER, ()).get_or_load(load)).unwrap();
+        cache.insert("i32", 32i32);
+        cache.get::<i32>("i32").unwrap();
+
+        assert_eq!(
+            cache.key_stats(&CK_NUMBER),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                loads: 1,
+                load_failures: 0,
+                evictions: 0,
+            }
+        );
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(
+            cache
+                .stats_by_key()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            vec!["i32".to_string(), "test.number".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_cache_removal_listener_causes() {
+        const CK_LIMITED: CacheKey<i32, u32> = CacheKey::new("limited").with_capacity(1);
+        const CK_SHORT: CacheKey<i32> = CacheKey::new("short").with_ttl(Duration::from_millis(50));
+
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let removals = Arc::new(std::sync::Mutex::new(Vec::new()));
+        cache.on_removal({
+            let removals = Arc::clone(&removals);
+            move |key, cause| removals.lock().unwrap().push((key.to_string(), cause))
+        });
+
+        cache.insert("i32", 32i32);
+        cache.delete("i32");
+        cache.delete("i32");
+        cache.entry(&CK_LIMITED, 1).insert(1);
+        cache.entry(&CK_LIMITED, 2).insert(2);
+        cache.entry(&CK_SHORT, ()).insert(3);
+        thread::sleep(Duration::from_millis(100));
+        assert!(cache.entry(&CK_SHORT, ()).get().is_err());
+
+        assert_eq!(
+            *removals.lock().unwrap(),
+            vec![
+                ("i32".to_string(), RemovalCause::Explicit),
+                ("limited1".to_string(), RemovalCause::Size),
+                ("short".to_string(), RemovalCause::Expired),
+            ]
+        );
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn test_cache_expired_entry_is_not_evicted_again() {
+        const CK_LIMITED: CacheKey<i32, u32> = CacheKey::new("limited")
+            .with_capacity(1)
+            .with_ttl(Duration::from_millis(50));
+
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let removals = Arc::new(std::sync::Mutex::new(Vec::new()));
+        cache.on_removal({
+            let removals = Arc::clone(&removals);
+            move |key, cause| removals.lock().unwrap().push((key.to_string(), cause))
+        });
+
+        cache.entry(&CK_LIMITED, 1).insert(1);
+        thread::sleep(Duration::from_millis(100));
+        assert!(cache.entry(&CK_LIMITED, 1).get().is_err());
+        cache.entry(&CK_LIMITED, 2).insert(2);
+
+        assert_eq!(
+            *removals.lock().unwrap(),
+            vec![("limited1".to_string(), RemovalCause::Expired)]
+        );
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.entry(&CK_LIMITED, 2).contains());
+    }
+
+    #[test]
+    fn test_cache_invalidate_tag() {
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        for (language, version) in [("de", 1), ("de", 2), ("en", 1)] {
+            cache
+                .entry(&CK_PAIR, (language.to_string(), version))
+                .with_tag(format!("locale:{language}"))
+                .insert(format!("{language}-{version}"));
+        }
+
+        assert_eq!(cache.invalidate_tag("locale:de"), 2);
+        assert!(!cache.entry(&CK_PAIR, ("de".to_string(), 1)).contains());
+        assert!(!cache.entry(&CK_PAIR, ("de".to_string(), 2)).contains());
+        assert!(cache.entry(&CK_PAIR, ("en".to_string(), 1)).contains());
+        assert_eq!(cache.invalidate_tag("locale:de"), 0);
+    }
+
+    #[test]
+    fn test_cache_stamp_file_invalidates_changed_file() {
+        const CK_FILE: CacheKey<String, (String, FileStamp)> = CacheKey::new("file");
+
+        let path = std::env::temp_dir().join(format!("moss-cache-stamp-{}", std::process::id()));
+        let source = path.to_string_lossy().to_string();
+        std::fs::write(&path, "first").unwrap();
+
+        let mock_backend = MockBackend::new(Duration::from_millis(1000));
+        let cache = MockCache::new(mock_backend);
+        let first = cache.stamp_file(&path).unwrap();
+        cache
+            .entry(&CK_FILE, (source.clone(), first))
+            .with_tag(FileStamp::tag(&path))
+            .insert("first".to_string());
+        assert_eq!(cache.stamp_file(&path).unwrap(), first);
+        assert!(cache.entry(&CK_FILE, (source.clone(), first)).contains());
+
+        std::fs::write(&path, "second!").unwrap();
+        let second = cache.stamp_file(&path).unwrap();
+        assert_ne!(first, second);
+        assert!(!cache.entry(&CK_FILE, (source.clone(), first)).contains());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(cache.stamp_file(&path).is_err());
+    }
 }
@@ -0,0 +1,107 @@
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::key::RawKey;
+
+/// A snapshot of the counters of a cache, or of one of its keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Values requested from retrievers and loaders, including the failed attempts.
+    pub loads: u64,
+    pub load_failures: u64,
+    /// Entries removed because they expired or to make room for other entries.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The share of the lookups that found a value, `0.0` when there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Event {
+    Hit,
+    Miss,
+    Load,
+    LoadFailure,
+    Eviction,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    loads: AtomicU64,
+    load_failures: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Counters {
+    fn record(&self, event: Event) {
+        let counter = match event {
+            Event::Hit => &self.hits,
+            Event::Miss => &self.misses,
+            Event::Load => &self.loads,
+            Event::LoadFailure => &self.load_failures,
+            Event::Eviction => &self.evictions,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            loads: self.loads.load(Ordering::Relaxed),
+            load_failures: self.load_failures.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The counters of the whole cache and of each key, see [`RawKey::qualified_name`].
+#[derive(Default)]
+pub(crate) struct Metrics {
+    total: Counters,
+    by_key: DashMap<String, Counters>,
+}
+
+impl Metrics {
+    pub(crate) fn record(&self, key: &RawKey, event: Event) {
+        self.total.record(event);
+        self.by_key
+            .entry(key.qualified_name())
+            .or_default()
+            .record(event);
+    }
+
+    pub(crate) fn total(&self) -> CacheStats {
+        self.total.snapshot()
+    }
+
+    pub(crate) fn get(&self, qualified_name: &str) -> CacheStats {
+        self.by_key
+            .get(qualified_name)
+            .map(|counters| counters.snapshot())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn by_key(&self) -> Vec<(String, CacheStats)> {
+        let mut stats = self
+            .by_key
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().snapshot()))
+            .collect::<Vec<_>>();
+        stats.sort_by(|(a, _), (b, _)| a.cmp(b));
+        stats
+    }
+}
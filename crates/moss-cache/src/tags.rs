@@ -0,0 +1,61 @@
+use dashmap::DashMap;
+use std::collections::HashSet;
+
+use crate::key::RawKey;
+
+/// The tags of the entries, for invalidating groups of entries at once.
+#[derive(Default)]
+pub(crate) struct TagIndex {
+    entries: DashMap<String, HashSet<RawKey>>,
+    tags: DashMap<RawKey, HashSet<String>>,
+}
+
+impl TagIndex {
+    pub(crate) fn tag(&self, key: &RawKey, tags: &[String]) {
+        if tags.is_empty() {
+            return;
+        }
+
+        self.tags
+            .entry(key.clone())
+            .or_default()
+            .extend(tags.iter().cloned());
+        for tag in tags {
+            self.entries
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+    }
+
+    /// Forgets the tags of a removed entry.
+    pub(crate) fn untag(&self, key: &RawKey) {
+        let Some((_, tags)) = self.tags.remove(key) else {
+            return;
+        };
+
+        for tag in tags {
+            if let Some(mut entries) = self.entries.get_mut(&tag) {
+                entries.remove(key);
+            }
+            self.entries
+                .remove_if(&tag, |_, entries| entries.is_empty());
+        }
+    }
+
+    /// Removes the tag, returning the keys of the entries it was attached to.
+    pub(crate) fn take(&self, tag: &str) -> Vec<RawKey> {
+        let Some((_, entries)) = self.entries.remove(tag) else {
+            return Vec::new();
+        };
+
+        for key in &entries {
+            if let Some(mut tags) = self.tags.get_mut(key) {
+                tags.remove(tag);
+            }
+            self.tags.remove_if(key, |_, tags| tags.is_empty());
+        }
+
+        entries.into_iter().collect()
+    }
+}
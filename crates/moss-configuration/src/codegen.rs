@@ -0,0 +1,400 @@
+use anyhow::{anyhow, Result};
+use moss_extension_point::registry::ConfigurationRegistry;
+use moss_mel::foundations::typ::{TypeBoolean, TypeNumber, TypeString, Typeable};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// Keywords that cannot be used as raw identifiers, so they get a trailing underscore instead.
+const RUST_PATH_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    Bool,
+    String,
+    U32,
+    I64,
+    F64,
+}
+
+impl ValueType {
+    fn infer(type_name: &str, default: &JsonValue) -> Option<Self> {
+        match type_name {
+            TypeBoolean::NAME => Some(ValueType::Bool),
+            TypeString::NAME => Some(ValueType::String),
+            TypeNumber::NAME => Some(match default {
+                JsonValue::Number(number)
+                    if number.as_u64().is_some_and(|n| n <= u32::MAX as u64) =>
+                {
+                    ValueType::U32
+                }
+                JsonValue::Number(number) if number.is_i64() => ValueType::I64,
+                JsonValue::Number(_) => ValueType::F64,
+                _ => ValueType::U32,
+            }),
+            _ => None,
+        }
+    }
+
+    fn rust_type(&self) -> &'static str {
+        match self {
+            ValueType::Bool => "bool",
+            ValueType::String => "String",
+            ValueType::U32 => "u32",
+            ValueType::I64 => "i64",
+            ValueType::F64 => "f64",
+        }
+    }
+
+    fn literal(&self, value: &JsonValue) -> String {
+        match self {
+            ValueType::Bool => value.as_bool().unwrap_or_default().to_string(),
+            ValueType::String => format!("String::from({:?})", value.as_str().unwrap_or_default()),
+            ValueType::U32 => format!("{}u32", value.as_u64().unwrap_or_default()),
+            ValueType::I64 => format!("{}i64", value.as_i64().unwrap_or_default()),
+            ValueType::F64 => format!("{:?}f64", value.as_f64().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Accessor {
+    key: String,
+    method: String,
+    field: String,
+    value_type: ValueType,
+    default: String,
+    description: Option<String>,
+}
+
+/// Generates strongly typed accessors for every parameter in the registry.
+///
+/// A parameter `group.someName` becomes `Settings::group().some_name()`, so renaming
+/// or retyping a parameter breaks the build of the code relying on it. The generated
+/// code expects `configuration_type` to provide a `get_typed_value_or(key, default)` method
+/// and the including crate to depend on `serde` and `ts-rs`. The `SettingsValues` struct
+/// is exported to the `settings.ts` bindings.
+pub fn generate_settings(
+    registry: &ConfigurationRegistry,
+    configuration_type: &str,
+) -> Result<String> {
+    let mut groups: BTreeMap<String, Vec<Accessor>> = BTreeMap::new();
+
+    // The excluded parameters are hidden from the frontend, so they get no accessors.
+    let mut parameters = registry.parameters().iter().collect::<Vec<_>>();
+    parameters.sort_by_key(|&(key, _)| key);
+
+    for (key, parameter) in parameters {
+        let default = registry
+            .get_override(key)
+            .map(|descriptor| descriptor.value.clone())
+            .unwrap_or_else(|| parameter.default.clone());
+
+        let Some(value_type) = ValueType::infer(parameter.typ.data().name, &default) else {
+            return Err(anyhow!(
+                "The parameter '{key}' has the type '{}', which has no Rust equivalent",
+                parameter.typ.data().name
+            ));
+        };
+
+        let (group, name) = key
+            .split_once('.')
+            .ok_or_else(|| anyhow!("The parameter key '{key}' must be prefixed with a group"))?;
+
+        let accessors = groups.entry(group.to_string()).or_default();
+        let method = escape_keyword(to_snake_case(name));
+        if accessors.iter().any(|accessor| accessor.method == method) {
+            return Err(anyhow!(
+                "The parameter '{key}' produces the accessor '{group}.{method}', which is already taken"
+            ));
+        }
+
+        accessors.push(Accessor {
+            key: key.to_string(),
+            field: format!("{}_{}", to_snake_case(group), to_snake_case(name)),
+            method,
+            value_type,
+            default: value_type.literal(&default),
+            description: parameter.description.clone(),
+        });
+    }
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        "// This file was generated from the MEL configuration declarations. Do not edit this file manually.\n"
+    )?;
+
+    writeln!(output, "pub struct Settings<'a> {{")?;
+    writeln!(output, "    configuration: &'a {configuration_type},")?;
+    writeln!(output, "}}\n")?;
+    writeln!(output, "impl<'a> Settings<'a> {{")?;
+    writeln!(
+        output,
+        "    pub fn new(configuration: &'a {configuration_type}) -> Self {{"
+    )?;
+    writeln!(output, "        Self {{ configuration }}")?;
+    writeln!(output, "    }}")?;
+    for group in groups.keys() {
+        writeln!(output)?;
+        writeln!(
+            output,
+            "    pub fn {}(&self) -> {}<'a> {{",
+            escape_keyword(to_snake_case(group)),
+            group_type_name(group)
+        )?;
+        writeln!(output, "        {} {{", group_type_name(group))?;
+        writeln!(output, "            configuration: self.configuration,")?;
+        writeln!(output, "        }}")?;
+        writeln!(output, "    }}")?;
+    }
+    writeln!(output)?;
+    writeln!(output, "    pub fn values(&self) -> SettingsValues {{")?;
+    writeln!(output, "        SettingsValues {{")?;
+    for (group, accessors) in &groups {
+        for accessor in accessors {
+            writeln!(
+                output,
+                "            {}: self.{}().{}(),",
+                accessor.field,
+                escape_keyword(to_snake_case(group)),
+                accessor.method
+            )?;
+        }
+    }
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}")?;
+
+    for (group, accessors) in &groups {
+        let type_name = group_type_name(group);
+
+        writeln!(output)?;
+        writeln!(output, "pub struct {type_name}<'a> {{")?;
+        writeln!(output, "    configuration: &'a {configuration_type},")?;
+        writeln!(output, "}}\n")?;
+        writeln!(output, "impl {type_name}<'_> {{")?;
+        for (index, accessor) in accessors.iter().enumerate() {
+            if index > 0 {
+                writeln!(output)?;
+            }
+            write_doc(&mut output, "    ", accessor.description.as_deref())?;
+            writeln!(
+                output,
+                "    pub fn {}(&self) -> {} {{",
+                accessor.method,
+                accessor.value_type.rust_type()
+            )?;
+            writeln!(
+                output,
+                "        self.configuration.get_typed_value_or({:?}, {})",
+                accessor.key, accessor.default
+            )?;
+            writeln!(output, "    }}")?;
+        }
+        writeln!(output, "}}")?;
+    }
+
+    writeln!(output)?;
+    writeln!(
+        output,
+        "#[derive(Debug, Clone, serde::Serialize, ts_rs::TS)]"
+    )?;
+    writeln!(output, "#[ts(export, export_to = \"settings.ts\")]")?;
+    writeln!(output, "pub struct SettingsValues {{")?;
+    for accessor in groups.values().flatten() {
+        write_doc(&mut output, "    ", accessor.description.as_deref())?;
+        writeln!(output, "    #[serde(rename = {:?})]", accessor.key)?;
+        writeln!(
+            output,
+            "    pub {}: {},",
+            accessor.field,
+            accessor.value_type.rust_type()
+        )?;
+    }
+    writeln!(output, "}}")?;
+
+    Ok(output)
+}
+
+fn write_doc(output: &mut String, indent: &str, description: Option<&str>) -> Result<()> {
+    if let Some(description) = description {
+        for line in description.lines() {
+            writeln!(output, "{indent}/// {line}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn group_type_name(group: &str) -> String {
+    let mut result = String::new();
+    let mut capitalize = true;
+    for ch in group.chars() {
+        if !ch.is_alphanumeric() {
+            capitalize = true;
+            continue;
+        }
+
+        if capitalize {
+            result.extend(ch.to_uppercase());
+            capitalize = false;
+        } else {
+            result.push(ch);
+        }
+    }
+
+    format!("{result}Settings")
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for ch in name.chars() {
+        if ch.is_uppercase() {
+            if !result.is_empty() && !result.ends_with('_') {
+                result.push('_');
+            }
+            result.extend(ch.to_lowercase());
+        } else if ch.is_alphanumeric() {
+            result.push(ch);
+        } else if !result.ends_with('_') {
+            result.push('_');
+        }
+    }
+
+    result
+}
+
+fn escape_keyword(ident: String) -> String {
+    if RUST_PATH_KEYWORDS.contains(&ident.as_str()) {
+        format!("{ident}_")
+    } else if RUST_KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{ident}")
+    } else {
+        ident
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::eval::Context;
+    use moss_mel::{foundations::scope::ModuleScope, parse::parse_module_file};
+
+    fn registry(input: &str) -> ConfigurationRegistry {
+        let mut scope = ModuleScope::new();
+        parse_module_file(input, &mut scope).unwrap();
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(
+            scope
+                .evaluate_with_context(&mut Context::new())
+                .unwrap()
+                .into_values(),
+        );
+
+        registry
+    }
+
+    #[test]
+    fn test_case_conversion() {
+        assert_eq!(to_snake_case("defaultWidth"), "default_width");
+        assert_eq!(
+            to_snake_case("minimap.renderCharacters"),
+            "minimap_render_characters"
+        );
+        assert_eq!(group_type_name("window"), "WindowSettings");
+        assert_eq!(escape_keyword(to_snake_case("type")), "r#type");
+        assert_eq!(escape_keyword(to_snake_case("self")), "self_");
+        assert_eq!(escape_keyword(to_snake_case("super")), "super_");
+        assert_eq!(escape_keyword("Self".to_string()), "Self_");
+    }
+
+    #[test]
+    fn test_generate_accessors() {
+        let code = generate_settings(
+            &registry(
+                r#"
+                configuration "moss.kernel.window" {
+                    parameter "window.defaultWidth" {
+                        type = number
+                        default = 800
+                        description = "The width of the application window in pixels."
+                    }
+
+                    parameter "window.restoreFullscreen" {
+                        type = bool
+                        default = true
+                    }
+
+                    parameter "editor.fontFamily" {
+                        type = string
+                        default = "Inter"
+                    }
+                }
+                "#,
+            ),
+            "ConfigurationService",
+        )
+        .unwrap();
+
+        assert!(code.contains("pub fn window(&self) -> WindowSettings<'a>"));
+        assert!(code.contains("pub fn default_width(&self) -> u32"));
+        assert!(code.contains("get_typed_value_or(\"window.defaultWidth\", 800u32)"));
+        assert!(code.contains("pub fn restore_fullscreen(&self) -> bool"));
+        assert!(code.contains("get_typed_value_or(\"editor.fontFamily\", String::from(\"Inter\"))"));
+        assert!(code.contains("#[serde(rename = \"window.defaultWidth\")]"));
+        assert!(code.contains("pub window_default_width: u32,"));
+    }
+
+    #[test]
+    fn test_generate_skips_excluded_parameters() {
+        let code = generate_settings(
+            &registry(
+                r#"
+                configuration "moss.kernel.telemetry" {
+                    parameter "telemetry.enabled" {
+                        type = bool
+                        default = true
+                    }
+
+                    parameter "telemetry.endpoint" {
+                        type = string
+                        default = "https://example.com"
+                        excluded = true
+                    }
+                }
+                "#,
+            ),
+            "ConfigurationService",
+        )
+        .unwrap();
+
+        assert!(code.contains("pub telemetry_enabled: bool,"));
+        assert!(!code.contains("telemetry.endpoint"));
+        assert!(!code.contains("telemetry_endpoint"));
+    }
+
+    #[test]
+    fn test_generate_rejects_ungrouped_key() {
+        let result = generate_settings(
+            &registry(
+                r#"
+                configuration "moss.kernel.window" {
+                    parameter "fullscreen" {
+                        type = bool
+                    }
+                }
+                "#,
+            ),
+            "ConfigurationService",
+        );
+
+        assert!(result.is_err());
+    }
+}
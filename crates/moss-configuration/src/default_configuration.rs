@@ -16,7 +16,7 @@ pub struct DefaultConfiguration {
 impl DefaultConfiguration {
     pub fn new(registry: Arc<ConfigurationRegistry>) -> Self {
         let mut model = ConfigurationModel::new();
-        for (key, value) in registry.parameters() {
+        for (key, value) in registry.all_parameters() {
             let default_value = if let Some(override_descriptor) = registry.get_override(key) {
                 override_descriptor.value.clone()
             } else if !value.default.is_null() {
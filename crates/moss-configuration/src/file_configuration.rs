@@ -0,0 +1,154 @@
+use anyhow::{Context as _, Result};
+use arcstr::ArcStr;
+use moss_extension_point::registry::ConfigurationRegistry;
+use serde_json::{Map, Value as JsonValue};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    migration::{migrate, SettingsVersion, VERSION_KEY},
+    ConfigurationModel,
+};
+
+/// A configuration model backed by a settings file, such as the user or workspace settings.
+pub struct FileConfiguration {
+    path: PathBuf,
+    version: SettingsVersion,
+    model: Arc<ConfigurationModel>,
+    /// The migrated contents that could not be saved to the file yet.
+    unsaved: Option<Map<String, JsonValue>>,
+}
+
+impl FileConfiguration {
+    pub fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            version: SettingsVersion::default(),
+            model: Arc::new(ConfigurationModel::new()),
+            unsaved: None,
+        }
+    }
+
+    /// Loads the settings file and brings it up to date with the registered migrations.
+    /// A missing file is treated as empty. When `write_back` is set, the migrated
+    /// contents are saved to the file.
+    pub fn load(path: PathBuf, registry: &ConfigurationRegistry, write_back: bool) -> Result<Self> {
+        if !path.exists() {
+            // A new file has nothing to migrate, so it starts at the latest version.
+            return Ok(Self {
+                version: SettingsVersion::latest(registry.migrations()),
+                ..Self::empty(path)
+            });
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read the settings file {}", path.display()))?;
+        let mut contents: Map<String, JsonValue> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the settings file {}", path.display()))?;
+
+        let report = migrate(&mut contents, registry.migrations());
+        let mut unsaved = None;
+        if report.is_changed() {
+            debug!(
+                "Migrated the settings file {} from version {:?} to {:?}",
+                path.display(),
+                report.initial_version,
+                report.version
+            );
+
+            if write_back {
+                if let Err(err) = write_atomically(&path, &contents) {
+                    warn!(
+                        "Failed to save the migrated settings file {}: {err:#}",
+                        path.display()
+                    );
+                    unsaved = Some(contents.clone());
+                }
+            }
+        }
+
+        contents.remove(VERSION_KEY);
+        for key in contents.keys() {
+            if registry.get_parameter(key).is_none() {
+                debug!("The setting '{key}' is not declared by any configuration");
+            }
+        }
+
+        Ok(Self {
+            path,
+            version: report.version,
+            model: Arc::new(build_model(contents)),
+            unsaved,
+        })
+    }
+
+    /// Replaces all the settings, saving them to the file first, so the model
+    /// stays untouched if the file cannot be written.
+    pub fn replace(
+        &mut self,
+        settings: Map<String, JsonValue>,
+        version: SettingsVersion,
+    ) -> Result<()> {
+        let mut contents = settings.clone();
+        version.write(&mut contents);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomically(&self.path, &contents)
+            .with_context(|| format!("Failed to save the settings file {}", self.path.display()))?;
+
+        self.version = version;
+        self.model = Arc::new(build_model(settings));
+        self.unsaved = None;
+
+        Ok(())
+    }
+
+    /// Saves the migrated contents that could not be saved when the file was loaded,
+    /// returning whether there were any.
+    pub fn save_unsaved(&mut self) -> Result<bool> {
+        let Some(contents) = &self.unsaved else {
+            return Ok(false);
+        };
+
+        write_atomically(&self.path, contents)
+            .with_context(|| format!("Failed to save the settings file {}", self.path.display()))?;
+        self.unsaved = None;
+
+        Ok(true)
+    }
+
+    /// The settings version, which holds the latest migration of every contributor applied
+    /// to the file.
+    pub fn version(&self) -> &SettingsVersion {
+        &self.version
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn model(&self) -> &Arc<ConfigurationModel> {
+        &self.model
+    }
+}
+
+fn build_model(contents: Map<String, JsonValue>) -> ConfigurationModel {
+    let mut model = ConfigurationModel::new();
+    for (key, value) in contents {
+        model.insert(&ArcStr::from(key), value);
+    }
+
+    model
+}
+
+fn write_atomically(path: &Path, contents: &Map<String, JsonValue>) -> Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string_pretty(contents)?)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
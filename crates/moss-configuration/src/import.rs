@@ -0,0 +1,215 @@
+use moss_extension_point::registry::ConfigurationRegistry;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::BTreeSet;
+
+use crate::{
+    migration::{migrate, SettingsVersion, VERSION_KEY},
+    validation::validate_value,
+    ConfigurationModel,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+    pub key: String,
+    /// The current value, missing when the setting is added by the import.
+    pub old_value: Option<JsonValue>,
+    /// The imported value, missing when the setting is removed by the import.
+    pub new_value: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedSetting {
+    pub key: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    /// The settings that replace the current ones once the import is applied.
+    pub settings: Map<String, JsonValue>,
+    /// The version of the settings after the migrations were applied.
+    pub version: SettingsVersion,
+    pub changes: Vec<SettingChange>,
+    /// Keys that are not declared by any configuration, these are not imported.
+    pub unknown_keys: Vec<String>,
+    /// Settings whose values are not valid for their parameters, these are not imported.
+    pub invalid: Vec<RejectedSetting>,
+}
+
+/// Validates the imported settings against the registry and compares them with the current ones.
+///
+/// Settings exported by an older release are migrated first. The import replaces all the
+/// current settings, so the ones missing from the imported settings are reported as removed.
+pub fn plan_import(
+    registry: &ConfigurationRegistry,
+    current: &ConfigurationModel,
+    mut settings: Map<String, JsonValue>,
+    version: &SettingsVersion,
+) -> ImportPlan {
+    version.write(&mut settings);
+    let report = migrate(&mut settings, registry.migrations());
+    settings.remove(VERSION_KEY);
+
+    let mut plan = ImportPlan {
+        version: report.version,
+        ..Default::default()
+    };
+
+    for (key, value) in settings {
+        let Some(parameter) = registry.get_parameter(&key) else {
+            plan.unknown_keys.push(key);
+            continue;
+        };
+
+        if let Err(err) = validate_value(parameter, &value) {
+            plan.invalid.push(RejectedSetting {
+                key,
+                reason: err.to_string(),
+            });
+            continue;
+        }
+
+        plan.settings.insert(key, value);
+    }
+
+    let keys = current
+        .contents
+        .keys()
+        .map(|key| key.as_str())
+        .chain(plan.settings.keys().map(String::as_str))
+        .collect::<BTreeSet<_>>();
+
+    for key in keys {
+        let old_value = current.contents.get(key);
+        let new_value = plan.settings.get(key);
+        if old_value != new_value {
+            plan.changes.push(SettingChange {
+                key: key.to_string(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            });
+        }
+    }
+
+    plan.unknown_keys.sort();
+    plan.invalid.sort_by(|a, b| a.key.cmp(&b.key));
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arcstr::ArcStr;
+    use hcl::eval::Context;
+    use moss_mel::{foundations::scope::ModuleScope, parse::parse_module_file};
+    use serde_json::json;
+
+    fn registry() -> ConfigurationRegistry {
+        let mut scope = ModuleScope::new();
+        parse_module_file(
+            r#"
+            configuration "moss.kernel.editor" {
+                parameter "editor.fontSize" {
+                    type = number
+                    minimum = 10
+                    maximum = 20
+                    default = 14
+                }
+
+                parameter "editor.fontFamily" {
+                    type = string
+                    default = "Inter"
+                }
+
+                parameter "editor.wordWrap" {
+                    type = bool
+                    default = false
+                }
+
+                migrate "editor.fontSizePx" {
+                    to = "editor.fontSize"
+                    version = 1
+                }
+            }
+            "#,
+            &mut scope,
+        )
+        .unwrap();
+
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(
+            scope
+                .evaluate_with_context(&mut Context::new())
+                .unwrap()
+                .into_values(),
+        );
+
+        registry
+    }
+
+    fn settings(value: JsonValue) -> Map<String, JsonValue> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_plan_import() {
+        let mut current = ConfigurationModel::new();
+        current.insert(&ArcStr::from("editor.fontFamily"), json!("Inter"));
+        current.insert(&ArcStr::from("editor.wordWrap"), json!(true));
+
+        let plan = plan_import(
+            &registry(),
+            &current,
+            settings(json!({
+                "editor.fontSizePx": 16,
+                "editor.fontFamily": "Inter",
+                "editor.wordWrap": "on",
+                "terminal.shell": "zsh",
+            })),
+            &SettingsVersion::default(),
+        );
+
+        assert_eq!(
+            JsonValue::Object(plan.settings),
+            json!({
+                "editor.fontSize": 16,
+                "editor.fontFamily": "Inter",
+            })
+        );
+        assert_eq!(plan.version.get("moss.kernel.editor"), 1);
+        assert_eq!(plan.unknown_keys, ["terminal.shell"]);
+        assert_eq!(plan.invalid.len(), 1);
+        assert_eq!(plan.invalid[0].key, "editor.wordWrap");
+        assert_eq!(
+            plan.changes,
+            [
+                SettingChange {
+                    key: "editor.fontSize".to_string(),
+                    old_value: None,
+                    new_value: Some(json!(16)),
+                },
+                SettingChange {
+                    key: "editor.wordWrap".to_string(),
+                    old_value: Some(json!(true)),
+                    new_value: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_import_rejects_out_of_bounds_value() {
+        let registry = registry();
+        let plan = plan_import(
+            &registry,
+            &ConfigurationModel::new(),
+            settings(json!({ "editor.fontSize": 42 })),
+            &SettingsVersion::latest(registry.migrations()),
+        );
+
+        assert!(plan.settings.is_empty());
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.invalid[0].key, "editor.fontSize");
+    }
+}
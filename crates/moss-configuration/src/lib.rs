@@ -1,4 +1,11 @@
+pub mod codegen;
 pub mod default_configuration;
+pub mod file_configuration;
+pub mod import;
+pub mod migration;
+pub mod schema;
+pub mod search;
+pub mod validation;
 
 use arcstr::ArcStr;
 use serde_json::Value as JsonValue;
@@ -72,19 +79,24 @@ impl ConfigurationModel {
 
 pub struct Configuration {
     default: Arc<ConfigurationModel>,
+    user: Arc<ConfigurationModel>,
     // TODO:
-    // user: ConfigurationModel,
     // workspace: ConfigurationModel,
     // inmem: ConfigurationModel,
     // consolidated: ConfigurationModel,
 }
 
 impl Configuration {
-    pub fn new(default: Arc<ConfigurationModel>) -> Self {
-        Self { default }
+    pub fn new(default: Arc<ConfigurationModel>, user: Arc<ConfigurationModel>) -> Self {
+        Self { default, user }
+    }
+
+    pub fn set_user(&mut self, user: Arc<ConfigurationModel>) {
+        self.user = user;
     }
 
     pub fn get_value(&self, key: &str) -> Option<&JsonValue> {
-        self.default.get(&ArcStr::from(key))
+        let key = ArcStr::from(key);
+        self.user.get(&key).or_else(|| self.default.get(&key))
     }
 }
@@ -0,0 +1,370 @@
+use arcstr::ArcStr;
+use hashbrown::HashSet;
+use moss_mel::foundations::configuration::Migration;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The key under which a settings file stores the version of its contents.
+pub const VERSION_KEY: &str = "$version";
+
+/// The version of the settings contents, kept for every configuration that declares
+/// migrations, since each of them numbers the versions of its migrations on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SettingsVersion(BTreeMap<String, u64>);
+
+impl SettingsVersion {
+    /// The version of the contents that have all the migrations applied.
+    pub fn latest(migrations: &[Arc<Migration>]) -> Self {
+        let mut version = Self::default();
+        for migration in migrations {
+            version.advance(&migration.contributor, migration.version);
+        }
+
+        version
+    }
+
+    /// Reads the version stored in the settings contents. The contents without one
+    /// have no migration applied.
+    pub fn read(contents: &Map<String, JsonValue>) -> Self {
+        let Some(value) = contents.get(VERSION_KEY) else {
+            return Self::default();
+        };
+
+        serde_json::from_value(value.clone()).unwrap_or_else(|err| {
+            warn!("The settings version {value} is not valid, all the migrations apply: {err}");
+            Self::default()
+        })
+    }
+
+    pub fn write(&self, contents: &mut Map<String, JsonValue>) {
+        contents.insert(
+            VERSION_KEY.to_string(),
+            JsonValue::Object(
+                self.0
+                    .iter()
+                    .map(|(contributor, version)| (contributor.clone(), JsonValue::from(*version)))
+                    .collect(),
+            ),
+        );
+    }
+
+    pub fn get(&self, contributor: &str) -> u64 {
+        self.0.get(contributor).copied().unwrap_or_default()
+    }
+
+    fn advance(&mut self, contributor: &str, version: u64) {
+        let current = self.0.entry(contributor.to_string()).or_default();
+        *current = (*current).max(version);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub from: ArcStr,
+    pub to: ArcStr,
+    pub version: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// The version of the settings before the migrations were applied.
+    pub initial_version: SettingsVersion,
+    /// The version of the settings after the migrations were applied.
+    pub version: SettingsVersion,
+    pub applied: Vec<AppliedMigration>,
+}
+
+impl MigrationReport {
+    pub fn is_changed(&self) -> bool {
+        self.initial_version != self.version || !self.applied.is_empty()
+    }
+}
+
+/// Applies the migrations newer than the version of their contributor stored in the settings
+/// contents.
+///
+/// The migrations must be ordered by their version, as returned by the registry. A value that
+/// is already set under the new key takes precedence over the migrated one. The migrations of
+/// a contributor to the same version are applied together: when one of them fails, the values
+/// are left as they were and the version of the contributor stays before it, so that they are
+/// tried again the next time.
+pub fn migrate(
+    contents: &mut Map<String, JsonValue>,
+    migrations: &[Arc<Migration>],
+) -> MigrationReport {
+    let initial_version = SettingsVersion::read(contents);
+    let mut report = MigrationReport {
+        version: initial_version.clone(),
+        initial_version,
+        applied: Vec::new(),
+    };
+
+    let mut steps = Vec::<(&str, u64, Vec<&Migration>)>::new();
+    for migration in migrations {
+        let contributor = migration.contributor.as_str();
+        if migration.version <= report.initial_version.get(contributor) {
+            continue;
+        }
+
+        match steps
+            .iter_mut()
+            .find(|(c, version, _)| *c == contributor && *version == migration.version)
+        {
+            Some((_, _, step)) => step.push(migration),
+            None => steps.push((contributor, migration.version, vec![migration])),
+        }
+    }
+
+    let mut failed = HashSet::new();
+    for (contributor, version, step) in steps {
+        if failed.contains(contributor) {
+            continue;
+        }
+
+        let mut migrated = contents.clone();
+        let mut applied = Vec::new();
+        for migration in step {
+            match apply_migration(&mut migrated, migration) {
+                Ok(Some(migration)) => applied.push(migration),
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(
+                        "Failed to migrate the setting '{}' to version {version} of '{contributor}': {err}",
+                        migration.ident
+                    );
+                    failed.insert(contributor);
+                    break;
+                }
+            }
+        }
+
+        if !failed.contains(contributor) {
+            *contents = migrated;
+            report.applied.extend(applied);
+            report.version.advance(contributor, version);
+        }
+    }
+
+    if report.version != report.initial_version {
+        report.version.write(contents);
+    }
+
+    report
+}
+
+/// Moves the value of the outdated setting to its new key, returns `None` if there is
+/// nothing to migrate.
+fn apply_migration(
+    contents: &mut Map<String, JsonValue>,
+    migration: &Migration,
+) -> anyhow::Result<Option<AppliedMigration>> {
+    let Some(value) = contents.get(migration.ident.as_str()) else {
+        return Ok(None);
+    };
+
+    let target = migration.target();
+    if target != &migration.ident && contents.contains_key(target.as_str()) {
+        warn!(
+            "The setting '{}' is already set, the value of the outdated setting '{}' is discarded",
+            target, migration.ident
+        );
+        contents.remove(migration.ident.as_str());
+        return Ok(None);
+    }
+
+    let migrated = migration.apply(value)?;
+    info!(
+        "Migrated the setting '{}' to '{}' (version {} of '{}')",
+        migration.ident, target, migration.version, migration.contributor
+    );
+
+    contents.remove(migration.ident.as_str());
+    contents.insert(target.to_string(), migrated);
+
+    Ok(Some(AppliedMigration {
+        from: ArcStr::clone(&migration.ident),
+        to: ArcStr::clone(target),
+        version: migration.version,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::eval::Context;
+    use moss_extension_point::registry::ConfigurationRegistry;
+    use moss_mel::{foundations::scope::ModuleScope, parse::parse_module_file};
+    use serde_json::json;
+
+    fn migrations(input: &str) -> Vec<Arc<Migration>> {
+        let mut scope = ModuleScope::new();
+        parse_module_file(input, &mut scope).unwrap();
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(
+            scope
+                .evaluate_with_context(&mut Context::new())
+                .unwrap()
+                .into_values(),
+        );
+
+        registry.migrations().to_vec()
+    }
+
+    const MIGRATIONS: &str = r#"
+    configuration "moss.kernel.editor" {
+        migrate "editor.fontSizeOld" {
+            to = "editor.fontSizeLegacy"
+            version = 1
+        }
+
+        migrate "editor.fontSizeLegacy" {
+            to = "editor.fontSize"
+            transform = value * 2
+            version = 2
+        }
+
+        migrate "editor.wordWrap" {
+            transform = value ? "on" : "off"
+            version = 2
+        }
+    }
+    "#;
+
+    fn contents(value: JsonValue) -> Map<String, JsonValue> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_migrate_chain() {
+        let mut contents = contents(json!({
+            "editor.fontSizeOld": 7,
+            "editor.wordWrap": true,
+        }));
+
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert_eq!(
+            JsonValue::Object(contents),
+            json!({
+                "$version": { "moss.kernel.editor": 2 },
+                "editor.fontSize": 14,
+                "editor.wordWrap": "on",
+            })
+        );
+        assert_eq!(report.initial_version.get("moss.kernel.editor"), 0);
+        assert_eq!(report.version.get("moss.kernel.editor"), 2);
+        assert_eq!(report.applied.len(), 3);
+    }
+
+    #[test]
+    fn test_migrate_skips_applied_versions() {
+        let mut contents = contents(json!({
+            "$version": { "moss.kernel.editor": 1 },
+            "editor.fontSizeOld": 7,
+            "editor.fontSizeLegacy": 8,
+        }));
+
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert_eq!(
+            JsonValue::Object(contents),
+            json!({
+                "$version": { "moss.kernel.editor": 2 },
+                "editor.fontSizeOld": 7,
+                "editor.fontSize": 16,
+            })
+        );
+        assert_eq!(report.applied.len(), 1);
+        assert!(report.is_changed());
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_target() {
+        let mut contents = contents(json!({
+            "$version": { "moss.kernel.editor": 1 },
+            "editor.fontSizeLegacy": 8,
+            "editor.fontSize": 12,
+        }));
+
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert_eq!(
+            JsonValue::Object(contents),
+            json!({
+                "$version": { "moss.kernel.editor": 2 },
+                "editor.fontSize": 12,
+            })
+        );
+        assert!(report.applied.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_is_noop_when_up_to_date() {
+        let mut contents = contents(json!({
+            "$version": { "moss.kernel.editor": 2 },
+            "editor.wordWrap": true,
+        }));
+
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert!(!report.is_changed());
+        assert_eq!(contents["editor.wordWrap"], json!(true));
+    }
+
+    #[test]
+    fn test_migrate_tracks_version_per_contributor() {
+        const LAYOUT: &str = r#"
+        configuration "moss.kernel.layout" {
+            migrate "layout.sidebarOld" {
+                to = "layout.sidebar"
+                version = 1
+            }
+        }
+        "#;
+
+        let mut contents = contents(json!({
+            "$version": { "moss.kernel.editor": 2 },
+            "layout.sidebarOld": "left",
+        }));
+
+        let report = migrate(&mut contents, &migrations(&format!("{MIGRATIONS}{LAYOUT}")));
+
+        assert_eq!(
+            JsonValue::Object(contents),
+            json!({
+                "$version": { "moss.kernel.editor": 2, "moss.kernel.layout": 1 },
+                "layout.sidebar": "left",
+            })
+        );
+        assert_eq!(report.applied.len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_retries_failed_transform() {
+        let mut contents = contents(json!({
+            "editor.fontSizeLegacy": "large",
+            "editor.wordWrap": true,
+        }));
+
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert_eq!(
+            JsonValue::Object(contents.clone()),
+            json!({
+                "$version": { "moss.kernel.editor": 1 },
+                "editor.fontSizeLegacy": "large",
+                "editor.wordWrap": true,
+            })
+        );
+        assert_eq!(report.version.get("moss.kernel.editor"), 1);
+
+        contents.insert("editor.fontSizeLegacy".to_string(), json!(8));
+        let report = migrate(&mut contents, &migrations(MIGRATIONS));
+
+        assert_eq!(contents["editor.fontSize"], json!(16));
+        assert_eq!(contents["editor.wordWrap"], json!("on"));
+        assert_eq!(report.version.get("moss.kernel.editor"), 2);
+    }
+}
@@ -0,0 +1,52 @@
+use moss_extension_point::registry::ConfigurationRegistry;
+use moss_mel::foundations::typ::{TypeBoolean, TypeNumber, TypeString, Typeable};
+use serde_json::{Map, Value as JsonValue};
+
+/// Generates a JSON Schema for the parameters that are listed in the settings UI.
+/// Excluded parameters are left out, even though their values remain readable.
+pub fn generate_schema(registry: &ConfigurationRegistry) -> JsonValue {
+    let mut properties = Map::new();
+
+    for (key, parameter) in registry.parameters() {
+        let mut property = Map::new();
+
+        if let Some(typ) = json_schema_type(parameter.typ.data().name) {
+            property.insert("type".to_string(), typ.into());
+        }
+
+        let default_value = registry
+            .get_override(key)
+            .map(|descriptor| descriptor.value.clone())
+            .unwrap_or_else(|| parameter.default.clone());
+        if !default_value.is_null() {
+            property.insert("default".to_string(), default_value);
+        }
+
+        if let Some(minimum) = parameter.minimum {
+            property.insert("minimum".to_string(), minimum.into());
+        }
+        if let Some(maximum) = parameter.maximum {
+            property.insert("maximum".to_string(), maximum.into());
+        }
+        if let Some(description) = &parameter.description {
+            property.insert("description".to_string(), description.clone().into());
+        }
+
+        properties.insert(key.to_string(), JsonValue::Object(property));
+    }
+
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), "object".into());
+    schema.insert("properties".to_string(), JsonValue::Object(properties));
+
+    JsonValue::Object(schema)
+}
+
+fn json_schema_type(type_name: &str) -> Option<&'static str> {
+    match type_name {
+        TypeNumber::NAME => Some("number"),
+        TypeString::NAME => Some("string"),
+        TypeBoolean::NAME => Some("boolean"),
+        _ => None,
+    }
+}
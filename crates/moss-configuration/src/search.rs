@@ -0,0 +1,583 @@
+use arcstr::ArcStr;
+use moss_extension_point::registry::ConfigurationRegistry;
+use moss_mel::foundations::configuration::ParameterScope;
+use serde_json::Value as JsonValue;
+use std::{cmp::Ordering, collections::HashMap, str::FromStr, sync::Arc};
+
+use crate::ConfigurationModel;
+
+const MODIFIED_FILTER: &str = "@modified";
+const SCOPE_FILTER: &str = "@scope:";
+const ADDON_FILTER: &str = "@addon:";
+
+// The weights of the fields a query term is matched against,
+// from the most to the least relevant one.
+const DISPLAY_NAME_WEIGHT: f64 = 1.0;
+const KEY_WEIGHT: f64 = 0.9;
+const LOCALIZED_WEIGHT: f64 = 0.8;
+const GROUP_WEIGHT: f64 = 0.6;
+const DESCRIPTION_WEIGHT: f64 = 0.4;
+
+/// A parsed search query, e.g. `font size @modified @scope:window @addon:theme-defaults`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Lowercased terms, each of them must match the parameter.
+    pub terms: Vec<String>,
+    pub modified: bool,
+    pub scope: Option<String>,
+    pub addon: Option<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = SearchQuery::default();
+
+        for token in input.split_whitespace() {
+            if token.eq_ignore_ascii_case(MODIFIED_FILTER) {
+                query.modified = true;
+            } else if let Some(scope) = strip_filter(token, SCOPE_FILTER) {
+                query.scope = Some(scope.to_uppercase());
+            } else if let Some(addon) = strip_filter(token, ADDON_FILTER) {
+                query.addon = Some(addon.to_string());
+            } else {
+                query.terms.push(token.to_lowercase());
+            }
+        }
+
+        query
+    }
+}
+
+fn strip_filter<'a>(token: &'a str, filter: &str) -> Option<&'a str> {
+    token
+        .get(..filter.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(filter))
+        .map(|_| &token[filter.len()..])
+}
+
+/// The state the search filters and localized matches depend on.
+#[derive(Default)]
+pub struct SearchContext<'a> {
+    /// The model holding the settings changed by the user, used by the `@modified` filter.
+    pub modified: Option<&'a ConfigurationModel>,
+    /// The localized text of the parameters, keyed by the parameter key.
+    pub localized: Option<&'a HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub key: ArcStr,
+    pub display_name: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResultGroup {
+    /// The identifier of the configuration that declares the parameters.
+    pub ident: ArcStr,
+    pub display_name: Option<String>,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    key: ArcStr,
+    display_name: String,
+    description: Option<String>,
+    order: Option<u64>,
+    scope: ParameterScope,
+    addon: Option<String>,
+    group_index: usize,
+}
+
+#[derive(Debug)]
+struct IndexGroup {
+    ident: ArcStr,
+    display_name: Option<String>,
+    order: Option<u64>,
+}
+
+/// An index of the parameters displayed in the settings UI.
+///
+/// The index is built from a snapshot of the registry and has to be rebuilt
+/// once new configurations are registered.
+#[derive(Debug)]
+pub struct SettingsSearchIndex {
+    entries: Vec<IndexEntry>,
+    groups: Vec<IndexGroup>,
+}
+
+impl SettingsSearchIndex {
+    pub fn new(registry: &ConfigurationRegistry) -> Self {
+        let mut entries = Vec::new();
+        let mut groups = Vec::new();
+
+        for node_entry in registry.configuration_nodes() {
+            let node = &node_entry.node;
+            let group_index = groups.len();
+            let mut has_entries = false;
+
+            for (key, parameter) in &node.parameters {
+                // Skip the excluded parameters and the duplicates rejected by the registry.
+                if !registry
+                    .parameters()
+                    .get(key)
+                    .is_some_and(|registered| Arc::ptr_eq(registered, parameter))
+                {
+                    continue;
+                }
+
+                has_entries = true;
+                entries.push(IndexEntry {
+                    key: ArcStr::clone(key),
+                    display_name: display_name(key),
+                    description: parameter.description.clone(),
+                    order: parameter.order,
+                    scope: parameter.scope,
+                    addon: node_entry
+                        .provider_info
+                        .as_ref()
+                        .map(|provider| provider.id.clone()),
+                    group_index,
+                });
+            }
+
+            if has_entries {
+                groups.push(IndexGroup {
+                    ident: ArcStr::clone(&node.ident),
+                    display_name: node.display_name.clone(),
+                    order: node.order,
+                });
+            }
+        }
+
+        Self { entries, groups }
+    }
+
+    /// Returns the matching parameters grouped by the configuration declaring them.
+    ///
+    /// Groups and the parameters within them are ranked by their score. Equally scored
+    /// ones, which is always the case for a query without terms, keep the order declared
+    /// by the `order` fields.
+    pub fn search(&self, input: &str, context: &SearchContext) -> Vec<SearchResultGroup> {
+        let query = SearchQuery::parse(input);
+        let mut matches: Vec<(&IndexEntry, f64)> = self
+            .entries
+            .iter()
+            .filter(|entry| self.matches_filters(entry, &query, context))
+            .filter_map(|entry| Some((entry, self.score(entry, &query, context)?)))
+            .collect();
+
+        matches.sort_by(|(a, a_score), (b, b_score)| {
+            compare_scores(*a_score, *b_score)
+                .then_with(|| compare_orders(a.order, b.order))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        let mut result: Vec<(usize, SearchResultGroup)> = Vec::new();
+        for (entry, score) in matches {
+            let position = match result
+                .iter()
+                .position(|(group_index, _)| *group_index == entry.group_index)
+            {
+                Some(position) => position,
+                None => {
+                    let group = &self.groups[entry.group_index];
+                    result.push((
+                        entry.group_index,
+                        SearchResultGroup {
+                            ident: ArcStr::clone(&group.ident),
+                            display_name: group.display_name.clone(),
+                            results: Vec::new(),
+                        },
+                    ));
+                    result.len() - 1
+                }
+            };
+
+            result[position].1.results.push(SearchResult {
+                key: ArcStr::clone(&entry.key),
+                display_name: entry.display_name.clone(),
+                score,
+            });
+        }
+
+        // Groups are created in the order of their best match, so only equally
+        // scored groups need to be arranged by their order.
+        result.sort_by(|(a_index, a), (b_index, b)| {
+            compare_scores(a.results[0].score, b.results[0].score)
+                .then_with(|| {
+                    compare_orders(self.groups[*a_index].order, self.groups[*b_index].order)
+                })
+                .then_with(|| a_index.cmp(b_index))
+        });
+
+        result.into_iter().map(|(_, group)| group).collect()
+    }
+
+    fn matches_filters(
+        &self,
+        entry: &IndexEntry,
+        query: &SearchQuery,
+        context: &SearchContext,
+    ) -> bool {
+        if query.modified
+            && !context
+                .modified
+                .is_some_and(|model| model.keys.contains(&entry.key))
+        {
+            return false;
+        }
+
+        if let Some(scope) = &query.scope {
+            if !ParameterScope::from_str(scope).is_ok_and(|scope| scope == entry.scope) {
+                return false;
+            }
+        }
+
+        if let Some(addon) = &query.addon {
+            if entry.addon.as_deref() != Some(addon.as_str()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn score(
+        &self,
+        entry: &IndexEntry,
+        query: &SearchQuery,
+        context: &SearchContext,
+    ) -> Option<f64> {
+        if query.terms.is_empty() {
+            return Some(1.0);
+        }
+
+        let group = &self.groups[entry.group_index];
+        let localized = context
+            .localized
+            .and_then(|localized| localized.get(entry.key.as_str()));
+
+        let mut total = 0.0;
+        for term in &query.terms {
+            let scores = [
+                fuzzy_score(term, &entry.display_name).map(|score| score * DISPLAY_NAME_WEIGHT),
+                fuzzy_score(term, &entry.key).map(|score| score * KEY_WEIGHT),
+                group
+                    .display_name
+                    .as_deref()
+                    .and_then(|name| fuzzy_score(term, name))
+                    .map(|score| score * GROUP_WEIGHT),
+                localized
+                    .and_then(|text| substring_score(term, text))
+                    .map(|score| score * LOCALIZED_WEIGHT),
+                entry
+                    .description
+                    .as_deref()
+                    .and_then(|description| substring_score(term, description))
+                    .map(|score| score * DESCRIPTION_WEIGHT),
+            ];
+
+            total += scores.into_iter().flatten().reduce(f64::max)?;
+        }
+
+        Some(total / query.terms.len() as f64)
+    }
+}
+
+/// Collects the localized text of the parameters from the `settings` translations namespace,
+/// where a parameter key maps either to a string or to an object of strings.
+pub fn collect_localized_text(translations: &JsonValue) -> HashMap<String, String> {
+    let Some(translations) = translations.as_object() else {
+        return HashMap::new();
+    };
+
+    translations
+        .iter()
+        .filter_map(|(key, value)| {
+            let text = match value {
+                JsonValue::String(text) => text.clone(),
+                JsonValue::Object(object) => object
+                    .values()
+                    .filter_map(JsonValue::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => return None,
+            };
+
+            Some((key.clone(), text))
+        })
+        .collect()
+}
+
+/// Builds a display name from the last segment of the key, e.g. `window.defaultWidth`
+/// becomes `Default Width`.
+fn display_name(key: &str) -> String {
+    let name = key.rsplit('.').next().unwrap_or(key);
+    let mut result = String::new();
+    for ch in name.chars() {
+        if result.is_empty() {
+            result.extend(ch.to_uppercase());
+        } else if ch.is_uppercase() {
+            result.push(' ');
+            result.push(ch);
+        } else if ch == '_' || ch == '-' {
+            result.push(' ');
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    index == 0
+        || !chars[index - 1].is_alphanumeric()
+        || (chars[index - 1].is_lowercase() && chars[index].is_uppercase())
+}
+
+/// Scores a substring match of the lowercased term in the text.
+fn substring_score(term: &str, text: &str) -> Option<f64> {
+    let lowercase = text.to_lowercase();
+    if lowercase == term {
+        return Some(1.0);
+    }
+
+    let byte_index = lowercase.find(term)?;
+    let chars = text.chars().collect::<Vec<_>>();
+    let index = lowercase[..byte_index].chars().count();
+
+    if index < chars.len() && is_word_start(&chars, index) {
+        Some(0.9)
+    } else {
+        Some(0.7)
+    }
+}
+
+/// Scores the lowercased term against the text, falling back to matching the term
+/// as a subsequence, in which case consecutive characters and word starts score higher.
+fn fuzzy_score(term: &str, text: &str) -> Option<f64> {
+    if let Some(score) = substring_score(term, text) {
+        return Some(score);
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut term_chars = term.chars().peekable();
+    let mut matched = 0usize;
+    let mut consecutive = 0usize;
+    let mut word_starts = 0usize;
+    let mut previous: Option<usize> = None;
+
+    for (index, ch) in chars.iter().enumerate() {
+        let Some(expected) = term_chars.peek() else {
+            break;
+        };
+
+        if ch.to_lowercase().eq(std::iter::once(*expected)) {
+            matched += 1;
+            if previous.is_some_and(|previous| previous + 1 == index) {
+                consecutive += 1;
+            }
+            if is_word_start(&chars, index) {
+                word_starts += 1;
+            }
+            previous = Some(index);
+            term_chars.next();
+        }
+    }
+
+    if term_chars.peek().is_some() || matched == 0 {
+        return None;
+    }
+
+    let matched = matched as f64;
+    Some(0.2 + 0.25 * (consecutive as f64 / matched) + 0.25 * (word_starts as f64 / matched))
+}
+
+fn compare_scores(a: f64, b: f64) -> Ordering {
+    b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+}
+
+/// Parameters without an order are placed after the ordered ones.
+fn compare_orders(a: Option<u64>, b: Option<u64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::eval::Context;
+    use moss_extension_point::registry::ValueProviderInfo;
+    use moss_mel::{
+        foundations::{configuration::ConfigurationNode, scope::ModuleScope},
+        parse::parse_module_file,
+    };
+
+    fn evaluate(input: &str) -> Vec<ConfigurationNode> {
+        let mut scope = ModuleScope::new();
+        parse_module_file(input, &mut scope).unwrap();
+        let mut nodes = scope
+            .evaluate_with_context(&mut Context::new())
+            .unwrap()
+            .into_values();
+        nodes.sort_by_key(|node| node.order);
+
+        nodes
+    }
+
+    fn index() -> SettingsSearchIndex {
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(evaluate(
+            r#"
+            configuration "moss.kernel.window" {
+                display_name = "Window"
+                order = 1
+
+                parameter "window.defaultWidth" {
+                    type = number
+                    order = 2
+                    scope = "APPLICATION"
+                    description = "The width of the application window in pixels."
+                }
+
+                parameter "window.defaultHeight" {
+                    type = number
+                    order = 1
+                    scope = "APPLICATION"
+                    description = "The height of the application window in pixels."
+                }
+
+                parameter "window.telemetryId" {
+                    type = string
+                    excluded = true
+                }
+            }
+
+            configuration "moss.kernel.editor" {
+                display_name = "Editor"
+                order = 2
+
+                parameter "editor.fontSize" {
+                    type = number
+                    order = 1
+                    description = "The font size of the editor in pixels."
+                }
+            }
+            "#,
+        ));
+        registry.register_from_provider(
+            ValueProviderInfo {
+                id: "theme-defaults".to_string(),
+            },
+            evaluate(
+                r#"
+                configuration "theme.defaults" {
+                    display_name = "Theme"
+
+                    parameter "theme.accentColor" {
+                        type = string
+                        description = "The accent color of the workbench."
+                    }
+                }
+                "#,
+            ),
+        );
+
+        SettingsSearchIndex::new(&registry)
+    }
+
+    fn keys(groups: &[SearchResultGroup]) -> Vec<&str> {
+        groups
+            .iter()
+            .flat_map(|group| group.results.iter().map(|result| result.key.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            SearchQuery::parse("Font  @modified @scope:window @addon:theme-defaults"),
+            SearchQuery {
+                terms: vec!["font".to_string()],
+                modified: true,
+                scope: Some("WINDOW".to_string()),
+                addon: Some("theme-defaults".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty_query_follows_declared_order() {
+        let groups = index().search("", &SearchContext::default());
+
+        assert_eq!(
+            keys(&groups),
+            [
+                "window.defaultHeight",
+                "window.defaultWidth",
+                "editor.fontSize",
+                "theme.accentColor"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_search_is_ranked() {
+        let groups = index().search("dfwid", &SearchContext::default());
+        assert_eq!(keys(&groups), ["window.defaultWidth"]);
+
+        let groups = index().search("pixels font", &SearchContext::default());
+        assert_eq!(keys(&groups), ["editor.fontSize"]);
+
+        let groups = index().search("width", &SearchContext::default());
+        assert_eq!(keys(&groups)[0], "window.defaultWidth");
+    }
+
+    #[test]
+    fn test_filters() {
+        let index = index();
+
+        let groups = index.search("@scope:application", &SearchContext::default());
+        assert_eq!(
+            keys(&groups),
+            ["window.defaultHeight", "window.defaultWidth"]
+        );
+
+        let groups = index.search("@addon:theme-defaults", &SearchContext::default());
+        assert_eq!(keys(&groups), ["theme.accentColor"]);
+
+        let mut modified = ConfigurationModel::new();
+        modified.insert(&ArcStr::from("editor.fontSize"), JsonValue::from(18));
+        let groups = index.search(
+            "@modified",
+            &SearchContext {
+                modified: Some(&modified),
+                ..Default::default()
+            },
+        );
+        assert_eq!(keys(&groups), ["editor.fontSize"]);
+    }
+
+    #[test]
+    fn test_localized_text() {
+        let localized = collect_localized_text(&serde_json::json!({
+            "editor.fontSize": { "description": "Schriftgröße des Editors" }
+        }));
+        let groups = index().search(
+            "schriftgröße",
+            &SearchContext {
+                localized: Some(&localized),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(keys(&groups), ["editor.fontSize"]);
+    }
+}
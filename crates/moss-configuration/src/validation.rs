@@ -0,0 +1,39 @@
+use anyhow::{anyhow, Result};
+use moss_mel::foundations::{
+    configuration::Parameter,
+    typ::{TypeBoolean, TypeNumber, TypeString, Typeable},
+};
+use serde_json::Value as JsonValue;
+
+/// Checks that the value matches the type of the parameter and stays within its bounds.
+pub fn validate_value(parameter: &Parameter, value: &JsonValue) -> Result<()> {
+    match (parameter.typ.data().name, value) {
+        (TypeNumber::NAME, JsonValue::Number(number)) => {
+            let number = number.as_f64().unwrap_or_default();
+
+            if let Some(minimum) = parameter.minimum {
+                if number < minimum as f64 {
+                    return Err(anyhow!(
+                        "The value {number} is less than the minimum of {minimum}"
+                    ));
+                }
+            }
+
+            if let Some(maximum) = parameter.maximum {
+                if number > maximum as f64 {
+                    return Err(anyhow!(
+                        "The value {number} is greater than the maximum of {maximum}"
+                    ));
+                }
+            }
+
+            Ok(())
+        }
+        (TypeString::NAME, JsonValue::String(_)) | (TypeBoolean::NAME, JsonValue::Bool(_)) => {
+            Ok(())
+        }
+        (type_name, value) => Err(anyhow!(
+            "Expected a value of the type '{type_name}', found {value}"
+        )),
+    }
+}
@@ -0,0 +1,130 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, FnArg, GenericArgument, ItemFn, LitStr, PathArguments, ReturnType, Type,
+};
+
+/// Declares a typed command from its handler, which takes the command context and the
+/// arguments, and returns `Result<Output, TauriError>` or `TauriResult<Output>`:
+///
+/// ```ignore
+/// #[command_handler("workbench.changeColorTheme")]
+/// pub fn change_color_theme(ctx: CommandContext, args: ChangeColorThemeArgs) -> Result<(), TauriError> {
+///     ...
+/// }
+/// ```
+///
+/// The handler can be `async`. It is kept as is, next to a `ChangeColorThemeCommand` type
+/// implementing `TypedCommand`, which is contributed with `CommandDecl::typed`. The command
+/// is also added to `COMMAND_BINDINGS`, the `MossCommandMap` binding is generated from.
+#[proc_macro_attribute]
+pub fn command_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let id = parse_macro_input!(attr as LitStr);
+    let handler = parse_macro_input!(item as ItemFn);
+
+    match expand_command_handler(id, handler) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_command_handler(id: LitStr, handler: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let signature = &handler.sig;
+    if signature.inputs.len() != 2 {
+        return Err(syn::Error::new_spanned(
+            &signature.inputs,
+            "A command handler takes the command context and the arguments",
+        ));
+    }
+
+    let args_type = match &signature.inputs[1] {
+        FnArg::Typed(arg) => &arg.ty,
+        receiver => {
+            return Err(syn::Error::new_spanned(
+                receiver,
+                "A command handler cannot be a method",
+            ))
+        }
+    };
+    let output_type = result_ok_type(&signature.output)?;
+
+    let vis = &handler.vis;
+    let handler_name = &signature.ident;
+    let command_name = format_ident!("{}Command", to_pascal_case(&handler_name.to_string()));
+    let binding_name = format_ident!(
+        "__COMMAND_BINDING_{}__",
+        handler_name.to_string().to_uppercase()
+    );
+    let call = if signature.asyncness.is_some() {
+        quote! { #handler_name(ctx, args).await }
+    } else {
+        quote! { #handler_name(ctx, args) }
+    };
+
+    Ok(quote! {
+        #handler
+
+        #vis struct #command_name;
+
+        impl ::moss_desktop::command::TypedCommand for #command_name {
+            const ID: &'static str = #id;
+            type Args = #args_type;
+            type Output = #output_type;
+
+            fn execute(
+                ctx: ::moss_desktop::command::CommandContext,
+                args: Self::Args,
+            ) -> ::moss_desktop::command::TypedCommandFuture<Self::Output> {
+                ::std::boxed::Box::pin(async move { #call })
+            }
+        }
+
+        #[::moss_desktop::linkme::distributed_slice(::moss_desktop::command::COMMAND_BINDINGS)]
+        #[linkme(crate = ::moss_desktop::linkme)]
+        static #binding_name: ::moss_desktop::command::CommandBinding =
+            ::moss_desktop::command::CommandBinding::of::<#command_name>();
+    })
+}
+
+/// Extracts `T` from the `Result<T, TauriError>` or `TauriResult<T>` the handler returns.
+fn result_ok_type(output: &ReturnType) -> syn::Result<&Type> {
+    let error = || {
+        syn::Error::new_spanned(
+            output,
+            "A command handler must return `Result<Output, TauriError>` or `TauriResult<Output>`",
+        )
+    };
+
+    let ReturnType::Type(_, ty) = output else {
+        return Err(error());
+    };
+    let Type::Path(path) = &**ty else {
+        return Err(error());
+    };
+    let segment = path.path.segments.last().ok_or_else(error)?;
+    // `TauriResult<T>` is the alias of `Result<T, TauriError>`.
+    if segment.ident != "Result" && segment.ident != "TauriResult" {
+        return Err(error());
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return Err(error());
+    };
+
+    match arguments.args.first() {
+        Some(GenericArgument::Type(ty)) => Ok(ty),
+        _ => Err(error()),
+    }
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
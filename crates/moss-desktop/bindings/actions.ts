@@ -36,6 +36,40 @@ export type MenuItem = { "action": ActionMenuItem } | { "submenu": SubmenuMenuIt
 
 export type MenuItemVisibility = "classic" | "hidden" | "compact";
 
+export type ResolvedActionMenuItem = {
+  command: CommandAction;
+  /**
+   * Whether the toggle of the command is on, for the commands that have one.
+   */
+  toggled?: boolean;
+  visibility: MenuItemVisibility;
+};
+
+export type ResolvedMenu = {
+  namespace: string;
+  items: Array<ResolvedMenuItem>;
+  /**
+   * The submenus left out because no menu is contributed under their id.
+   */
+  danglingSubmenus: Array<string>;
+};
+
+/**
+ * A menu item as shown, once its `when` rule holds and its groups are laid out.
+ */
+export type ResolvedMenuItem =
+  | { "action": ResolvedActionMenuItem }
+  | { "submenu": ResolvedSubmenuMenuItem }
+  | "separator";
+
+export type ResolvedSubmenuMenuItem = {
+  submenuId: string;
+  defaultActionId?: string;
+  title?: LocalizedString;
+  visibility: MenuItemVisibility;
+  items: Array<ResolvedMenuItem>;
+};
+
 export type SubmenuMenuItem = {
   submenuId: string;
   defaultActionId?: string;
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeColorThemeArgs, ChangeLanguagePackArgs } from "./commands";
+
+/**
+ * The typed commands, with the types of their arguments and result.
+ */
+export type MossCommandMap = {
+  "workbench.changeColorTheme": { args: ChangeColorThemeArgs; result: null };
+  "workbench.changeLanguagePack": { args: ChangeLanguagePackArgs; result: null };
+};
@@ -0,0 +1,78 @@
+// This file was generated by misc/importer.py. Do not edit this file manually.
+//
+// The necessary import statements have been automatically added by a Python script.
+// This ensures that all required dependencies are correctly referenced and available
+// within this module.
+//
+// If you need to add or modify imports, please update the imports.json and
+// re-run `make gen-models` it to regenerate the file accordingly.
+
+import type { LocalizedString } from "@repo/moss-text";
+import type { LocaleDescriptor } from "./application";
+import type { ThemeDescriptor } from "./application";
+
+export type ChangeColorThemeArgs = { themeDescriptor: ThemeDescriptor };
+
+export type ChangeLanguagePackArgs = { localeDescriptor: LocaleDescriptor };
+
+/**
+ * A command with the arguments it is executed with.
+ */
+export type CommandCall = { command: string; args: Record<string, unknown> };
+
+/**
+ * A command as listed in the command palette.
+ */
+export type CommandDescriptor = {
+  id: string;
+  title?: LocalizedString;
+  category?: LocalizedString;
+  icon?: string;
+  argsDescription?: LocalizedString;
+  /**
+   * The JSON schema of the arguments, for the commands that declare it.
+   */
+  argsSchema?: unknown;
+  /**
+   * Whether the enablement rule of the command holds in the window.
+   */
+  enabled: boolean;
+};
+
+/**
+ * The commands that can be undone and redone in a window, the next one first.
+ */
+export type CommandHistoryDescriptor = { undo: Array<CommandCall>; redo: Array<CommandCall> };
+
+/**
+ * A command executed in a window, as kept in the log the commands can be replayed from.
+ */
+export type CommandLogEntry = {
+  /**
+   * Orders the entries, it grows with every executed command.
+   */
+  sequence: number;
+  window: string;
+  call: CommandCall;
+  /**
+   * When the command finished, in milliseconds since the Unix epoch.
+   */
+  executedAt: number;
+  /**
+   * Whether the command can be undone.
+   */
+  undoable: boolean;
+};
+
+export type CommandProgress = {
+  /**
+   * The execution of the command the progress is reported for.
+   */
+  invocationId: string;
+  command: string;
+  message?: string;
+  /**
+   * How much of the work is done, from 0 to 100.
+   */
+  percentage?: number;
+};
@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Sent with the `core://context-keys-changed` event whenever a key is set or reset.
+ */
+export type ContextKeyChange = {
+  key: string;
+  scope: ContextScope;
+  /**
+   * The value set in the scope, none when it was reset.
+   */
+  value?: unknown;
+};
+
+export type ContextKeyDescriptor = { key: string; type: ContextKeyType; default: unknown; description?: string };
+
+export type ContextKeyType = "boolean" | "number" | "string" | "array" | "object";
+
+/**
+ * Where a context key is set. The value set in a scope overrides the ones of the scopes
+ * that contain it: a view overrides its window, a window overrides the global scope.
+ */
+export type ContextScope =
+  | { kind: "global" }
+  | { kind: "window"; window: string }
+  | { kind: "view"; window: string; view: string };
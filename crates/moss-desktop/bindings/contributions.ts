@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Sent with the `core://contributions-changed` event whenever the contributions of an
+ * owner are added or removed.
+ */
+export type ContributionChange = { owner: ContributionOwner; kind: ContributionChangeKind };
+
+export type ContributionChangeKind = "added" | "removed";
+
+/**
+ * Who added a set of contributions, which are added and removed together: a module of
+ * the application, by its path, or an addon.
+ */
+export type ContributionOwner = { kind: "builtin"; module: string } | { kind: "addon"; id: string };
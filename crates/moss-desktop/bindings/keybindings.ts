@@ -0,0 +1,48 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A key pressed in a window, as reported by the `keydown` event.
+ */
+export type KeyEvent = {
+  /**
+   * The physical key, as in `KeyboardEvent.code`.
+   */
+  code: string;
+  ctrlKey: boolean;
+  shiftKey: boolean;
+  altKey: boolean;
+  metaKey: boolean;
+};
+
+export type KeybindingConflict = {
+  kind: KeybindingConflictKind;
+  key: string;
+  /**
+   * The command that is shadowed or takes precedence first, then the others.
+   */
+  commands: Array<string>;
+};
+
+export type KeybindingConflictKind = "duplicate" | "shadowedPrefix";
+
+export type KeybindingDescriptor = {
+  /**
+   * The chords of the binding, e.g. `ctrl+k ctrl+s`.
+   */
+  key: string;
+  command: string;
+  args?: unknown;
+  /**
+   * The rule the context must satisfy, in its JSON Logic form.
+   */
+  when?: unknown;
+  /**
+   * Where the binding comes from: `default`, `user` or `addon:<id>`.
+   */
+  source: string;
+};
+
+export type KeybindingResolution =
+  | { kind: "command"; command: string; args?: unknown }
+  | { kind: "pending"; chords: Array<string> }
+  | { kind: "noMatch" };
@@ -0,0 +1,39 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ServiceDescriptor = {
+  /**
+   * The type name of the service.
+   */
+  name: string;
+  state: ServiceState;
+  /**
+   * Whether the service is activated with its phase rather than on its first request.
+   */
+  instant: boolean;
+  /**
+   * The lifecycle phase an instant service is activated in.
+   */
+  activationPhase: string;
+  /**
+   * When the service was activated, in milliseconds since the Unix epoch.
+   */
+  activatedAt?: number;
+  /**
+   * How long the construction took, including the failed attempts.
+   */
+  constructionTimeMs?: number;
+  /**
+   * The number of attempts made to construct the service.
+   */
+  attempts: number;
+  /**
+   * The type names of the services this one depends on.
+   */
+  dependencies: Array<string>;
+  /**
+   * The error of the last failed construction, kept when the service runs degraded.
+   */
+  lastError?: string;
+};
+
+export type ServiceState = "pending" | "active" | "degraded" | "failed" | "disposed";
@@ -0,0 +1,132 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LocaleDescriptor } from "./application";
+import type { ThemeDescriptor } from "./application";
+
+export type RejectedSettingDescriptor = {
+  key: string;
+  /**
+   * Explains why the value cannot be imported.
+   */
+  reason: string;
+};
+
+export type SettingChangeDescriptor = {
+  key: string;
+  /**
+   * The current value, missing when the setting is added by the import.
+   */
+  oldValue?: unknown;
+  /**
+   * The imported value, missing when the setting is removed by the import.
+   */
+  newValue?: unknown;
+};
+
+export type SettingsBundle = {
+  /**
+   * Identifies the file as a Moss settings bundle, always "moss.settings".
+   */
+  format: string;
+  /**
+   * The version of the bundle format.
+   */
+  version: number;
+  /**
+   * The version of the settings for every configuration that declares migrations, used to
+   * migrate the settings exported by older releases.
+   */
+  settingsVersion: Record<string, number>;
+  /**
+   * The settings changed by the user, keyed by the parameter key.
+   */
+  settings: Record<string, unknown>;
+  /**
+   * The identifier of the selected color theme.
+   */
+  theme?: string;
+  /**
+   * The language code of the selected locale.
+   */
+  locale?: string;
+};
+
+export type SettingsImportPreview = {
+  /**
+   * Identifies the previewed import, which is applied with it.
+   */
+  token: string;
+  /**
+   * The changes to the user settings, sorted by the key.
+   */
+  changes: Array<SettingChangeDescriptor>;
+  /**
+   * Keys that are not declared by any configuration, these are not imported.
+   */
+  unknownKeys: Array<string>;
+  /**
+   * Settings whose values are not valid for their parameters, these are not imported.
+   */
+  invalidSettings: Array<RejectedSettingDescriptor>;
+  /**
+   * The color theme selected by the import.
+   */
+  theme?: ThemeDescriptor;
+  /**
+   * The locale selected by the import.
+   */
+  locale?: LocaleDescriptor;
+  /**
+   * The identifier of the theme from the bundle that is not available on this machine.
+   */
+  unknownTheme?: string;
+  /**
+   * The code of the locale from the bundle that is not available on this machine.
+   */
+  unknownLocale?: string;
+};
+
+export type SettingsSearchGroup = {
+  /**
+   * The identifier of the configuration that declares the parameters.
+   */
+  id: string;
+  /**
+   * The display name of the configuration (e.g., "Window").
+   */
+  displayName?: string;
+  results: Array<SettingsSearchResult>;
+};
+
+export type SettingsSearchResult = {
+  /**
+   * The key of the matching parameter (e.g., "window.defaultWidth").
+   */
+  key: string;
+  /**
+   * The display name of the parameter (e.g., "Default Width").
+   */
+  displayName: string;
+  /**
+   * The relevance of the match, a higher score is a better match.
+   */
+  score: number;
+};
+
+export type SettingsValues = {
+  /**
+   * The font size of the editor in pixels.
+   */
+  "editor.fontSize": number;
+  /**
+   * The height of the application window in pixels.
+   */
+  "window.defaultHeight": number;
+  /**
+   * The width of the application window in pixels.
+   */
+  "window.defaultWidth": number;
+  /**
+   * The color theme used until another one is selected.
+   */
+  "workbench.colorTheme": string;
+};
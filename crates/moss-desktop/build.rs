@@ -1,4 +1,9 @@
-use std::process::Command;
+use moss_configuration::codegen::generate_settings;
+use moss_extension_point::registry::ConfigurationRegistry;
+use moss_mel::loader::Loader;
+use std::{env, path::PathBuf, process::Command};
+
+const CONTRIBUTIONS_DIR: &str = "contributions";
 
 fn main() {
     #[cfg(target_os = "windows")]
@@ -12,4 +17,34 @@ fn main() {
         .arg("package.json")
         .status()
         .expect("Failed to execute script");
+
+    generate_settings_accessors();
+}
+
+fn generate_settings_accessors() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    let mut loader = Loader::new();
+    loader
+        .load(manifest_dir, vec![PathBuf::from(CONTRIBUTIONS_DIR)])
+        .expect("Failed to load the configuration declarations");
+
+    let mut registry = ConfigurationRegistry::default();
+    for result in loader.resolve() {
+        registry.register(
+            result
+                .expect("Failed to resolve the configuration declarations")
+                .into_values(),
+        );
+    }
+
+    let code = generate_settings(
+        &registry,
+        "crate::services::configuration_service::ConfigurationService",
+    )
+    .expect("Failed to generate the settings accessors");
+
+    std::fs::write(out_dir.join("settings.rs"), code)
+        .expect("Failed to write the settings accessors");
 }
@@ -17,6 +17,27 @@
 #     }
 # }
 
+# configuration {
+#     migrate "editor.fontSizePx" {
+#         to = "editor.fontSize"
+#         transform = value < 10 ? 10 : value
+#         version = 1
+#     }
+# }
+
+configuration "moss.kernel.workbench" {
+    display_name = "Workbench"
+    order = 1
+
+    parameter "workbench.colorTheme" {
+        type = string
+        default = "theme-defaults.MossLightDefault"
+        order = 1
+        scope = "APPLICATION"
+        description = "The color theme used until another one is selected."
+    }
+}
+
 configuration "moss.kernel.window" {
     display_name = "Window"
     order = 5
@@ -48,7 +69,7 @@ configuration "moss.kernel.window" {
         default = 14
         order = 1
         scope = "WINDOW"
-        description = "The width of the application window in pixels."
+        description = "The font size of the editor in pixels."
     }
 
     override "editor.fontSize" {
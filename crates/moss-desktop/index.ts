@@ -1,2 +1,9 @@
 export * from "./bindings/actions";
 export * from "./bindings/application";
+export * from "./bindings/command_map";
+export * from "./bindings/commands";
+export * from "./bindings/context_keys";
+export * from "./bindings/contributions";
+export * from "./bindings/keybindings";
+export * from "./bindings/services";
+export * from "./bindings/settings";
@@ -1,9 +1,13 @@
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LifecyclePhase {
+    /// The application is being set up.
     Starting = 1,
+    /// The setup is done, the `Instant` services are active and the main window is created.
     Ready = 2,
+    /// The first window has restored its state.
     Restored = 3,
+    /// The first window has been idle since it restored its state.
     Settled = 4,
 }
 
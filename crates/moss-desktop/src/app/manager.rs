@@ -1,11 +1,13 @@
 use anyhow::Result;
 use moss_extension_point::registry::Registry;
-use tauri::AppHandle;
+use std::{future::Future, time::Duration};
 
 use super::{
     instantiation::InstantiationType,
-    service::{Service, ServiceCollection, ServiceHandle},
+    lifecycle::LifecyclePhase,
+    service::{Service, ServiceCollection, ServiceError, ServiceHandle, ServiceResolver},
 };
+use crate::{models::services::ServiceDescriptor, services::lifecycle_service::LifecycleService};
 
 pub struct AppManager {
     services: ServiceCollection,
@@ -16,9 +18,9 @@ unsafe impl Send for AppManager {}
 unsafe impl Sync for AppManager {}
 
 impl AppManager {
-    pub fn new(app_handle: AppHandle, registry: Registry) -> Self {
+    pub fn new(registry: Registry) -> Self {
         Self {
-            services: ServiceCollection::new(app_handle),
+            services: ServiceCollection::new(),
             registry,
         }
     }
@@ -26,13 +28,98 @@ impl AppManager {
     pub fn with_service<T, F>(self, service: F, activation_type: InstantiationType) -> Self
     where
         T: Service + 'static,
-        F: FnOnce(&AppHandle) -> T + 'static,
+        F: FnOnce(&ServiceResolver) -> T + Send + 'static,
     {
         self.services.register(service, activation_type);
         self
     }
 
-    pub fn service<T: Service>(&self) -> Result<ServiceHandle<T>> {
+    /// Registers a service with a fallible, asynchronous constructor,
+    /// see [`ServiceCollection::register_async`].
+    pub fn with_async_service<T, F, Fut>(
+        self,
+        service: F,
+        activation_type: InstantiationType,
+    ) -> Self
+    where
+        T: Service + 'static,
+        F: Fn(ServiceResolver) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.services.register_async(service, activation_type);
+        self
+    }
+
+    /// Activates the `Instant` services once all the services are registered,
+    /// failing if a dependency is missing or the dependencies form a cycle. A service that
+    /// fails to construct is recorded as failed and reported when it is requested.
+    pub fn activate(self) -> Result<Self, ServiceError> {
+        self.services.activate()?;
+        Ok(self)
+    }
+
+    pub fn service<T: Service>(&self) -> Result<ServiceHandle<T>, ServiceError> {
         self.services.get()
     }
+
+    /// Describes the registered services, see [`ServiceCollection::describe`].
+    pub fn describe_services(&self) -> Vec<ServiceDescriptor> {
+        self.services.describe()
+    }
+
+    /// Renders the description of the services as a plain text table, one service per line.
+    pub fn services_report(&self) -> String {
+        let descriptors = self.describe_services();
+        let name_width = descriptors
+            .iter()
+            .map(|descriptor| descriptor.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("SERVICE".len());
+
+        let mut report = format!(
+            "{:<name_width$}  {:<8}  {:<8}  {:>9}  {:>8}  ERROR\n",
+            "SERVICE", "STATE", "PHASE", "CTOR (ms)", "ATTEMPTS"
+        );
+        for descriptor in descriptors {
+            report.push_str(&format!(
+                "{:<name_width$}  {:<8}  {:<8}  {:>9}  {:>8}  {}\n",
+                descriptor.name,
+                descriptor.state.as_str(),
+                if descriptor.instant {
+                    descriptor.activation_phase.as_str()
+                } else {
+                    "on use"
+                },
+                descriptor
+                    .construction_time_ms
+                    .map_or("-".to_string(), |ms| ms.to_string()),
+                descriptor.attempts,
+                descriptor.last_error.as_deref().unwrap_or("-"),
+            ));
+        }
+
+        report
+    }
+
+    /// Moves the application to the phase, activating the `Instant` services declared for it
+    /// before notifying the code waiting for the phase.
+    pub fn advance_phase(&self, phase: LifecyclePhase) -> Result<(), ServiceError> {
+        let lifecycle_service = self.service::<LifecycleService>()?;
+        self.services.activate_phase(phase);
+        lifecycle_service.set_current_phase(phase);
+
+        Ok(())
+    }
+
+    /// Waits until the application reaches the phase.
+    pub async fn when_phase(&self, phase: LifecyclePhase) -> Result<(), ServiceError> {
+        self.service::<LifecycleService>()?.when_phase(phase).await;
+        Ok(())
+    }
+
+    /// Disposes the services on application exit, see [`ServiceCollection::dispose`].
+    pub async fn dispose(&self, timeout: Duration) {
+        self.services.dispose(timeout).await
+    }
 }
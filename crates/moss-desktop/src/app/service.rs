@@ -1,22 +1,175 @@
 use anyhow::{anyhow, Result};
 use derive_more::{Deref, DerefMut};
-use fnv::FnvHashMap;
-use parking_lot::RwLock;
+use fnv::{FnvHashMap, FnvHashSet};
+use moss_tauri::ErrorCode;
+use parking_lot::{Mutex, RwLock};
+use serde_json::{json, Value};
 use std::{
     any::{Any, TypeId},
+    cell::RefCell,
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
     sync::{
         atomic::{AtomicU8, Ordering},
         Arc,
     },
+    task::Poll,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tauri::AppHandle;
+use thiserror::Error;
+use tokio::runtime::RuntimeFlavor;
 
-use super::instantiation::InstantiationType;
+use super::{instantiation::InstantiationType, lifecycle::LifecyclePhase};
+use crate::models::services::{ServiceDescriptor, ServiceState};
+
+pub type DisposeFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+type ServiceFuture = Pin<Box<dyn Future<Output = Result<Arc<dyn Service>>> + Send>>;
+
+thread_local! {
+    /// The services being activated by the current thread, including the ones activated
+    /// by the threads waiting for it.
+    static ACTIVATING: RefCell<Vec<TypeId>> = const { RefCell::new(Vec::new()) };
+}
 
 pub trait Service: Any + Send + Sync {
     fn name(&self) -> &'static str;
-    fn dispose(&self);
     fn as_any(&self) -> &dyn Any;
+
+    /// Releases the service on application exit, giving it a chance to flush what it holds.
+    /// Services are disposed in the reverse order of their activation.
+    fn dispose(&self) -> DisposeFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// The services this one gets from the [`ServiceResolver`] during its construction.
+    /// They are activated before it, in the order they are listed in.
+    fn dependencies() -> Vec<ServiceId>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// The phase an `Instant` service is activated in. A service that is requested
+    /// before its phase is activated on demand.
+    fn activation_phase() -> LifecyclePhase
+    where
+        Self: Sized,
+    {
+        LifecyclePhase::Starting
+    }
+
+    /// How many times the construction of the service is attempted before giving up on it.
+    fn retry_policy() -> RetryPolicy
+    where
+        Self: Sized,
+    {
+        RetryPolicy::default()
+    }
+
+    /// An instance with reduced functionality to fall back to once all the attempts
+    /// to construct the service have failed. Without one the service is marked as failed.
+    fn degraded(_error: &anyhow::Error) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/// How the construction of a service is retried when it fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of attempts, including the first one.
+    pub attempts: u32,
+    /// The pause between two attempts.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ServiceError {
+    #[error("The service {service} must be registered before it can be used")]
+    NotRegistered { service: &'static str },
+
+    #[error("The service {service} depends on {dependency}, which is not registered")]
+    MissingDependency {
+        service: &'static str,
+        dependency: &'static str,
+    },
+
+    #[error("The services form a dependency cycle: {}", .cycle.join(" -> "))]
+    DependencyCycle { cycle: Vec<&'static str> },
+
+    #[error("The service {service} must declare {dependency} as a dependency before resolving it")]
+    UndeclaredDependency {
+        service: &'static str,
+        dependency: &'static str,
+    },
+
+    #[error("The service {service} is already being activated, it depends on itself")]
+    Reentrant { service: &'static str },
+
+    #[error("Failed to activate {dependency}, a dependency of {service}")]
+    DependencyFailed {
+        service: &'static str,
+        dependency: &'static str,
+        #[source]
+        source: Box<ServiceError>,
+    },
+
+    #[error("Failed to construct the service {service} after {attempts} attempt(s): {reason}")]
+    ConstructionFailed {
+        service: &'static str,
+        attempts: u32,
+        reason: String,
+    },
+
+    #[error("The service {service} has already been disposed")]
+    Disposed { service: &'static str },
+
+    #[error("Failed to cast service {service} to the required type")]
+    TypeMismatch { service: &'static str },
+}
+
+impl ErrorCode for ServiceError {
+    fn code(&self) -> &'static str {
+        match self {
+            ServiceError::NotRegistered { .. } => "service.notRegistered",
+            ServiceError::MissingDependency { .. } => "service.missingDependency",
+            ServiceError::DependencyCycle { .. } => "service.dependencyCycle",
+            ServiceError::UndeclaredDependency { .. } => "service.undeclaredDependency",
+            ServiceError::Reentrant { .. } => "service.reentrant",
+            ServiceError::DependencyFailed { .. } => "service.dependencyFailed",
+            ServiceError::ConstructionFailed { .. } => "service.constructionFailed",
+            ServiceError::Disposed { .. } => "service.disposed",
+            ServiceError::TypeMismatch { .. } => "service.typeMismatch",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            ServiceError::DependencyCycle { cycle } => Some(json!({ "cycle": cycle })),
+            ServiceError::NotRegistered { service }
+            | ServiceError::MissingDependency { service, .. }
+            | ServiceError::UndeclaredDependency { service, .. }
+            | ServiceError::Reentrant { service }
+            | ServiceError::DependencyFailed { service, .. }
+            | ServiceError::ConstructionFailed { service, .. }
+            | ServiceError::Disposed { service }
+            | ServiceError::TypeMismatch { service } => Some(json!({ "service": service })),
+        }
+    }
 }
 
 impl dyn Service {
@@ -30,6 +183,25 @@ impl dyn Service {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServiceId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl ServiceId {
+    pub fn of<T: Service>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
 #[derive(Debug, Deref, DerefMut)]
 pub struct ServiceHandle<T>
 where
@@ -43,11 +215,37 @@ where
     metadata: Arc<ServiceMetadata>,
 }
 
+/// Gives a service under construction access to the services it depends on.
+/// It is owned, so an asynchronous constructor can hold it across awaits.
+pub struct ServiceResolver {
+    collection: ServiceCollection,
+    metadata: Arc<ServiceMetadata>,
+}
+
+impl ServiceResolver {
+    /// Returns a service declared in the [`Service::dependencies`] of the service under construction.
+    pub fn get<T: Service>(&self) -> Result<ServiceHandle<T>, ServiceError> {
+        let service_id = ServiceId::of::<T>();
+        if !self.metadata.dependencies.contains(&service_id) {
+            return Err(ServiceError::UndeclaredDependency {
+                service: self.metadata.service_name,
+                dependency: service_id.name,
+            });
+        }
+
+        self.collection.get::<T>()
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Eq, Hash, PartialEq)]
 enum ServiceInstantiationMode {
     Pending = 0,
     Active = 1,
+    /// Active, but with the instance returned by [`Service::degraded`].
+    Degraded = 2,
+    Failed = 3,
+    Disposed = 4,
 }
 
 impl From<u8> for ServiceInstantiationMode {
@@ -64,16 +262,6 @@ impl ServiceInstantiationMode {
     }
 }
 
-impl From<InstantiationType> for ServiceInstantiationMode {
-    #[inline]
-    fn from(value: InstantiationType) -> Self {
-        match value {
-            InstantiationType::Instant => ServiceInstantiationMode::Active,
-            InstantiationType::Delayed => ServiceInstantiationMode::Pending,
-        }
-    }
-}
-
 impl Into<AtomicU8> for ServiceInstantiationMode {
     #[inline]
     fn into(self) -> AtomicU8 {
@@ -83,11 +271,34 @@ impl Into<AtomicU8> for ServiceInstantiationMode {
 
 #[derive(Debug)]
 struct ServiceMetadata {
+    type_id: TypeId,
     service_name: &'static str,
+    instantiation_type: InstantiationType,
     instantiation_mode: AtomicU8,
+    dependencies: Vec<ServiceId>,
+    activation_phase: LifecyclePhase,
+    /// Serializes the activations of the service.
+    activation_lock: Mutex<()>,
+    last_error: Mutex<Option<ServiceError>>,
+    activation: Mutex<ActivationRecord>,
+}
+
+/// When and how the service was constructed.
+#[derive(Debug, Default, Clone, Copy)]
+struct ActivationRecord {
+    activated_at: Option<SystemTime>,
+    construction_time: Option<Duration>,
+    attempts: u32,
 }
 
 impl ServiceMetadata {
+    fn id(&self) -> ServiceId {
+        ServiceId {
+            type_id: self.type_id,
+            name: self.service_name,
+        }
+    }
+
     fn set_instantiation_mode(&self, mode: ServiceInstantiationMode) {
         self.instantiation_mode
             .store(mode.as_u8(), Ordering::SeqCst);
@@ -96,141 +307,539 @@ impl ServiceMetadata {
     fn get_instantiation_mode(&self) -> ServiceInstantiationMode {
         ServiceInstantiationMode::from(self.instantiation_mode.load(Ordering::SeqCst))
     }
+
+    fn describe(&self) -> ServiceDescriptor {
+        let activation = *self.activation.lock();
+
+        ServiceDescriptor {
+            name: self.service_name.to_string(),
+            state: match self.get_instantiation_mode() {
+                ServiceInstantiationMode::Pending => ServiceState::Pending,
+                ServiceInstantiationMode::Active => ServiceState::Active,
+                ServiceInstantiationMode::Degraded => ServiceState::Degraded,
+                ServiceInstantiationMode::Failed => ServiceState::Failed,
+                ServiceInstantiationMode::Disposed => ServiceState::Disposed,
+            },
+            instant: self.instantiation_type == InstantiationType::Instant,
+            activation_phase: format!("{:?}", self.activation_phase),
+            activated_at: activation
+                .activated_at
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_millis() as u64),
+            construction_time_ms: activation
+                .construction_time
+                .map(|duration| duration.as_millis() as u64),
+            attempts: activation.attempts,
+            dependencies: self
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.name.to_string())
+                .collect(),
+            last_error: self
+                .last_error
+                .lock()
+                .as_ref()
+                .map(|err| format!("{:#}", anyhow::Error::from(err.clone()))),
+        }
+    }
+
+    fn fail(&self, error: ServiceError) -> ServiceError {
+        error!(
+            "Failed to activate service {}: {error:#}",
+            self.service_name
+        );
+
+        *self.last_error.lock() = Some(error.clone());
+        self.set_instantiation_mode(ServiceInstantiationMode::Failed);
+
+        error
+    }
+}
+
+struct ServiceConstructor {
+    create: Box<dyn Fn(ServiceResolver) -> ServiceFuture + Send + Sync>,
+    degraded: fn(&anyhow::Error) -> Option<Arc<dyn Service>>,
+    retry_policy: RetryPolicy,
+}
+
+fn degraded_service<T: Service>(error: &anyhow::Error) -> Option<Arc<dyn Service>> {
+    T::degraded(error).map(|service| Arc::new(service) as Arc<dyn Service>)
 }
 
+#[derive(Default)]
 struct ServiceCollectionState {
     services: FnvHashMap<TypeId, Arc<dyn Service>>,
-    pending_services: FnvHashMap<TypeId, Box<dyn FnOnce(&AppHandle) -> Arc<dyn Service>>>,
+    pending_services: FnvHashMap<TypeId, Arc<ServiceConstructor>>,
     known_services: FnvHashMap<TypeId, Arc<ServiceMetadata>>,
+    /// The registered services, in the order of registration.
+    registration_order: Vec<TypeId>,
+    /// The activated services, in the order of activation.
+    activation_order: Vec<TypeId>,
+    /// The phase the `Instant` services were activated up to, `None` before the activation.
+    phase: Option<LifecyclePhase>,
 }
 
-impl Default for ServiceCollectionState {
-    fn default() -> Self {
-        Self {
-            services: Default::default(),
-            pending_services: Default::default(),
-            known_services: Default::default(),
-        }
-    }
+#[derive(Default)]
+struct ServiceCollectionInner {
+    state: RwLock<ServiceCollectionState>,
 }
 
+/// The registered services. Cloning it is cheap, the clones share the services.
+#[derive(Clone, Default)]
 pub struct ServiceCollection {
-    app_handle: AppHandle,
-    state: RwLock<ServiceCollectionState>,
+    inner: Arc<ServiceCollectionInner>,
 }
 
 impl ServiceCollection {
-    pub fn new(app_handle: AppHandle) -> Self {
-        Self {
-            app_handle,
-            state: RwLock::new(ServiceCollectionState::default()),
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 
+    /// Registers a service with a one-shot constructor. As the constructor cannot run again,
+    /// the service is constructed in a single attempt whatever its [`Service::retry_policy`].
     pub fn register<T, F>(&self, creation_fn: F, activation_type: InstantiationType)
     where
         T: Service + 'static,
-        F: FnOnce(&AppHandle) -> T + 'static,
+        F: FnOnce(&ServiceResolver) -> T + Send + 'static,
     {
+        let service_name = std::any::type_name::<T>();
+        if T::retry_policy().attempts > 1 {
+            warn!(
+                "The service {service_name} has a one-shot constructor, its retry policy is ignored"
+            );
+        }
+
+        let creation_fn = Mutex::new(Some(creation_fn));
+        let constructor = ServiceConstructor {
+            create: Box::new(move |resolver| {
+                // A single attempt is made, so the constructor is always there.
+                let service = creation_fn.lock().take().map(|f| f(&resolver));
+                Box::pin(async move {
+                    service
+                        .map(|service| Arc::new(service) as Arc<dyn Service>)
+                        .ok_or_else(|| {
+                            anyhow!("The constructor of {service_name} cannot run more than once")
+                        })
+                })
+            }),
+            degraded: degraded_service::<T>,
+            retry_policy: RetryPolicy::default(),
+        };
+
+        self.insert_constructor::<T>(constructor, activation_type);
+    }
+
+    /// Registers a service with a fallible, asynchronous constructor. The constructor runs
+    /// outside of the collection lock, and is retried according to [`Service::retry_policy`].
+    pub fn register_async<T, F, Fut>(&self, creation_fn: F, activation_type: InstantiationType)
+    where
+        T: Service + 'static,
+        F: Fn(ServiceResolver) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let constructor = ServiceConstructor {
+            create: Box::new(move |resolver| {
+                let future = creation_fn(resolver);
+                Box::pin(async move { Ok(Arc::new(future.await?) as Arc<dyn Service>) })
+            }),
+            degraded: degraded_service::<T>,
+            retry_policy: T::retry_policy(),
+        };
+
+        self.insert_constructor::<T>(constructor, activation_type);
+    }
+
+    fn insert_constructor<T: Service>(
+        &self,
+        constructor: ServiceConstructor,
+        activation_type: InstantiationType,
+    ) {
         let type_id = TypeId::of::<T>();
         let service_name = std::any::type_name::<T>();
-        let mut state_lock = self.state.write();
 
-        match activation_type {
-            InstantiationType::Instant => {
-                let service = creation_fn(&self.app_handle);
+        let activate_now = {
+            let mut state_lock = self.inner.state.write();
 
-                state_lock
-                    .services
-                    .insert(type_id.clone(), Arc::new(service));
+            let metadata = Arc::new(ServiceMetadata {
+                type_id,
+                service_name,
+                instantiation_type: activation_type,
+                instantiation_mode: ServiceInstantiationMode::Pending.into(),
+                dependencies: T::dependencies(),
+                activation_phase: T::activation_phase(),
+                activation_lock: Mutex::new(()),
+                last_error: Mutex::new(None),
+                activation: Mutex::new(ActivationRecord::default()),
+            });
+            state_lock
+                .pending_services
+                .insert(type_id, Arc::new(constructor));
+            state_lock
+                .known_services
+                .insert(type_id, Arc::clone(&metadata));
+            state_lock.registration_order.push(type_id);
 
-                debug!("Service {service_name} was activated");
+            // The dependencies of the services registered before the activation are checked
+            // by `activate`, the ones of the services registered after it are checked here.
+            let checked = match state_lock.phase {
+                Some(_) => check_service_dependencies(
+                    &state_lock.known_services,
+                    type_id,
+                    &mut FnvHashSet::default(),
+                )
+                .map_err(|err| metadata.fail(err))
+                .is_ok(),
+                None => true,
+            };
+
+            checked
+                && activation_type == InstantiationType::Instant
+                && state_lock
+                    .phase
+                    .is_some_and(|phase| T::activation_phase() <= phase)
+        };
+
+        // Services registered after their phase has been reached are activated right away.
+        // A failure is recorded on the service, so it is not reported again here.
+        if activate_now {
+            let _ = self.get_internal(ServiceId {
+                type_id,
+                name: service_name,
+            });
+        }
+    }
+
+    /// Checks the dependencies of the registered services and activates the `Instant` ones
+    /// of the `Starting` phase, each after its dependencies. A service that fails to activate
+    /// is recorded as failed, without preventing the others from being activated.
+    pub fn activate(&self) -> Result<(), ServiceError> {
+        self.check_dependencies()?;
+        self.activate_phase(LifecyclePhase::Starting);
+
+        Ok(())
+    }
+
+    /// Activates the `Instant` services of the phases up to the given one. A service that
+    /// fails to activate does not prevent the others from being activated.
+    pub fn activate_phase(&self, phase: LifecyclePhase) {
+        for service_id in self.advance_phase(phase) {
+            // The failure is already logged and recorded on the service.
+            let _ = self.get_internal(service_id);
+        }
+    }
+
+    /// Records the phase, returning the `Instant` services to activate in it.
+    fn advance_phase(&self, phase: LifecyclePhase) -> Vec<ServiceId> {
+        let mut state_lock = self.inner.state.write();
+        state_lock.phase = Some(phase);
+
+        state_lock
+            .registration_order
+            .iter()
+            .map(|type_id| &state_lock.known_services[type_id])
+            .filter(|metadata| {
+                metadata.instantiation_type == InstantiationType::Instant
+                    && metadata.activation_phase <= phase
+                    && metadata.get_instantiation_mode() == ServiceInstantiationMode::Pending
+            })
+            .map(|metadata| metadata.id())
+            .collect()
+    }
+
+    fn check_dependencies(&self) -> Result<(), ServiceError> {
+        let state_lock = self.inner.state.read();
+
+        let mut visited = FnvHashSet::default();
+        for type_id in &state_lock.registration_order {
+            check_service_dependencies(&state_lock.known_services, *type_id, &mut visited)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_internal(&self, service_id: ServiceId) -> Result<Arc<dyn Service>, ServiceError> {
+        let service_metadata = self.metadata(service_id)?;
+        if let Some(service) = self.get_activated(&service_metadata)? {
+            return Ok(service);
+        }
+
+        // A service requested by its own constructor, directly or through other services,
+        // would wait for itself.
+        if ACTIVATING.with_borrow(|activating| activating.contains(&service_metadata.type_id)) {
+            return Err(ServiceError::Reentrant {
+                service: service_metadata.service_name,
+            });
+        }
+
+        // Only the activations of the same service wait for each other.
+        let _activation_lock = service_metadata.activation_lock.lock();
+
+        // The service could have been activated while waiting for the lock.
+        if let Some(service) = self.get_activated(&service_metadata)? {
+            return Ok(service);
+        }
+
+        ACTIVATING.with_borrow_mut(|activating| activating.push(service_metadata.type_id));
+        let result = self.construct(&service_metadata);
+        ACTIVATING.with_borrow_mut(|activating| activating.pop());
+
+        result
+    }
+
+    /// Activates the dependencies of the service, then constructs it. The state lock
+    /// is not held while the constructor runs.
+    fn construct(
+        &self,
+        service_metadata: &Arc<ServiceMetadata>,
+    ) -> Result<Arc<dyn Service>, ServiceError> {
+        let type_id = service_metadata.type_id;
+
+        for dependency in &service_metadata.dependencies {
+            if let Err(err) = self.get_internal(*dependency) {
+                return Err(service_metadata.fail(ServiceError::DependencyFailed {
+                    service: service_metadata.service_name,
+                    dependency: dependency.name,
+                    source: Box::new(err),
+                }));
             }
-            InstantiationType::Delayed => {
-                state_lock.pending_services.insert(
-                    type_id.clone(),
-                    Box::new(move |app_handle| {
-                        let service = creation_fn(app_handle);
-
-                        Arc::new(service)
-                    }),
-                );
+        }
+
+        let constructor = self
+            .inner
+            .state
+            .read()
+            .pending_services
+            .get(&type_id)
+            .cloned()
+            .ok_or(ServiceError::Reentrant {
+                service: service_metadata.service_name,
+            })?;
+
+        let attempts = constructor.retry_policy.attempts.max(1);
+        let started_at = Instant::now();
+        let result = self.run_attempts(service_metadata, &constructor, attempts);
+        service_metadata.activation.lock().construction_time = Some(started_at.elapsed());
+
+        let error = match result {
+            Ok(service) => {
+                return Ok(self.activated(
+                    service_metadata,
+                    service,
+                    ServiceInstantiationMode::Active,
+                ));
             }
+            Err(error) => error,
+        };
+
+        if let Some(service) = (constructor.degraded)(&error) {
+            warn!(
+                "Service {} runs in a degraded mode: {error:#}",
+                service_metadata.service_name
+            );
+            *service_metadata.last_error.lock() = Some(ServiceError::ConstructionFailed {
+                service: service_metadata.service_name,
+                attempts,
+                reason: format!("{error:#}"),
+            });
+
+            return Ok(self.activated(
+                service_metadata,
+                service,
+                ServiceInstantiationMode::Degraded,
+            ));
         }
 
-        state_lock.known_services.insert(
-            type_id,
-            Arc::new(ServiceMetadata {
-                service_name,
-                instantiation_mode: ServiceInstantiationMode::from(activation_type).into(),
-            }),
-        );
+        Err(service_metadata.fail(ServiceError::ConstructionFailed {
+            service: service_metadata.service_name,
+            attempts,
+            reason: format!("{error:#}"),
+        }))
     }
 
-    fn get_internal(
+    /// Runs the attempts to construct the service on a thread of their own, where the
+    /// asynchronous constructors can be driven to completion. The caller blocks until they
+    /// are over, delays between the attempts included; on a worker of the async runtime,
+    /// its other tasks are handed over to another worker meanwhile.
+    /// The thread knows the services being activated by the caller, to detect reentrancy.
+    fn run_attempts(
         &self,
-        service_metadata: Arc<ServiceMetadata>,
-        type_id: TypeId,
+        service_metadata: &Arc<ServiceMetadata>,
+        constructor: &ServiceConstructor,
+        attempts: u32,
     ) -> Result<Arc<dyn Service>> {
-        match service_metadata.get_instantiation_mode() {
-            ServiceInstantiationMode::Active => {
-                let state_lock = self.state.write();
-                let service = state_lock.services.get(&type_id).ok_or_else(|| {
-                    anyhow!(
-                        "The service {} was not found among the activated ones",
+        let activating = ACTIVATING.with_borrow(Clone::clone);
+        let wait = || {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(move || {
+                        ACTIVATING.set(activating);
+                        tauri::async_runtime::block_on(self.attempt_construction(
+                            service_metadata,
+                            constructor,
+                            attempts,
+                        ))
+                    })
+                    .join()
+                    .unwrap_or_else(|payload| Err(panic_error(payload)))
+            })
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(wait)
+            }
+            _ => wait(),
+        }
+    }
+
+    async fn attempt_construction(
+        &self,
+        service_metadata: &Arc<ServiceMetadata>,
+        constructor: &ServiceConstructor,
+        attempts: u32,
+    ) -> Result<Arc<dyn Service>> {
+        let mut last_error = None;
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                tokio::time::sleep(constructor.retry_policy.delay).await;
+            }
+            service_metadata.activation.lock().attempts = attempt;
+
+            let resolver = ServiceResolver {
+                collection: self.clone(),
+                metadata: Arc::clone(service_metadata),
+            };
+            match run_constructor(constructor, resolver).await {
+                Ok(service) => return Ok(service),
+                Err(err) => {
+                    warn!(
+                        "Attempt {attempt}/{attempts} to construct service {} failed: {err:#}",
                         service_metadata.service_name
-                    )
-                })?;
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.expect("at least one attempt is made"))
+    }
+
+    fn activated(
+        &self,
+        service_metadata: &ServiceMetadata,
+        service: Arc<dyn Service>,
+        mode: ServiceInstantiationMode,
+    ) -> Arc<dyn Service> {
+        let type_id = service_metadata.type_id;
+        {
+            let mut state_lock = self.inner.state.write();
+            state_lock.pending_services.remove(&type_id);
+            state_lock.services.insert(type_id, Arc::clone(&service));
+            state_lock.activation_order.push(type_id);
+        }
+        service_metadata.activation.lock().activated_at = Some(SystemTime::now());
+        service_metadata.set_instantiation_mode(mode);
+        debug!("Service {} was activated", service_metadata.service_name);
+
+        service
+    }
 
-                Ok(Arc::clone(service))
+    /// Returns the service if it was already activated, `None` if it is pending.
+    fn get_activated(
+        &self,
+        service_metadata: &ServiceMetadata,
+    ) -> Result<Option<Arc<dyn Service>>, ServiceError> {
+        match service_metadata.get_instantiation_mode() {
+            ServiceInstantiationMode::Pending => Ok(None),
+            ServiceInstantiationMode::Active | ServiceInstantiationMode::Degraded => Ok(self
+                .inner
+                .state
+                .read()
+                .services
+                .get(&service_metadata.type_id)
+                .cloned()),
+            ServiceInstantiationMode::Failed => {
+                Err(service_metadata.last_error.lock().clone().unwrap_or(
+                    ServiceError::ConstructionFailed {
+                        service: service_metadata.service_name,
+                        attempts: 0,
+                        reason: "unknown".to_string(),
+                    },
+                ))
             }
-            ServiceInstantiationMode::Pending => {
-                let mut state_lock = self.state.write();
-                let creation_fn =
-                    state_lock
-                        .pending_services
-                        .remove(&type_id)
-                        .ok_or_else(|| {
-                            anyhow!(
-                                "The service {} was not found among those awaiting activation",
-                                service_metadata.service_name
-                            )
-                        })?;
-
-                let service = creation_fn(&self.app_handle);
-
-                state_lock.services.insert(type_id, Arc::clone(&service));
-                service_metadata.set_instantiation_mode(ServiceInstantiationMode::Active);
-                debug!(
-                    "Service {} was activated upon request",
-                    service_metadata.service_name
-                );
-
-                Ok(service)
+            ServiceInstantiationMode::Disposed => Err(ServiceError::Disposed {
+                service: service_metadata.service_name,
+            }),
+        }
+    }
+
+    /// Disposes the activated services in the reverse order of their activation, giving each
+    /// of them up to `timeout` to finish. The services are no longer available afterwards.
+    pub async fn dispose(&self, timeout: Duration) {
+        let services = {
+            let mut state_lock = self.inner.state.write();
+            let activation_order = std::mem::take(&mut state_lock.activation_order);
+
+            activation_order
+                .into_iter()
+                .rev()
+                .filter_map(|type_id| {
+                    let service = state_lock.services.remove(&type_id)?;
+                    let metadata = Arc::clone(&state_lock.known_services[&type_id]);
+                    Some((service, metadata))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (service, metadata) in services {
+            metadata.set_instantiation_mode(ServiceInstantiationMode::Disposed);
+
+            let started_at = Instant::now();
+            match tokio::time::timeout(timeout, service.dispose()).await {
+                Ok(Ok(())) => debug!(
+                    "Service {} was disposed in {:?}",
+                    metadata.service_name,
+                    started_at.elapsed()
+                ),
+                Ok(Err(err)) => error!(
+                    "Failed to dispose service {}: {err:#}",
+                    metadata.service_name
+                ),
+                Err(_) => warn!(
+                    "Service {} was not disposed within {timeout:?}",
+                    metadata.service_name
+                ),
             }
         }
     }
 
-    pub fn get<T: Service>(&self) -> Result<ServiceHandle<T>> {
-        let type_id = TypeId::of::<T>();
-        let service_metadata = self
+    /// Describes the registered services, in the order of registration.
+    pub fn describe(&self) -> Vec<ServiceDescriptor> {
+        let state_lock = self.inner.state.read();
+
+        state_lock
+            .registration_order
+            .iter()
+            .map(|type_id| state_lock.known_services[type_id].describe())
+            .collect()
+    }
+
+    fn metadata(&self, service_id: ServiceId) -> Result<Arc<ServiceMetadata>, ServiceError> {
+        self.inner
             .state
-            .write()
+            .read()
             .known_services
-            .get(&type_id)
+            .get(&service_id.type_id)
             .cloned()
-            .ok_or_else(|| {
-                anyhow!(
-                    "The service {} must be registered before it can be used",
-                    std::any::type_name::<T>()
-                )
-            })?;
+            .ok_or(ServiceError::NotRegistered {
+                service: service_id.name,
+            })
+    }
 
-        // A panic here is likely an indicator of a bug in the program because if
-        // the service was "known", this panic should not occur in this location.
-        let any_service = self
-            .get_internal(Arc::clone(&service_metadata), type_id)
-            .unwrap();
+    pub fn get<T: Service>(&self) -> Result<ServiceHandle<T>, ServiceError> {
+        let service_id = ServiceId::of::<T>();
+        let service_metadata = self.metadata(service_id)?;
+        let any_service = self.get_internal(service_id)?;
 
         if let Ok(service) = any_service.downcast_arc::<T>() {
             Ok(ServiceHandle {
@@ -238,44 +847,491 @@ impl ServiceCollection {
                 metadata: service_metadata,
             })
         } else {
-            Err(anyhow!(
-                "Failed to cast service {} to the required type",
-                service_metadata.service_name,
-            ))
+            Err(ServiceError::TypeMismatch {
+                service: service_metadata.service_name,
+            })
         }
     }
 
     pub fn get_unchecked<T: Service>(&self) -> ServiceHandle<T> {
-        let type_id = TypeId::of::<T>();
-        let service_metadata = self
-            .state
-            .write()
-            .known_services
-            .get(&type_id)
-            .cloned()
-            .unwrap_or_else(|| {
-                panic!(
-                    "The service {} must be registered before it can be used",
-                    std::any::type_name::<T>()
-                )
-            });
-
-        // A panic here is likely an indicator of a bug in the program because if the service was
-        // known and activated, this panic should not occur in this location.
-        let any_service = self
-            .get_internal(Arc::clone(&service_metadata), type_id)
-            .unwrap();
-
-        let service = any_service.downcast_arc::<T>().unwrap_or_else(|_| {
+        self.get::<T>().unwrap_or_else(|err| {
             panic!(
-                "Failed to cast service {} to the required type",
-                service_metadata.service_name,
+                "The service {} must be available: {err:#}",
+                std::any::type_name::<T>()
             )
+        })
+    }
+}
+
+/// Runs one attempt of the constructor to completion, turning a panic into an error,
+/// so that a broken service does not take the application down.
+async fn run_constructor(
+    constructor: &ServiceConstructor,
+    resolver: ServiceResolver,
+) -> Result<Arc<dyn Service>> {
+    let mut future =
+        match std::panic::catch_unwind(AssertUnwindSafe(|| (constructor.create)(resolver))) {
+            Ok(future) => future,
+            Err(payload) => return Err(panic_error(payload)),
+        };
+
+    std::future::poll_fn(|cx| {
+        match std::panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(poll) => poll,
+            Err(payload) => Poll::Ready(Err(panic_error(payload))),
+        }
+    })
+    .await
+}
+
+fn panic_error(payload: Box<dyn Any + Send>) -> anyhow::Error {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+
+    anyhow!("The constructor panicked: {message}")
+}
+
+/// Checks that the dependencies of the service are registered and do not form a cycle.
+/// The services in `visited` are already known not to be part of a cycle.
+fn check_service_dependencies(
+    known_services: &FnvHashMap<TypeId, Arc<ServiceMetadata>>,
+    type_id: TypeId,
+    visited: &mut FnvHashSet<TypeId>,
+) -> Result<(), ServiceError> {
+    let metadata = &known_services[&type_id];
+    if let Some(missing) = metadata
+        .dependencies
+        .iter()
+        .find(|dependency| !known_services.contains_key(&dependency.type_id))
+    {
+        return Err(ServiceError::MissingDependency {
+            service: metadata.service_name,
+            dependency: missing.name,
         });
+    }
+
+    find_cycle(known_services, type_id, visited, &mut Vec::new())
+}
+
+/// Walks the dependencies of the service depth-first, failing on the first cycle.
+fn find_cycle(
+    known_services: &FnvHashMap<TypeId, Arc<ServiceMetadata>>,
+    type_id: TypeId,
+    visited: &mut FnvHashSet<TypeId>,
+    path: &mut Vec<TypeId>,
+) -> Result<(), ServiceError> {
+    if let Some(position) = path.iter().position(|id| *id == type_id) {
+        let cycle = path[position..]
+            .iter()
+            .chain(std::iter::once(&type_id))
+            .map(|id| known_services[id].service_name)
+            .collect::<Vec<_>>();
+
+        return Err(ServiceError::DependencyCycle { cycle });
+    }
+
+    if !visited.insert(type_id) {
+        return Ok(());
+    }
 
-        ServiceHandle {
-            service,
-            metadata: service_metadata,
+    path.push(type_id);
+    // A dependency that is not registered is reported by the check of its dependent.
+    for dependency in &known_services[&type_id].dependencies {
+        if known_services.contains_key(&dependency.type_id) {
+            find_cycle(known_services, dependency.type_id, visited, path)?;
         }
     }
+    path.pop();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type ActivationLog = Arc<Mutex<Vec<&'static str>>>;
+
+    macro_rules! test_service {
+        ($name:ident $(, $dependency:ident)*) => {
+            struct $name;
+
+            impl Service for $name {
+                fn name(&self) -> &'static str {
+                    stringify!($name)
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn dependencies() -> Vec<ServiceId> {
+                    vec![$(ServiceId::of::<$dependency>()),*]
+                }
+            }
+        };
+    }
+
+    test_service!(Storage);
+    test_service!(Settings, Storage);
+    test_service!(Themes, Settings);
+    test_service!(Orphan, Settings);
+    test_service!(Ping, Pong);
+    test_service!(Pong, Ping);
+    test_service!(Recursive);
+
+    fn register_logged<T: Service>(
+        collection: &ServiceCollection,
+        log: &ActivationLog,
+        service: T,
+        activation_type: InstantiationType,
+    ) {
+        let log = Arc::clone(log);
+        collection.register(
+            move |_| {
+                log.lock().push(service.name());
+                service
+            },
+            activation_type,
+        );
+    }
+
+    #[test]
+    fn test_activation_follows_dependencies() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        register_logged(&collection, &log, Themes, InstantiationType::Instant);
+        register_logged(&collection, &log, Settings, InstantiationType::Instant);
+        register_logged(&collection, &log, Storage, InstantiationType::Instant);
+
+        collection.activate().unwrap();
+
+        assert_eq!(*log.lock(), vec!["Storage", "Settings", "Themes"]);
+    }
+
+    #[test]
+    fn test_delayed_dependencies_are_activated_on_demand() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        register_logged(&collection, &log, Storage, InstantiationType::Delayed);
+        register_logged(&collection, &log, Settings, InstantiationType::Delayed);
+        register_logged(&collection, &log, Themes, InstantiationType::Delayed);
+
+        collection.activate().unwrap();
+        assert!(log.lock().is_empty());
+
+        collection.get::<Settings>().unwrap();
+        assert_eq!(*log.lock(), vec!["Storage", "Settings"]);
+    }
+
+    #[test]
+    fn test_missing_dependency() {
+        let collection = ServiceCollection::new();
+        collection.register(|_| Storage, InstantiationType::Instant);
+        collection.register(|_| Themes, InstantiationType::Instant);
+
+        assert!(matches!(
+            collection.activate(),
+            Err(ServiceError::MissingDependency {
+                service,
+                dependency,
+            }) if service == std::any::type_name::<Themes>()
+                && dependency == std::any::type_name::<Settings>()
+        ));
+    }
+
+    #[test]
+    fn test_missing_dependency_of_late_registration() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        collection.activate().unwrap();
+
+        register_logged(&collection, &log, Orphan, InstantiationType::Instant);
+
+        assert!(log.lock().is_empty());
+        assert!(matches!(
+            collection.get::<Orphan>(),
+            Err(ServiceError::MissingDependency { .. })
+        ));
+    }
+
+    #[test]
+    fn test_dependency_cycle() {
+        let collection = ServiceCollection::new();
+        collection.register(|_| Ping, InstantiationType::Delayed);
+        collection.register(|_| Pong, InstantiationType::Delayed);
+
+        match collection.activate() {
+            Err(ServiceError::DependencyCycle { cycle }) => assert_eq!(
+                cycle,
+                vec![
+                    std::any::type_name::<Ping>(),
+                    std::any::type_name::<Pong>(),
+                    std::any::type_name::<Ping>(),
+                ]
+            ),
+            other => panic!("Expected a dependency cycle, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_late_registration_is_activated() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        register_logged(&collection, &log, Storage, InstantiationType::Delayed);
+        collection.activate().unwrap();
+
+        register_logged(&collection, &log, Settings, InstantiationType::Instant);
+
+        assert_eq!(*log.lock(), vec!["Storage", "Settings"]);
+    }
+
+    #[test]
+    fn test_undeclared_dependency() {
+        let collection = ServiceCollection::new();
+        let resolved = Arc::new(Mutex::new(None));
+        collection.register(|_| Storage, InstantiationType::Instant);
+        collection.register(|_| Settings, InstantiationType::Instant);
+        collection.register(
+            {
+                let resolved = Arc::clone(&resolved);
+                move |resolver| {
+                    // `Themes` only declares `Settings`.
+                    *resolved.lock() = Some(resolver.get::<Storage>().err());
+                    Themes
+                }
+            },
+            InstantiationType::Instant,
+        );
+
+        collection.activate().unwrap();
+
+        assert!(matches!(
+            resolved.lock().take(),
+            Some(Some(ServiceError::UndeclaredDependency { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_reentrant_activation() {
+        let collection = ServiceCollection::new();
+        let resolved = Arc::new(Mutex::new(None));
+        collection.register(
+            {
+                let collection = collection.clone();
+                let resolved = Arc::clone(&resolved);
+                move |_| {
+                    *resolved.lock() = Some(collection.get::<Recursive>().err());
+                    Recursive
+                }
+            },
+            InstantiationType::Delayed,
+        );
+
+        collection.get::<Recursive>().unwrap();
+
+        assert!(matches!(
+            resolved.lock().take(),
+            Some(Some(ServiceError::Reentrant { .. }))
+        ));
+    }
+
+    /// Logs its disposal, which takes `delay`.
+    struct Disposable<const ID: u8> {
+        log: ActivationLog,
+        delay: Duration,
+    }
+
+    impl<const ID: u8> Service for Disposable<ID> {
+        fn name(&self) -> &'static str {
+            std::any::type_name::<Self>()
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn dispose(&self) -> DisposeFuture<'_> {
+            Box::pin(async move {
+                tokio::time::sleep(self.delay).await;
+                self.log.lock().push(self.name());
+
+                Ok(())
+            })
+        }
+    }
+
+    fn register_disposable<const ID: u8>(
+        collection: &ServiceCollection,
+        log: &ActivationLog,
+        delay: Duration,
+    ) {
+        let log = Arc::clone(log);
+        collection.register(
+            move |_| Disposable::<ID> { log, delay },
+            InstantiationType::Delayed,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispose_in_reverse_activation_order() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        register_disposable::<1>(&collection, &log, Duration::ZERO);
+        register_disposable::<2>(&collection, &log, Duration::ZERO);
+        register_disposable::<3>(&collection, &log, Duration::ZERO);
+        collection.activate().unwrap();
+
+        collection.get::<Disposable<2>>().unwrap();
+        collection.get::<Disposable<3>>().unwrap();
+        collection.get::<Disposable<1>>().unwrap();
+        collection.dispose(Duration::from_secs(1)).await;
+
+        assert_eq!(
+            *log.lock(),
+            vec![
+                std::any::type_name::<Disposable<1>>(),
+                std::any::type_name::<Disposable<3>>(),
+                std::any::type_name::<Disposable<2>>(),
+            ]
+        );
+        assert!(matches!(
+            collection.get::<Disposable<1>>(),
+            Err(ServiceError::Disposed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dispose_timeout_does_not_block_the_others() {
+        let collection = ServiceCollection::new();
+        let log = ActivationLog::default();
+        register_disposable::<1>(&collection, &log, Duration::ZERO);
+        register_disposable::<2>(&collection, &log, Duration::from_secs(60));
+        collection.activate().unwrap();
+
+        collection.get::<Disposable<1>>().unwrap();
+        collection.get::<Disposable<2>>().unwrap();
+        let started_at = Instant::now();
+        collection.dispose(Duration::from_millis(50)).await;
+
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+        assert_eq!(*log.lock(), vec![std::any::type_name::<Disposable<1>>()]);
+    }
+
+    /// Fails to construct until its last attempt, then falls back to a degraded instance.
+    struct Flaky {
+        degraded: bool,
+    }
+
+    impl Service for Flaky {
+        fn name(&self) -> &'static str {
+            "Flaky"
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn retry_policy() -> RetryPolicy {
+            RetryPolicy {
+                attempts: 3,
+                delay: Duration::from_millis(10),
+            }
+        }
+
+        fn degraded(_error: &anyhow::Error) -> Option<Self> {
+            Some(Flaky { degraded: true })
+        }
+    }
+
+    fn register_flaky(collection: &ServiceCollection, failures: u32) -> Arc<AtomicU8> {
+        let calls = Arc::new(AtomicU8::new(0));
+        collection.register_async(
+            {
+                let calls = Arc::clone(&calls);
+                move |_| {
+                    let call = calls.fetch_add(1, Ordering::SeqCst) as u32 + 1;
+                    async move {
+                        if call <= failures {
+                            return Err(anyhow!("Attempt {call} failed"));
+                        }
+                        Ok(Flaky { degraded: false })
+                    }
+                }
+            },
+            InstantiationType::Delayed,
+        );
+
+        calls
+    }
+
+    #[test]
+    fn test_retry_then_success() {
+        let collection = ServiceCollection::new();
+        let calls = register_flaky(&collection, 2);
+
+        let service = collection.get::<Flaky>().unwrap();
+
+        assert!(!service.degraded);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(collection.describe()[0].attempts, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_retry_on_a_runtime_worker() {
+        let collection = ServiceCollection::new();
+        let calls = register_flaky(&collection, 1);
+
+        let service = collection.get::<Flaky>().unwrap();
+
+        assert!(!service.degraded);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_degraded_fallback() {
+        let collection = ServiceCollection::new();
+        let calls = register_flaky(&collection, 3);
+
+        let service = collection.get::<Flaky>().unwrap();
+
+        assert!(service.degraded);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(collection.describe()[0]
+            .last_error
+            .as_deref()
+            .is_some_and(|error| error.contains("Attempt 3 failed")));
+    }
+
+    fn unavailable<T>(what: &str) -> T {
+        panic!("The {what} is unavailable")
+    }
+
+    #[test]
+    fn test_panicking_constructor() {
+        let collection = ServiceCollection::new();
+        collection.register(
+            |_| unavailable::<Storage>("storage"),
+            InstantiationType::Delayed,
+        );
+        collection.register_async(
+            |_| async { Ok(unavailable::<Recursive>("recursion")) },
+            InstantiationType::Delayed,
+        );
+
+        // The panic is reported rather than the one-shot constructor having been consumed.
+        assert!(matches!(
+            collection.get::<Storage>(),
+            Err(ServiceError::ConstructionFailed { attempts: 1, reason, .. })
+                if reason.contains("The storage is unavailable")
+        ));
+        assert!(matches!(
+            collection.get::<Recursive>(),
+            Err(ServiceError::ConstructionFailed { reason, .. })
+                if reason.contains("The recursion is unavailable")
+        ));
+    }
 }
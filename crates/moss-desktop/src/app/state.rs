@@ -1,16 +1,27 @@
 use moss_addon::BUILTIN_ADDONS_DIR;
-use moss_cache::{backend::moka::MokaBackend, Cache};
+use moss_cache::backend::{disk::DiskBackend, moka::MokaBackend, tiered::TieredBackend};
+use moss_cache::Cache;
 use moss_text::ReadOnlyStr;
 use parking_lot::RwLock;
+use serde_json::Value;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::command::CommandHandler;
+use crate::command::{CommandInvocations, RegisteredCommand};
+use crate::command_history::CommandHistory;
 use crate::contribution_registry::ContributionRegistry;
 use crate::models::application::{LocaleDescriptor, ThemeDescriptor};
+use crate::models::commands::CommandDescriptor;
+use crate::settings_bundle::PendingImports;
 
 const STATE_CACHE_TTL: Duration = Duration::from_secs(60 * 3);
 const STATE_MAX_CAPACITY: u64 = 100;
+const STATE_DISK_CACHE_BUDGET: u64 = 64 * 1024 * 1024;
+
+/// The application cache, kept in memory and, for the entries loaded as persistent,
+/// on the disk between runs.
+pub type AppCache = Cache<TieredBackend<MokaBackend, DiskBackend>>;
 
 pub struct Preferences {
     pub theme: RwLock<Option<ThemeDescriptor>>,
@@ -24,14 +35,21 @@ pub struct AppDefaults {
 
 pub struct AppStateManager {
     pub contributions: ContributionRegistry,
-    pub cache: Arc<Cache<MokaBackend>>,
+    pub cache: Arc<AppCache>,
     pub preferences: Preferences,
     pub defaults: AppDefaults,
+    pub invocations: CommandInvocations,
+    pub history: CommandHistory,
+    pub settings_imports: PendingImports,
 }
 
 impl AppStateManager {
     pub fn new() -> Self {
-        let cache = Cache::new(MokaBackend::new(STATE_MAX_CAPACITY, STATE_CACHE_TTL));
+        let cache = Cache::new(TieredBackend::new(
+            MokaBackend::new(STATE_MAX_CAPACITY, STATE_CACHE_TTL),
+            DiskBackend::new(get_cache_dir(), STATE_DISK_CACHE_BUDGET),
+        ));
+        cache.on_removal(|key, cause| trace!("Cache entry '{key}' removed: {cause:?}"));
 
         Self {
             cache: Arc::new(cache),
@@ -58,14 +76,58 @@ impl AppStateManager {
             },
             contributions: ContributionRegistry::new()
                 .init(crate::contribution::CONTRIBUTIONS.iter().map(|c| &**c)),
+            invocations: CommandInvocations::default(),
+            history: CommandHistory::default(),
+            settings_imports: PendingImports::default(),
         }
     }
 
-    pub fn get_command(&self, id: &ReadOnlyStr) -> Option<CommandHandler> {
-        self.contributions
+    pub fn get_command(&self, id: &ReadOnlyStr) -> Option<RegisteredCommand> {
+        self.contributions.commands.get(id).map(|cmd| cmd.clone())
+    }
+
+    /// Lists the commands with their metadata, sorted by id, telling which are enabled in
+    /// the context.
+    pub fn list_commands(&self, context: &Value) -> Vec<CommandDescriptor> {
+        let mut commands = self
+            .contributions
             .commands
-            .get(id)
-            .map(|cmd| Arc::clone(&cmd))
+            .iter()
+            .map(|entry| {
+                let (id, command) = entry.pair();
+                let metadata = &command.metadata;
+
+                CommandDescriptor {
+                    id: id.to_string(),
+                    title: metadata.title.clone(),
+                    category: metadata.category.clone(),
+                    icon: metadata.icon.as_ref().map(|icon| icon.to_string()),
+                    args_description: metadata.args_description.clone(),
+                    args_schema: command.args_schema.as_deref().cloned(),
+                    enabled: command.is_enabled(id, context),
+                }
+            })
+            .collect::<Vec<_>>();
+        commands.sort_by(|a, b| a.id.cmp(&b.id));
+
+        commands
+    }
+
+    /// Logs the cache statistics, for tuning the TTL and the capacity of the cache.
+    pub fn log_cache_stats(&self) {
+        let stats = self.cache.stats();
+        debug!(
+            "Cache: {} hits, {} misses ({:.1}% hit rate), {} loads, {} failed, {} evictions",
+            stats.hits,
+            stats.misses,
+            stats.hit_rate() * 100.0,
+            stats.loads,
+            stats.load_failures,
+            stats.evictions
+        );
+        for (key, stats) in self.cache.stats_by_key() {
+            debug!("Cache key '{key}': {stats:?}");
+        }
     }
 
     pub fn change_language_pack(&self, locale_descriptor: LocaleDescriptor) {
@@ -78,3 +140,11 @@ impl AppStateManager {
         *theme_descriptor_lock = Some(theme_descriptor);
     }
 }
+
+/// A directory of its own, since the disk cache deletes the leftover temporary files in it.
+fn get_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("moss")
+        .join("cache")
+}
@@ -1,13 +1,35 @@
 use anyhow::Result;
+use dashmap::{mapref::entry::Entry, DashMap};
 use hashbrown::HashMap;
-use moss_text::ReadOnlyStr;
-use serde::de::DeserializeOwned;
-use serde_json::Value;
-use std::{cell::RefCell, sync::Arc};
-use tauri::{AppHandle, Window};
+use moss_jsonlogic::raw_rule::RawRule;
+use moss_tauri::{ErrorCode, TauriError};
+use moss_text::{localized_string::LocalizedString, ReadOnlyStr};
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::{self, Future},
+    path::Path,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tauri::{AppHandle, Emitter, EventTarget, Manager, Window};
 use thiserror::Error;
+use tokio::sync::watch;
+use ts_rs::TS;
+
+use crate::{
+    app::state::AppStateManager,
+    models::commands::{CommandCall, CommandProgress},
+};
 
-use crate::app::state::AppStateManager;
+/// The event the progress of a command is reported to the calling window with.
+pub const COMMAND_PROGRESS_EVENT: &str = "core://command-progress";
 
 #[derive(Error, Debug)]
 pub enum CommandContextError {
@@ -22,9 +44,65 @@ pub enum CommandContextError {
     },
 }
 
-impl From<CommandContextError> for String {
-    fn from(err: CommandContextError) -> Self {
-        err.to_string()
+impl ErrorCode for CommandContextError {
+    fn code(&self) -> &'static str {
+        match self {
+            CommandContextError::ArgNotFound { .. } => "command.argumentMissing",
+            CommandContextError::DeserializationError { .. } => "command.argumentInvalid",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            CommandContextError::ArgNotFound { key }
+            | CommandContextError::DeserializationError { key, .. } => Some(json!({ "key": key })),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("Command '{command}' is not found")]
+    NotFound { command: ReadOnlyStr },
+
+    #[error("Command '{command}' was cancelled")]
+    Cancelled { command: ReadOnlyStr },
+
+    #[error("Command '{command}' is disabled")]
+    Disabled { command: ReadOnlyStr },
+
+    #[error("Invalid arguments for command '{command}': {}", .errors.join("; "))]
+    InvalidArguments {
+        command: ReadOnlyStr,
+        /// Each error is prefixed with the JSON pointer of the invalid value.
+        errors: Vec<String>,
+    },
+
+    #[error("An execution with the id '{id}' is already running")]
+    DuplicateInvocation { id: String },
+}
+
+impl ErrorCode for CommandError {
+    fn code(&self) -> &'static str {
+        match self {
+            CommandError::NotFound { .. } => "command.notFound",
+            CommandError::Cancelled { .. } => "command.cancelled",
+            CommandError::Disabled { .. } => "command.disabled",
+            CommandError::InvalidArguments { .. } => "command.argumentInvalid",
+            CommandError::DuplicateInvocation { .. } => "command.invocationDuplicate",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            CommandError::NotFound { command }
+            | CommandError::Cancelled { command }
+            | CommandError::Disabled { command } => Some(json!({ "command": command.as_str() })),
+            CommandError::InvalidArguments { command, errors } => {
+                Some(json!({ "command": command.as_str(), "errors": errors }))
+            }
+            CommandError::DuplicateInvocation { id } => Some(json!({ "id": id })),
+        }
     }
 }
 
@@ -32,15 +110,76 @@ pub struct CommandContext {
     pub app_handle: AppHandle,
     pub window: Window,
 
-    args: RefCell<HashMap<String, Value>>,
+    args: Mutex<HashMap<String, Value>>,
+    invocation: CommandInvocation,
 }
 
 impl CommandContext {
-    pub fn new(app_handle: AppHandle, window: Window, args: HashMap<String, Value>) -> Self {
+    pub fn new(
+        app_handle: AppHandle,
+        window: Window,
+        args: HashMap<String, Value>,
+        invocation: CommandInvocation,
+    ) -> Self {
         Self {
             app_handle,
             window,
-            args: RefCell::new(args),
+            args: Mutex::new(args),
+            invocation,
+        }
+    }
+
+    pub fn invocation(&self) -> &CommandInvocation {
+        &self.invocation
+    }
+
+    /// Whether the caller cancelled the command, for the handlers that check it between steps.
+    pub fn is_cancelled(&self) -> bool {
+        self.invocation.token.is_cancelled()
+    }
+
+    /// Resolves once the caller cancels the command.
+    pub async fn cancelled(&self) {
+        self.invocation.token.cancelled().await
+    }
+
+    /// Reports the progress of the command to the window that executed it.
+    pub fn report_progress(&self, message: Option<String>, percentage: Option<f32>) {
+        let progress = CommandProgress {
+            invocation_id: self.invocation.id.clone(),
+            command: self.invocation.command.to_string(),
+            message,
+            percentage,
+        };
+
+        if let Err(err) = self.app_handle.emit_to(
+            EventTarget::window(self.window.label()),
+            COMMAND_PROGRESS_EVENT,
+            progress,
+        ) {
+            warn!(
+                "Failed to report the progress of command {}: {err}",
+                self.invocation.command
+            );
+        }
+    }
+
+    /// Makes the execution undoable with the command, and its arguments, that reverts it.
+    /// The arguments must serialize to an object.
+    pub fn set_inverse(&self, command: impl Into<String>, args: impl Serialize) {
+        let command = command.into();
+        match serde_json::to_value(args) {
+            Ok(Value::Object(args)) => {
+                *self.invocation.inverse.lock() = Some(CommandCall { command, args });
+            }
+            Ok(args) => warn!(
+                "The arguments of the inverse {command} of command {} are not an object: {args}",
+                self.invocation.command
+            ),
+            Err(err) => warn!(
+                "Failed to serialize the arguments of the inverse {command} of command {}: {err}",
+                self.invocation.command
+            ),
         }
     }
 
@@ -48,7 +187,7 @@ impl CommandContext {
     where
         T: DeserializeOwned,
     {
-        let mut args = self.args.borrow_mut();
+        let mut args = self.args.lock();
         let value = args.remove(key).ok_or(CommandContextError::ArgNotFound {
             key: key.to_string(),
         })?;
@@ -59,11 +198,16 @@ impl CommandContext {
         })
     }
 
+    /// Takes all the arguments at once, as a JSON object.
+    pub fn take_args(&self) -> Value {
+        Value::Object(std::mem::take(&mut *self.args.lock()).into_iter().collect())
+    }
+
     pub fn get_arg<T>(&self, key: &str) -> Result<T, CommandContextError>
     where
         T: DeserializeOwned,
     {
-        let args = self.args.borrow();
+        let args = self.args.lock();
         let value = args.get(key).ok_or(CommandContextError::ArgNotFound {
             key: key.to_string(),
         })?;
@@ -77,11 +221,437 @@ impl CommandContext {
     }
 }
 
-pub type CommandHandler =
-    Arc<dyn Fn(CommandContext, &AppStateManager) -> Result<Value, String> + Send + Sync>;
+pub type CommandFuture = Pin<Box<dyn Future<Output = Result<Value, TauriError>> + Send>>;
+
+pub type CommandHandler = Arc<dyn Fn(CommandContext) -> CommandFuture + Send + Sync>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CommandCallback {
+    /// Runs to completion as soon as the command is executed.
+    Sync(fn(CommandContext, &AppStateManager) -> Result<Value, TauriError>),
+    /// Returns a future, which is dropped when the caller cancels the command.
+    Async(fn(CommandContext) -> CommandFuture),
+    /// A [`TypedCommand`], its arguments are validated against their schema before dispatch.
+    Typed {
+        args_schema: fn() -> Value,
+        callback: fn(CommandContext) -> CommandFuture,
+    },
+}
+
+impl CommandCallback {
+    /// The JSON schema of the arguments, known for the typed commands only.
+    pub fn args_schema(&self) -> Option<Value> {
+        match self {
+            CommandCallback::Typed { args_schema, .. } => Some(args_schema()),
+            CommandCallback::Sync(_) | CommandCallback::Async(_) => None,
+        }
+    }
+
+    pub fn into_handler(self, command: &ReadOnlyStr) -> CommandHandler {
+        match self {
+            CommandCallback::Sync(callback) => Arc::new(move |ctx: CommandContext| {
+                let app_handle = ctx.app_handle.clone();
+                let result = callback(ctx, &app_handle.state::<AppStateManager>());
+
+                Box::pin(async move { result })
+            }),
+            CommandCallback::Async(callback) => Arc::new(callback),
+            CommandCallback::Typed {
+                args_schema,
+                callback,
+            } => {
+                let command = command.clone();
+                let validator = jsonschema::validator_for(&args_schema())
+                    .inspect_err(|err| {
+                        error!("Invalid schema of the arguments of command {command}: {err}")
+                    })
+                    .ok();
+
+                Arc::new(move |ctx: CommandContext| {
+                    if let Some(validator) = &validator {
+                        let args = Value::Object(ctx.args.lock().clone().into_iter().collect());
+                        let errors = validator
+                            .iter_errors(&args)
+                            .map(|err| format!("{}: {err}", err.instance_path))
+                            .collect::<Vec<_>>();
+                        if !errors.is_empty() {
+                            let error = CommandError::InvalidArguments {
+                                command: command.clone(),
+                                errors,
+                            };
+                            return Box::pin(future::ready(Err(error.into())));
+                        }
+                    }
+
+                    callback(ctx)
+                })
+            }
+        }
+    }
+}
+
+/// Describes a command to the user, e.g. in the command palette.
+#[derive(Debug, Clone, Default)]
+pub struct CommandMetadata {
+    pub title: Option<LocalizedString>,
+    pub category: Option<LocalizedString>,
+    pub icon: Option<ReadOnlyStr>,
+    pub args_description: Option<LocalizedString>,
+    /// The command can only be executed while the rule holds for the context of the window
+    /// it is executed in, see
+    /// [`ContextKeyService::context`](crate::services::context_key_service::ContextKeyService::context).
+    pub enablement: Option<RawRule>,
+}
 
 #[derive(Debug)]
 pub struct CommandDecl {
     pub name: ReadOnlyStr,
-    pub callback: fn(CommandContext, &AppStateManager) -> Result<serde_json::Value, String>,
+    pub callback: CommandCallback,
+    pub metadata: CommandMetadata,
+}
+
+impl CommandDecl {
+    pub fn new(name: impl Into<ReadOnlyStr>, callback: CommandCallback) -> Self {
+        Self {
+            name: name.into(),
+            callback,
+            metadata: CommandMetadata::default(),
+        }
+    }
+
+    pub fn typed<C: TypedCommand>() -> Self {
+        Self {
+            name: ReadOnlyStr::from(C::ID),
+            callback: CommandCallback::Typed {
+                args_schema: args_schema::<C>,
+                callback: execute_typed::<C>,
+            },
+            metadata: CommandMetadata::default(),
+        }
+    }
+
+    pub fn with_title(mut self, title: LocalizedString) -> Self {
+        self.metadata.title = Some(title);
+        self
+    }
+
+    pub fn with_category(mut self, category: LocalizedString) -> Self {
+        self.metadata.category = Some(category);
+        self
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<ReadOnlyStr>) -> Self {
+        self.metadata.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_args_description(mut self, description: LocalizedString) -> Self {
+        self.metadata.args_description = Some(description);
+        self
+    }
+
+    pub fn with_enablement(mut self, rule: RawRule) -> Self {
+        self.metadata.enablement = Some(rule);
+        self
+    }
+
+    pub fn register(self) -> RegisteredCommand {
+        RegisteredCommand {
+            args_schema: self.callback.args_schema().map(Arc::new),
+            handler: self.callback.into_handler(&self.name),
+            metadata: Arc::new(self.metadata),
+        }
+    }
+}
+
+/// A command in the [`ContributionRegistry`](crate::contribution_registry::ContributionRegistry).
+#[derive(Clone)]
+pub struct RegisteredCommand {
+    pub handler: CommandHandler,
+    pub metadata: Arc<CommandMetadata>,
+    pub args_schema: Option<Arc<Value>>,
+}
+
+impl RegisteredCommand {
+    /// Whether the enablement rule of the command holds for the context. A rule that fails
+    /// to evaluate disables the command.
+    pub fn is_enabled(&self, command: &ReadOnlyStr, context: &Value) -> bool {
+        let Some(rule) = &self.metadata.enablement else {
+            return true;
+        };
+
+        rule.is_satisfied(context).unwrap_or_else(|err| {
+            warn!("Failed to evaluate the enablement of command {command}: {err}");
+            false
+        })
+    }
+}
+
+pub type TypedCommandFuture<T> = Pin<Box<dyn Future<Output = Result<T, TauriError>> + Send>>;
+
+/// A command with typed arguments and result, usually declared with the
+/// [`command_handler`](moss_desktop_macro::command_handler) attribute. The TypeScript
+/// bindings of both are generated with ts-rs.
+pub trait TypedCommand: 'static {
+    const ID: &'static str;
+    type Args: DeserializeOwned + JsonSchema + TS;
+    type Output: Serialize + TS;
+
+    fn execute(ctx: CommandContext, args: Self::Args) -> TypedCommandFuture<Self::Output>;
+}
+
+fn args_schema<C: TypedCommand>() -> Value {
+    serde_json::to_value(schemars::schema_for!(C::Args)).unwrap_or(Value::Bool(true))
+}
+
+fn execute_typed<C: TypedCommand>(ctx: CommandContext) -> CommandFuture {
+    let args = match serde_json::from_value::<C::Args>(ctx.take_args()) {
+        Ok(args) => args,
+        Err(err) => {
+            let error = CommandError::InvalidArguments {
+                command: ReadOnlyStr::from(C::ID),
+                errors: vec![err.to_string()],
+            };
+            return Box::pin(future::ready(Err(error.into())));
+        }
+    };
+
+    let output = C::execute(ctx, args);
+    Box::pin(async move {
+        serde_json::to_value(output.await?).map_err(|err| {
+            TauriError::new("command.resultInvalid", err.to_string()).with_causes(&err)
+        })
+    })
+}
+
+/// The typed commands, collected from the `command_handler` attributes to generate the
+/// `MossCommandMap` binding the frontend invokes them with.
+#[linkme::distributed_slice]
+pub static COMMAND_BINDINGS: [CommandBinding] = [..];
+
+/// The TypeScript types of the arguments and the result of a typed command.
+pub struct CommandBinding {
+    pub id: &'static str,
+    pub args: fn() -> TsTypeRef,
+    pub output: fn() -> TsTypeRef,
+}
+
+impl CommandBinding {
+    pub const fn of<C: TypedCommand>() -> Self {
+        Self {
+            id: C::ID,
+            args: TsTypeRef::of::<C::Args>,
+            output: TsTypeRef::of::<C::Output>,
+        }
+    }
+}
+
+/// A TypeScript type as it is referred to, with the exported types it refers to.
+pub struct TsTypeRef {
+    name: String,
+    imports: Vec<(String, &'static Path)>,
+}
+
+impl TsTypeRef {
+    fn of<T: TS + 'static>() -> Self {
+        let imports = match T::output_path() {
+            Some(path) => vec![(T::ident(), path)],
+            None => T::dependencies()
+                .into_iter()
+                .map(|dependency| (dependency.ts_name, dependency.output_path))
+                .collect(),
+        };
+
+        Self {
+            name: T::name(),
+            imports,
+        }
+    }
+}
+
+/// Writes the `MossCommandMap` of the typed commands to `command_map.ts` in the directory
+/// of the bindings.
+pub fn export_command_map(out_dir: &Path) -> Result<()> {
+    let mut bindings = COMMAND_BINDINGS.iter().collect::<Vec<_>>();
+    bindings.sort_by_key(|binding| binding.id);
+
+    let mut imports = BTreeMap::<String, BTreeSet<String>>::new();
+    let mut entries = String::new();
+    for binding in bindings {
+        let (args, output) = ((binding.args)(), (binding.output)());
+        for (name, path) in args.imports.into_iter().chain(output.imports) {
+            imports
+                .entry(format!("./{}", path.with_extension("").display()))
+                .or_default()
+                .insert(name);
+        }
+        entries.push_str(&format!(
+            "  \"{}\": {{ args: {}; result: {} }};\n",
+            binding.id, args.name, output.name
+        ));
+    }
+
+    let mut content = String::from(
+        "// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.\n",
+    );
+    for (path, names) in imports {
+        let names = names.into_iter().collect::<Vec<_>>().join(", ");
+        content.push_str(&format!("import type {{ {names} }} from \"{path}\";\n"));
+    }
+    content.push_str(&format!(
+        "\n/**\n * The typed commands, with the types of their arguments and result.\n */\nexport type MossCommandMap = {{\n{entries}}};\n"
+    ));
+
+    std::fs::create_dir_all(out_dir)?;
+    std::fs::write(out_dir.join("command_map.ts"), content)?;
+
+    Ok(())
+}
+
+/// Lets the caller of a command cancel it. Cloning it is cheap, the clones share the state.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self {
+            cancelled: Arc::new(watch::Sender::new(false)),
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancelled.subscribe();
+        // The sender lives as long as the token, so the wait cannot fail while it is borrowed.
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// A running execution of a command.
+#[derive(Debug, Clone)]
+pub struct CommandInvocation {
+    /// Identifies the execution in the progress events and when cancelling it.
+    pub id: String,
+    pub command: ReadOnlyStr,
+    pub token: CancellationToken,
+    /// The command that reverts the execution, set by its handler.
+    inverse: Arc<Mutex<Option<CommandCall>>>,
+}
+
+impl CommandInvocation {
+    pub fn take_inverse(&self) -> Option<CommandCall> {
+        self.inverse.lock().take()
+    }
+}
+
+/// The commands being executed, so that their callers can cancel them.
+#[derive(Default)]
+pub struct CommandInvocations {
+    /// The cancellation tokens of the executions, with the label of the window running them.
+    running: DashMap<String, (String, CancellationToken)>,
+    next_id: AtomicU64,
+}
+
+impl CommandInvocations {
+    /// Starts an execution of the command in the window, under the id chosen by the caller
+    /// if it has one. An id cannot be reused while its execution is running.
+    pub fn begin(
+        &self,
+        command: ReadOnlyStr,
+        window: &str,
+        id: Option<String>,
+    ) -> Result<CommandInvocation, CommandError> {
+        let id = id.unwrap_or_else(|| {
+            format!(
+                "invocation-{}",
+                self.next_id.fetch_add(1, Ordering::Relaxed)
+            )
+        });
+        let token = CancellationToken::default();
+        match self.running.entry(id.clone()) {
+            Entry::Occupied(_) => return Err(CommandError::DuplicateInvocation { id }),
+            Entry::Vacant(entry) => {
+                entry.insert((window.to_string(), token.clone()));
+            }
+        }
+
+        Ok(CommandInvocation {
+            id,
+            command,
+            token,
+            inverse: Arc::default(),
+        })
+    }
+
+    pub fn finish(&self, invocation: &CommandInvocation) {
+        self.running.remove(&invocation.id);
+    }
+
+    /// Cancels the execution started by the window, returns `false` if it is not running
+    /// or was started by another window.
+    pub fn cancel(&self, id: &str, window: &str) -> bool {
+        match self.running.get(id) {
+            Some(running) if running.0 == window => {
+                running.1.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_bindings_moss_command_map() {
+        // Exported next to the bindings of ts-rs.
+        let out_dir = std::env::var("TS_RS_EXPORT_DIR").unwrap_or_else(|_| "./bindings".into());
+        export_command_map(Path::new(&out_dir)).unwrap();
+    }
+
+    #[test]
+    fn test_invocation_id_is_not_reused_while_running() {
+        let invocations = CommandInvocations::default();
+        let command = ReadOnlyStr::from("test.command");
+
+        let invocation = invocations
+            .begin(command.clone(), "main_0", Some("load".to_string()))
+            .unwrap();
+        assert!(matches!(
+            invocations.begin(command.clone(), "main_0", Some("load".to_string())),
+            Err(CommandError::DuplicateInvocation { id }) if id == "load"
+        ));
+        assert!(!invocation.token.is_cancelled());
+
+        invocations.finish(&invocation);
+        assert!(invocations
+            .begin(command, "main_0", Some("load".to_string()))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_invocation_is_cancelled_by_its_window_only() {
+        let invocations = CommandInvocations::default();
+        let invocation = invocations
+            .begin(ReadOnlyStr::from("test.command"), "main_0", None)
+            .unwrap();
+
+        assert!(!invocations.cancel(&invocation.id, "main_1"));
+        assert!(!invocation.token.is_cancelled());
+        assert!(invocations.cancel(&invocation.id, "main_0"));
+        assert!(invocation.token.is_cancelled());
+    }
 }
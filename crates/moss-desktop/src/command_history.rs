@@ -0,0 +1,366 @@
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::models::commands::{CommandCall, CommandHistoryDescriptor, CommandLogEntry};
+
+const UNDO_CAPACITY: usize = 100;
+const LOG_CAPACITY: usize = 500;
+const RECENT_CAPACITY: usize = 50;
+
+/// An executed command, along with the command that reverts it.
+#[derive(Debug, Clone)]
+pub struct UndoableCall {
+    pub call: CommandCall,
+    pub inverse: CommandCall,
+}
+
+#[derive(Default)]
+struct WindowHistory {
+    undo: VecDeque<UndoableCall>,
+    redo: Vec<UndoableCall>,
+}
+
+/// Keeps the commands executed in the windows: the undo and redo stacks of every window,
+/// the log the commands can be replayed from, and the commands used last.
+#[derive(Default)]
+pub struct CommandHistory {
+    windows: DashMap<String, WindowHistory>,
+    /// Held while a command taken from the stacks of the window is being run.
+    steps: DashMap<String, Arc<AsyncMutex<()>>>,
+    log: Mutex<VecDeque<CommandLogEntry>>,
+    recent: Mutex<VecDeque<String>>,
+    next_sequence: AtomicU64,
+}
+
+impl CommandHistory {
+    /// Records a command that ran to completion in the window. A command that can be undone
+    /// goes on the undo stack of the window, and the commands undone before it can no longer
+    /// be redone.
+    pub fn record(&self, window: &str, call: CommandCall, inverse: Option<CommandCall>) {
+        self.append_log(window, &call, inverse.is_some());
+        self.touch_recent(&call.command);
+
+        if let Some(inverse) = inverse {
+            let mut history = self.windows.entry(window.to_string()).or_default();
+            history.redo.clear();
+            push_undo(&mut history.undo, UndoableCall { call, inverse });
+        }
+    }
+
+    /// Waits for the undo or redo running in the window, if any, so that the next one takes
+    /// the command it hands back. Hold the guard until the taken command is handed back.
+    pub async fn lock_steps(&self, window: &str) -> OwnedMutexGuard<()> {
+        let lock = self.steps.entry(window.to_string()).or_default().clone();
+        lock.lock_owned().await
+    }
+
+    /// Takes the command to undo next in the window. The caller runs its inverse, then
+    /// hands it back with [`undone`](Self::undone), or with [`restore_undo`](Self::restore_undo)
+    /// if the inverse failed.
+    pub fn take_undo(&self, window: &str) -> Option<UndoableCall> {
+        self.windows.get_mut(window)?.undo.pop_back()
+    }
+
+    /// Takes the command to redo next in the window. The caller runs it again, then hands
+    /// it back with [`redone`](Self::redone), or with [`restore_redo`](Self::restore_redo)
+    /// if it failed.
+    pub fn take_redo(&self, window: &str) -> Option<UndoableCall> {
+        self.windows.get_mut(window)?.redo.pop()
+    }
+
+    pub fn undone(&self, window: &str, entry: UndoableCall) {
+        self.append_log(window, &entry.inverse, false);
+        self.windows
+            .entry(window.to_string())
+            .or_default()
+            .redo
+            .push(entry);
+    }
+
+    pub fn redone(&self, window: &str, entry: UndoableCall) {
+        self.append_log(window, &entry.call, true);
+        push_undo(
+            &mut self.windows.entry(window.to_string()).or_default().undo,
+            entry,
+        );
+    }
+
+    pub fn restore_undo(&self, window: &str, entry: UndoableCall) {
+        push_undo(
+            &mut self.windows.entry(window.to_string()).or_default().undo,
+            entry,
+        );
+    }
+
+    pub fn restore_redo(&self, window: &str, entry: UndoableCall) {
+        self.windows
+            .entry(window.to_string())
+            .or_default()
+            .redo
+            .push(entry);
+    }
+
+    pub fn describe(&self, window: &str) -> CommandHistoryDescriptor {
+        let Some(history) = self.windows.get(window) else {
+            return CommandHistoryDescriptor {
+                undo: Vec::new(),
+                redo: Vec::new(),
+            };
+        };
+
+        CommandHistoryDescriptor {
+            undo: history
+                .undo
+                .iter()
+                .rev()
+                .map(|entry| entry.call.clone())
+                .collect(),
+            redo: history
+                .redo
+                .iter()
+                .rev()
+                .map(|entry| entry.call.clone())
+                .collect(),
+        }
+    }
+
+    /// The executed commands, the oldest first, of the window or of all of them.
+    pub fn log(&self, window: Option<&str>) -> Vec<CommandLogEntry> {
+        self.log
+            .lock()
+            .iter()
+            .filter(|entry| window.is_none_or(|window| entry.window == window))
+            .cloned()
+            .collect()
+    }
+
+    /// The ids of the commands used last, the most recent first.
+    pub fn recent_commands(&self) -> Vec<String> {
+        self.recent.lock().iter().cloned().collect()
+    }
+
+    /// Forgets the undo and redo stacks of the window, once it is closed. Its commands stay
+    /// in the log.
+    pub fn clear_window(&self, window: &str) {
+        self.windows.remove(window);
+        self.steps.remove(window);
+    }
+
+    fn append_log(&self, window: &str, call: &CommandCall, undoable: bool) {
+        let executed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+
+        let mut log = self.log.lock();
+        if log.len() == LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(CommandLogEntry {
+            sequence: self.next_sequence.fetch_add(1, Ordering::Relaxed),
+            window: window.to_string(),
+            call: call.clone(),
+            executed_at,
+            undoable,
+        });
+    }
+
+    fn touch_recent(&self, command: &str) {
+        let mut recent = self.recent.lock();
+        recent.retain(|id| id != command);
+        recent.push_front(command.to_string());
+        recent.truncate(RECENT_CAPACITY);
+    }
+}
+
+fn push_undo(undo: &mut VecDeque<UndoableCall>, entry: UndoableCall) {
+    if undo.len() == UNDO_CAPACITY {
+        undo.pop_front();
+    }
+    undo.push_back(entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map, Value};
+    use std::time::Duration;
+
+    fn call(command: &str, value: i64) -> CommandCall {
+        let mut args = Map::new();
+        args.insert("value".to_string(), Value::from(value));
+        CommandCall {
+            command: command.to_string(),
+            args,
+        }
+    }
+
+    fn undoable(history: &CommandHistory, window: &str, value: i64) {
+        history.record(
+            window,
+            call("test.set", value),
+            Some(call("test.set", value - 1)),
+        );
+    }
+
+    fn values(calls: Vec<CommandCall>) -> Vec<Value> {
+        calls
+            .into_iter()
+            .map(|call| call.args["value"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let history = CommandHistory::default();
+        undoable(&history, "main_0", 1);
+        undoable(&history, "main_0", 2);
+        history.record("main_0", call("test.print", 0), None);
+
+        let entry = history.take_undo("main_0").unwrap();
+        assert_eq!(entry.call, call("test.set", 2));
+        assert_eq!(entry.inverse, call("test.set", 1));
+        history.undone("main_0", entry);
+
+        let described = history.describe("main_0");
+        assert_eq!(values(described.undo), [json!(1)]);
+        assert_eq!(values(described.redo), [json!(2)]);
+
+        let entry = history.take_redo("main_0").unwrap();
+        assert_eq!(entry.call, call("test.set", 2));
+        history.redone("main_0", entry);
+
+        let described = history.describe("main_0");
+        assert_eq!(values(described.undo), [json!(2), json!(1)]);
+        assert!(described.redo.is_empty());
+
+        let log = history
+            .log(Some("main_0"))
+            .into_iter()
+            .map(|entry| (entry.call, entry.undoable))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log,
+            [
+                (call("test.set", 1), true),
+                (call("test.set", 2), true),
+                (call("test.print", 0), false),
+                (call("test.set", 1), false),
+                (call("test.set", 2), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recording_clears_redo() {
+        let history = CommandHistory::default();
+        undoable(&history, "main_0", 1);
+        let entry = history.take_undo("main_0").unwrap();
+        history.undone("main_0", entry);
+
+        undoable(&history, "main_0", 5);
+        assert!(history.take_redo("main_0").is_none());
+        assert_eq!(values(history.describe("main_0").undo), [json!(5)]);
+    }
+
+    #[test]
+    fn test_restore_after_failure() {
+        let history = CommandHistory::default();
+        undoable(&history, "main_0", 1);
+
+        let entry = history.take_undo("main_0").unwrap();
+        assert!(history.take_undo("main_0").is_none());
+        history.restore_undo("main_0", entry);
+        assert_eq!(values(history.describe("main_0").undo), [json!(1)]);
+
+        let entry = history.take_undo("main_0").unwrap();
+        history.undone("main_0", entry);
+        let entry = history.take_redo("main_0").unwrap();
+        history.restore_redo("main_0", entry);
+        let described = history.describe("main_0");
+        assert!(described.undo.is_empty());
+        assert_eq!(values(described.redo), [json!(1)]);
+    }
+
+    #[test]
+    fn test_windows_are_separate() {
+        let history = CommandHistory::default();
+        undoable(&history, "main_0", 1);
+        undoable(&history, "main_1", 2);
+
+        assert_eq!(
+            history.take_undo("main_0").unwrap().call,
+            call("test.set", 1)
+        );
+        assert!(history.take_undo("main_0").is_none());
+
+        history.clear_window("main_1");
+        assert!(history.take_undo("main_1").is_none());
+        assert_eq!(history.log(Some("main_1")).len(), 1);
+        assert_eq!(history.log(None).len(), 2);
+    }
+
+    #[test]
+    fn test_capacity_eviction() {
+        let history = CommandHistory::default();
+        for value in 0..(LOG_CAPACITY as i64 + 10) {
+            undoable(&history, "main_0", value);
+        }
+
+        let undo = history.describe("main_0").undo;
+        assert_eq!(undo.len(), UNDO_CAPACITY);
+        assert_eq!(undo[0], call("test.set", LOG_CAPACITY as i64 + 9));
+        assert_eq!(
+            undo[UNDO_CAPACITY - 1],
+            call("test.set", (LOG_CAPACITY + 10 - UNDO_CAPACITY) as i64)
+        );
+
+        let log = history.log(None);
+        assert_eq!(log.len(), LOG_CAPACITY);
+        assert_eq!(log[0].call, call("test.set", 10));
+        assert_eq!(log[0].sequence, 10);
+    }
+
+    #[test]
+    fn test_recent_ordering() {
+        let history = CommandHistory::default();
+        for command in ["test.a", "test.b", "test.c", "test.a"] {
+            history.record("main_0", call(command, 0), None);
+        }
+        assert_eq!(history.recent_commands(), ["test.a", "test.c", "test.b"]);
+
+        for index in 0..RECENT_CAPACITY {
+            history.record("main_0", call(&format!("test.other{index}"), 0), None);
+        }
+        let recent = history.recent_commands();
+        assert_eq!(recent.len(), RECENT_CAPACITY);
+        assert_eq!(recent[0], format!("test.other{}", RECENT_CAPACITY - 1));
+        assert!(!recent.iter().any(|command| command == "test.b"));
+    }
+
+    #[tokio::test]
+    async fn test_steps_are_serialized_per_window() {
+        let history = CommandHistory::default();
+
+        let step = history.lock_steps("main_0").await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), history.lock_steps("main_0"))
+                .await
+                .is_err()
+        );
+        // The other windows are not held up.
+        drop(history.lock_steps("main_1").await);
+
+        drop(step);
+        drop(history.lock_steps("main_0").await);
+    }
+}
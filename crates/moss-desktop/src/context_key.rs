@@ -0,0 +1,456 @@
+use dashmap::DashMap;
+use hashbrown::HashMap;
+use moss_tauri::ErrorCode;
+use moss_text::ReadOnlyStr;
+use parking_lot::RwLock;
+use serde_json::{json, Map, Value};
+use std::{marker::PhantomData, sync::Arc};
+use thiserror::Error;
+
+use crate::models::context_keys::{
+    ContextKeyChange, ContextKeyDescriptor, ContextKeyType, ContextScope,
+};
+
+#[derive(Error, Debug)]
+pub enum ContextKeyError {
+    #[error("Context key '{key}' expects a {expected} value, got {value}")]
+    TypeMismatch {
+        key: String,
+        expected: ContextKeyType,
+        value: Value,
+    },
+}
+
+impl ErrorCode for ContextKeyError {
+    fn code(&self) -> &'static str {
+        match self {
+            ContextKeyError::TypeMismatch { .. } => "contextKey.typeMismatch",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            ContextKeyError::TypeMismatch { key, expected, .. } => {
+                Some(json!({ "key": key, "expected": expected }))
+            }
+        }
+    }
+}
+
+/// A context key declared with the `context_keys` field of `contribution_point!`.
+#[derive(Debug, Clone)]
+pub struct ContextKeyDecl {
+    pub key: ReadOnlyStr,
+    pub ty: ContextKeyType,
+    pub default: Value,
+    pub description: Option<&'static str>,
+}
+
+/// The Rust types the values of the context keys are read and written as.
+pub trait ContextKeyValue: Sized {
+    const TYPE: ContextKeyType;
+
+    fn into_value(self) -> Value;
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl ContextKeyValue for bool {
+    const TYPE: ContextKeyType = ContextKeyType::Boolean;
+
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl ContextKeyValue for i64 {
+    const TYPE: ContextKeyType = ContextKeyType::Number;
+
+    fn into_value(self) -> Value {
+        Value::from(self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_i64()
+    }
+}
+
+impl ContextKeyValue for f64 {
+    const TYPE: ContextKeyType = ContextKeyType::Number;
+
+    fn into_value(self) -> Value {
+        Value::from(self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+impl ContextKeyValue for String {
+    const TYPE: ContextKeyType = ContextKeyType::String;
+
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_str().map(ToString::to_string)
+    }
+}
+
+/// A typed handle on a context key, usually declared as a constant:
+///
+/// ```ignore
+/// pub const CONTEXT_KEY_TOGGLE_PANEL: ContextKey<bool> =
+///     ContextKey::new("togglePanel", "Whether the panel is visible");
+/// ```
+///
+/// Its value is the default of the type until it is set.
+pub struct ContextKey<T> {
+    pub key: &'static str,
+    pub description: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ContextKey<T> {
+    pub const fn new(key: &'static str, description: &'static str) -> Self {
+        Self {
+            key,
+            description,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ContextKeyValue + Default> ContextKey<T> {
+    pub fn decl(&self) -> ContextKeyDecl {
+        ContextKeyDecl {
+            key: ReadOnlyStr::from(self.key),
+            ty: T::TYPE,
+            default: T::default().into_value(),
+            description: Some(self.description),
+        }
+    }
+}
+
+/// The declarations of the context keys and the values set in every scope. The changes are
+/// returned to the caller, which notifies the ones interested in them.
+pub struct ContextKeyStore {
+    declarations: Arc<DashMap<ReadOnlyStr, Arc<ContextKeyDecl>>>,
+    values: RwLock<HashMap<ContextScope, HashMap<ReadOnlyStr, Value>>>,
+}
+
+impl ContextKeyStore {
+    pub fn new(declarations: Arc<DashMap<ReadOnlyStr, Arc<ContextKeyDecl>>>) -> Self {
+        Self {
+            declarations,
+            values: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Declares a key that is not contributed, replacing its previous declaration.
+    pub fn declare(&self, decl: ContextKeyDecl) {
+        self.declarations.insert(decl.key.clone(), Arc::new(decl));
+    }
+
+    pub fn describe(&self) -> Vec<ContextKeyDescriptor> {
+        let mut descriptors = self
+            .declarations
+            .iter()
+            .map(|decl| ContextKeyDescriptor {
+                key: decl.key.to_string(),
+                ty: decl.ty,
+                default: decl.default.clone(),
+                description: decl.description.map(ToString::to_string),
+            })
+            .collect::<Vec<_>>();
+        descriptors.sort_by(|a, b| a.key.cmp(&b.key));
+
+        descriptors
+    }
+
+    /// The value of the key in the scope, inherited from the scopes that contain it,
+    /// or the default of the key.
+    pub fn get_value(&self, key: &str, scope: &ContextScope) -> Value {
+        let values = self.values.read();
+        let mut scope = Some(scope.clone());
+        while let Some(current) = scope {
+            if let Some(value) = values.get(&current).and_then(|values| values.get(key)) {
+                return value.clone();
+            }
+            scope = current.parent();
+        }
+
+        self.declarations
+            .get(key)
+            .map(|decl| decl.default.clone())
+            .unwrap_or(Value::Null)
+    }
+
+    /// Sets the value of the key in the scope. The value of a declared key must be
+    /// of its type. Returns the change, none when the key already had the value.
+    pub fn set_value(
+        &self,
+        key: &str,
+        scope: ContextScope,
+        value: Value,
+    ) -> Result<Option<ContextKeyChange>, ContextKeyError> {
+        match self.declarations.get(key) {
+            Some(decl) if !value.is_null() && !decl.ty.accepts(&value) => {
+                return Err(ContextKeyError::TypeMismatch {
+                    key: key.to_string(),
+                    expected: decl.ty,
+                    value,
+                });
+            }
+            Some(_) => {}
+            None => trace!("Setting the undeclared context key '{key}'"),
+        }
+
+        let changed = {
+            let mut values = self.values.write();
+            let scope_values = values.entry(scope.clone()).or_default();
+            scope_values.insert(ReadOnlyStr::from(key), value.clone()) != Some(value.clone())
+        };
+
+        Ok(changed.then(|| ContextKeyChange {
+            key: key.to_string(),
+            scope,
+            value: Some(value),
+        }))
+    }
+
+    /// Removes the value of the key from the scope, the key gets the value of the scope
+    /// that contains it again. Returns the change, none when the scope had no value.
+    pub fn reset(&self, key: &str, scope: ContextScope) -> Option<ContextKeyChange> {
+        let removed = self
+            .values
+            .write()
+            .get_mut(&scope)
+            .and_then(|values| values.remove(key))
+            .is_some();
+
+        removed.then(|| ContextKeyChange {
+            key: key.to_string(),
+            scope,
+            value: None,
+        })
+    }
+
+    /// Forgets the values set in the window and its views.
+    pub fn clear_window(&self, label: &str) {
+        self.values
+            .write()
+            .retain(|scope, _| scope.window_label() != Some(label));
+    }
+
+    /// The values of all the keys in the scope, as the data the rules are evaluated against.
+    /// The `window` and `view` keys hold the label of the window and the id of the view.
+    pub fn context(&self, scope: &ContextScope) -> Value {
+        let mut context = self
+            .declarations
+            .iter()
+            .map(|decl| (decl.key.to_string(), decl.default.clone()))
+            .collect::<Map<_, _>>();
+
+        let mut chain = vec![scope.clone()];
+        while let Some(parent) = chain.last().and_then(ContextScope::parent) {
+            chain.push(parent);
+        }
+
+        let values = self.values.read();
+        for scope in chain.iter().rev() {
+            for (key, value) in values.get(scope).into_iter().flatten() {
+                context.insert(key.to_string(), value.clone());
+            }
+        }
+
+        match scope {
+            ContextScope::Global => {}
+            ContextScope::Window { window } => {
+                context.insert("window".to_string(), json!(window));
+            }
+            ContextScope::View { window, view } => {
+                context.insert("window".to_string(), json!(window));
+                context.insert("view".to_string(), json!(view));
+            }
+        }
+
+        Value::Object(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CK_PANEL: ContextKey<bool> = ContextKey::new("panel", "Whether the panel is visible");
+    const CK_TITLE: ContextKey<String> = ContextKey::new("title", "The title of the view");
+
+    fn store() -> ContextKeyStore {
+        let store = ContextKeyStore::new(Arc::new(DashMap::new()));
+        store.declare(CK_PANEL.decl());
+        store.declare(CK_TITLE.decl());
+
+        store
+    }
+
+    #[test]
+    fn test_get_value_is_inherited() {
+        let store = store();
+        let view = ContextScope::view("main", "explorer");
+
+        assert_eq!(store.get_value("panel", &view), json!(false));
+        assert_eq!(store.get_value("undeclared", &view), Value::Null);
+
+        store
+            .set_value("panel", ContextScope::Global, json!(true))
+            .unwrap();
+        assert_eq!(store.get_value("panel", &view), json!(true));
+
+        store
+            .set_value("panel", ContextScope::window("main"), json!(false))
+            .unwrap();
+        assert_eq!(store.get_value("panel", &view), json!(false));
+        assert_eq!(
+            store.get_value("panel", &ContextScope::view("other", "explorer")),
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn test_context_is_inherited() {
+        let store = store();
+        store
+            .set_value("title", ContextScope::Global, json!("global"))
+            .unwrap();
+        store
+            .set_value("panel", ContextScope::window("main"), json!(true))
+            .unwrap();
+        store
+            .set_value(
+                "title",
+                ContextScope::view("main", "explorer"),
+                json!("explorer"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            store.context(&ContextScope::view("main", "explorer")),
+            json!({
+                "panel": true,
+                "title": "explorer",
+                "window": "main",
+                "view": "explorer",
+            })
+        );
+        assert_eq!(
+            store.context(&ContextScope::window("main")),
+            json!({ "panel": true, "title": "global", "window": "main" })
+        );
+        assert_eq!(
+            store.context(&ContextScope::Global),
+            json!({ "panel": false, "title": "global" })
+        );
+    }
+
+    #[test]
+    fn test_set_value_type_mismatch() {
+        let store = store();
+
+        let result = store.set_value("panel", ContextScope::Global, json!("visible"));
+
+        assert!(matches!(
+            result,
+            Err(ContextKeyError::TypeMismatch {
+                expected: ContextKeyType::Boolean,
+                ..
+            })
+        ));
+        assert_eq!(
+            store.get_value("panel", &ContextScope::Global),
+            json!(false)
+        );
+        // Null clears a declared key, and an undeclared key takes any value.
+        assert!(store
+            .set_value("panel", ContextScope::Global, Value::Null)
+            .is_ok());
+        assert!(store
+            .set_value("undeclared", ContextScope::Global, json!(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_reset_falls_back_to_the_parent_scope() {
+        let store = store();
+        let window = ContextScope::window("main");
+        store
+            .set_value("title", ContextScope::Global, json!("global"))
+            .unwrap();
+        store
+            .set_value("title", window.clone(), json!("main"))
+            .unwrap();
+
+        let change = store.reset("title", window.clone()).unwrap();
+
+        assert_eq!(change.scope, window);
+        assert_eq!(change.value, None);
+        assert_eq!(store.get_value("title", &window), json!("global"));
+        assert!(store.reset("title", window).is_none());
+    }
+
+    #[test]
+    fn test_clear_window() {
+        let store = store();
+        store
+            .set_value("panel", ContextScope::Global, json!(true))
+            .unwrap();
+        store
+            .set_value("title", ContextScope::window("main"), json!("main"))
+            .unwrap();
+        store
+            .set_value(
+                "title",
+                ContextScope::view("main", "explorer"),
+                json!("explorer"),
+            )
+            .unwrap();
+        store
+            .set_value("title", ContextScope::window("other"), json!("other"))
+            .unwrap();
+
+        store.clear_window("main");
+
+        assert_eq!(
+            store.get_value("title", &ContextScope::view("main", "explorer")),
+            json!("")
+        );
+        assert_eq!(
+            store.get_value("panel", &ContextScope::window("main")),
+            json!(true)
+        );
+        assert_eq!(
+            store.get_value("title", &ContextScope::window("other")),
+            json!("other")
+        );
+    }
+
+    #[test]
+    fn test_set_same_value_is_no_change() {
+        let store = store();
+
+        let first = store
+            .set_value("title", ContextScope::Global, json!("moss"))
+            .unwrap();
+        let second = store
+            .set_value("title", ContextScope::Global, json!("moss"))
+            .unwrap();
+
+        assert!(first.is_some_and(|change| change.value == Some(json!("moss"))));
+        assert!(second.is_none());
+    }
+}
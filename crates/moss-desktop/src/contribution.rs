@@ -4,6 +4,8 @@ use parking_lot::Mutex;
 use std::fmt::Debug;
 
 use crate::command::CommandDecl;
+use crate::context_key::ContextKeyDecl;
+use crate::keybinding::KeybindingDecl;
 use crate::models::{actions::MenuItem, view::*};
 
 #[distributed_slice]
@@ -21,54 +23,65 @@ macro_rules! contribution_point {
                 #[allow(unused_mut)] let mut menus = Vec::new();
                 #[allow(unused_mut)] let mut tree_view_groups = Vec::new();
                 #[allow(unused_mut)] let mut tree_views = Vec::new();
+                #[allow(unused_mut)] let mut keybindings = Vec::new();
+                #[allow(unused_mut)] let mut context_keys = Vec::new();
 
                 $(
-                    contribution_point!(@assign_field $field, [ $( $item ),* ], commands, menus, tree_view_groups, tree_views);
+                    contribution_point!(@assign_field $field, [ $( $item ),* ], commands, menus, tree_view_groups, tree_views, keybindings, context_keys);
                 )*
 
                 $crate::contribution::Contribution {
+                    module: module_path!(),
                     source: concat!(module_path!(), "::", file!(), ":", line!(), ":", column!()),
                     commands: parking_lot::Mutex::new(commands),
                     menus: parking_lot::Mutex::new(menus),
                     tree_view_groups: parking_lot::Mutex::new(tree_view_groups),
                     tree_views: parking_lot::Mutex::new(tree_views),
+                    keybindings: parking_lot::Mutex::new(keybindings),
+                    context_keys: parking_lot::Mutex::new(context_keys),
                 }
             });
         }
     };
 
     // Helper patterns for field assignment
-    (@assign_field commands, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident) => {
+    (@assign_field commands, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
         $commands.extend(vec![ $( $item ),* ]);
     };
-    (@assign_field menus, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident) => {
+    (@assign_field menus, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
         $menus.extend(vec![ $( $item ),* ]);
     };
-    (@assign_field tree_view_groups, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident) => {
+    (@assign_field tree_view_groups, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
         $tree_view_groups.extend(vec![ $( $item ),* ]);
     };
-    (@assign_field tree_views, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident) => {
+    (@assign_field tree_views, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
         $tree_views.extend(vec![ $( $item ),* ]);
     };
+    (@assign_field keybindings, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
+        $keybindings.extend(vec![ $( $item ),* ]);
+    };
+    (@assign_field context_keys, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
+        $context_keys.extend(vec![ $( $item ),* ]);
+    };
     // Pattern for unknown fields — triggers a compilation error
-    (@assign_field $unknown:ident, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident) => {
+    (@assign_field $unknown:ident, [ $( $item:expr ),* ], $commands:ident, $menus:ident, $tree_view_groups:ident, $tree_views:ident, $keybindings:ident, $context_keys:ident) => {
         compile_error!(concat!("Unknown field in contribution_point!: ", stringify!($unknown)));
     };
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MenuDecl {
     pub namespace: ReadOnlyStr,
     pub items: Vec<MenuItem>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TreeViewGroupDecl {
     pub location: TreeViewGroupLocation,
     pub items: Vec<TreeViewGroup>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TreeViewDecl {
     pub group_id: &'static str,
     pub items: Vec<TreeViewDescriptor>,
@@ -76,10 +89,14 @@ pub struct TreeViewDecl {
 
 #[derive(Debug)]
 pub struct Contribution {
+    /// The module the contributions are declared in, which owns them.
+    pub module: &'static str,
     #[allow(dead_code)]
     pub source: &'static str,
     pub commands: Mutex<Vec<CommandDecl>>,
     pub menus: Mutex<Vec<MenuDecl>>,
     pub tree_view_groups: Mutex<Vec<TreeViewGroupDecl>>,
     pub tree_views: Mutex<Vec<TreeViewDecl>>,
+    pub keybindings: Mutex<Vec<KeybindingDecl>>,
+    pub context_keys: Mutex<Vec<ContextKeyDecl>>,
 }
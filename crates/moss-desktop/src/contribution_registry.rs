@@ -1,19 +1,84 @@
 use dashmap::{DashMap, DashSet};
+use hashbrown::{HashMap, HashSet};
+use indexmap::IndexMap;
 use moss_text::ReadOnlyStr;
+use parking_lot::RwLock;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
-use crate::command::CommandHandler;
-use crate::contribution::Contribution;
+use crate::command::{CommandDecl, RegisteredCommand};
+use crate::context_key::ContextKeyDecl;
+use crate::contribution::{Contribution, MenuDecl, TreeViewDecl, TreeViewGroupDecl};
+use crate::keybinding::{
+    Keybinding, KeybindingDecl, KeybindingRegistry, KeybindingSource, Platform,
+};
 use crate::models::application::{LocaleDescriptor, ThemeDescriptor};
+use crate::models::contributions::{ContributionChange, ContributionChangeKind, ContributionOwner};
 use crate::models::{actions::MenuItem, view::*};
 
+/// The event every window is notified of the changes of the contributions with.
+pub const CONTRIBUTIONS_CHANGED_EVENT: &str = "core://contributions-changed";
+
+const CHANGES_CHANNEL_CAPACITY: usize = 16;
+
+/// The contributions of an owner, added to and removed from the registry together.
+#[derive(Debug, Default)]
+pub struct ContributionSet {
+    pub commands: Vec<CommandDecl>,
+    pub menus: Vec<MenuDecl>,
+    pub tree_view_groups: Vec<TreeViewGroupDecl>,
+    pub tree_views: Vec<TreeViewDecl>,
+    pub keybindings: Vec<KeybindingDecl>,
+    pub context_keys: Vec<ContextKeyDecl>,
+    pub themes: Vec<ThemeDescriptor>,
+    pub locales: Vec<LocaleDescriptor>,
+}
+
+impl ContributionSet {
+    pub fn with_command(mut self, decl: CommandDecl) -> Self {
+        self.commands.push(decl);
+        self
+    }
+
+    fn take(&mut self, contrib: &Contribution) {
+        self.commands.append(&mut *contrib.commands.lock());
+        self.menus.append(&mut *contrib.menus.lock());
+        self.tree_view_groups
+            .append(&mut *contrib.tree_view_groups.lock());
+        self.tree_views.append(&mut *contrib.tree_views.lock());
+        self.keybindings.append(&mut *contrib.keybindings.lock());
+        self.context_keys.append(&mut *contrib.context_keys.lock());
+    }
+}
+
+/// The contributions of an owner as kept by the registry, with the commands registered and
+/// the keybindings parsed once.
+struct OwnedContributions {
+    commands: Vec<(ReadOnlyStr, RegisteredCommand)>,
+    menus: Vec<MenuDecl>,
+    tree_view_groups: Vec<TreeViewGroupDecl>,
+    tree_views: Vec<TreeViewDecl>,
+    keybindings: Vec<Keybinding>,
+    /// Shared with the declarations of the context keys, to tell them from the keys of the
+    /// same name declared at runtime.
+    context_keys: Vec<Arc<ContextKeyDecl>>,
+    themes: Vec<ThemeDescriptor>,
+    locales: Vec<LocaleDescriptor>,
+}
+
 pub struct ContributionRegistry {
     pub themes: Arc<DashSet<ThemeDescriptor>>,
     pub locales: Arc<DashSet<LocaleDescriptor>>,
-    pub commands: DashMap<ReadOnlyStr, CommandHandler>,
+    pub commands: DashMap<ReadOnlyStr, RegisteredCommand>,
     pub menus: DashMap<ReadOnlyStr, Vec<MenuItem>>,
     pub tree_view_groups: DashMap<TreeViewGroupLocation, Vec<TreeViewGroup>>,
     pub tree_views: DashMap<GroupId, Vec<TreeViewDescriptor>>,
+    pub keybindings: KeybindingRegistry,
+    pub context_keys: Arc<DashMap<ReadOnlyStr, Arc<ContextKeyDecl>>>,
+    /// The contributions of every owner, in the order they were added. The collections
+    /// above merge them, the contributions added later coming last.
+    owners: RwLock<IndexMap<ContributionOwner, OwnedContributions>>,
+    changes: broadcast::Sender<ContributionChange>,
 }
 
 impl ContributionRegistry {
@@ -25,53 +90,365 @@ impl ContributionRegistry {
             menus: DashMap::new(),
             tree_view_groups: DashMap::new(),
             tree_views: DashMap::new(),
+            keybindings: KeybindingRegistry::new(Platform::current()),
+            context_keys: Arc::new(DashMap::new()),
+            owners: RwLock::new(IndexMap::new()),
+            changes: broadcast::channel(CHANGES_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Adds the contribution points, each owned by the module it is declared in.
     pub fn init<I>(self, contributions: I) -> Self
     where
         I: IntoIterator<Item = &'static Contribution>,
     {
+        let mut sets = IndexMap::<ContributionOwner, ContributionSet>::new();
         for contrib in contributions {
-            for decl in std::mem::take(&mut *contrib.commands.lock()) {
-                self.commands
-                    .insert(decl.name, Arc::new(decl.callback) as CommandHandler);
+            sets.entry(ContributionOwner::builtin(contrib.module))
+                .or_default()
+                .take(contrib);
+        }
+
+        {
+            let mut owners = self.owners.write();
+            for (owner, set) in sets {
+                let owned = self.own(&owner, set);
+                self.declare_context_keys(&owned);
+                owners.insert(owner, owned);
             }
+        }
+        self.rebuild();
 
-            for decl in std::mem::take(&mut *contrib.menus.lock()) {
-                let mut items = decl.items;
+        self
+    }
 
-                self.menus
-                    .entry(decl.namespace)
-                    .and_modify(|existing_namespace| {
-                        existing_namespace.append(&mut items);
-                    })
-                    .or_insert(items);
-            }
+    /// Adds the contributions of the owner, replacing the ones it added before.
+    pub fn add(&self, owner: ContributionOwner, set: ContributionSet) {
+        let owned = self.own(&owner, set);
+        // The keys the owner declares again are replaced first, so only the ones it no longer
+        // declares are forgotten.
+        self.declare_context_keys(&owned);
+        if let Some(replaced) = self.owners.write().insert(owner.clone(), owned) {
+            self.forget_context_keys(&replaced);
+        }
+        self.rebuild();
 
-            for decl in std::mem::take(&mut *contrib.tree_view_groups.lock()) {
-                let mut items = decl.items;
+        self.notify(ContributionChange {
+            owner,
+            kind: ContributionChangeKind::Added,
+        });
+    }
 
-                self.tree_view_groups
-                    .entry(decl.location)
-                    .and_modify(|existing_group| {
-                        existing_group.append(&mut items);
-                    })
-                    .or_insert(items);
-            }
+    /// Removes all the contributions of the owner, returns `false` if it has none.
+    pub fn remove(&self, owner: &ContributionOwner) -> bool {
+        let Some(removed) = self.owners.write().shift_remove(owner) else {
+            return false;
+        };
+        self.forget_context_keys(&removed);
+        self.rebuild();
+
+        self.notify(ContributionChange {
+            owner: owner.clone(),
+            kind: ContributionChangeKind::Removed,
+        });
 
-            for decl in std::mem::take(&mut *contrib.tree_views.lock()) {
-                let mut items = decl.items;
+        true
+    }
+
+    pub fn owners(&self) -> Vec<ContributionOwner> {
+        self.owners.read().keys().cloned().collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ContributionChange> {
+        self.changes.subscribe()
+    }
+
+    fn own(&self, owner: &ContributionOwner, set: ContributionSet) -> OwnedContributions {
+        let source = match owner {
+            ContributionOwner::Builtin { .. } => KeybindingSource::Default,
+            ContributionOwner::Addon { id } => KeybindingSource::Addon(id.clone()),
+        };
+
+        OwnedContributions {
+            commands: set
+                .commands
+                .into_iter()
+                .map(|decl| (decl.name.clone(), decl.register()))
+                .collect(),
+            menus: set.menus,
+            tree_view_groups: set.tree_view_groups,
+            tree_views: set.tree_views,
+            keybindings: set
+                .keybindings
+                .into_iter()
+                .filter_map(|decl| self.keybindings.parse_default(decl, source.clone()))
+                .collect(),
+            context_keys: set.context_keys.into_iter().map(Arc::new).collect(),
+            themes: set.themes,
+            locales: set.locales,
+        }
+    }
+
+    /// Declares the context keys of the owner, replacing the declarations of the same name.
+    /// They are declared once, rather than on every rebuild, not to replace the keys
+    /// declared at runtime since.
+    fn declare_context_keys(&self, contributions: &OwnedContributions) {
+        for decl in &contributions.context_keys {
+            self.context_keys.insert(decl.key.clone(), Arc::clone(decl));
+        }
+    }
 
-                self.tree_views
+    /// The context keys are shared with the keys declared at runtime, so only the declarations
+    /// of the owner are removed, not the ones that replaced them since.
+    fn forget_context_keys(&self, contributions: &OwnedContributions) {
+        for decl in &contributions.context_keys {
+            self.context_keys
+                .remove_if(&decl.key, |_, current| Arc::ptr_eq(current, decl));
+        }
+    }
+
+    /// Merges the contributions of the owners into the collections. The entries are replaced
+    /// in place rather than cleared first, so that a reader never sees them missing.
+    fn rebuild(&self) {
+        let owners = self.owners.read();
+
+        let mut commands = HashMap::new();
+        let mut menus = HashMap::<ReadOnlyStr, Vec<MenuItem>>::new();
+        let mut tree_view_groups = HashMap::<TreeViewGroupLocation, Vec<TreeViewGroup>>::new();
+        let mut tree_views = HashMap::<GroupId, Vec<TreeViewDescriptor>>::new();
+        let mut keybindings = Vec::new();
+        let mut themes = HashSet::new();
+        let mut locales = HashSet::new();
+        for contributions in owners.values() {
+            for (id, command) in &contributions.commands {
+                commands.insert(id.clone(), command.clone());
+            }
+            for decl in &contributions.menus {
+                menus
+                    .entry(decl.namespace.clone())
+                    .or_default()
+                    .extend(decl.items.iter().cloned());
+            }
+            for decl in &contributions.tree_view_groups {
+                tree_view_groups
+                    .entry(decl.location)
+                    .or_default()
+                    .extend(decl.items.iter().cloned());
+            }
+            for decl in &contributions.tree_views {
+                tree_views
                     .entry(decl.group_id.into())
-                    .and_modify(|existing_group| {
-                        existing_group.append(&mut items);
-                    })
-                    .or_insert(items);
+                    .or_default()
+                    .extend(decl.items.iter().cloned());
             }
+            keybindings.extend(contributions.keybindings.iter().cloned());
+            themes.extend(contributions.themes.iter().cloned());
+            locales.extend(contributions.locales.iter().cloned());
         }
 
-        self
+        replace_map(&self.commands, commands);
+        replace_map(&self.menus, menus);
+        replace_map(&self.tree_view_groups, tree_view_groups);
+        replace_map(&self.tree_views, tree_views);
+        replace_set(&self.themes, themes);
+        replace_set(&self.locales, locales);
+        self.keybindings.set_defaults(keybindings);
+    }
+
+    fn notify(&self, change: ContributionChange) {
+        // There may be no subscriber yet, which is not an error.
+        let _ = self.changes.send(change);
+    }
+}
+
+impl Default for ContributionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn replace_map<K, V>(map: &DashMap<K, V>, entries: HashMap<K, V>)
+where
+    K: Eq + std::hash::Hash + Clone,
+{
+    map.retain(|key, _| entries.contains_key(key));
+    for (key, value) in entries {
+        map.insert(key, value);
+    }
+}
+
+fn replace_set<T>(set: &DashSet<T>, items: HashSet<T>)
+where
+    T: Eq + std::hash::Hash + Clone,
+{
+    set.retain(|item| items.contains(item));
+    for item in items {
+        set.insert(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moss_tauri::TauriError;
+    use serde_json::Value;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use crate::{
+        app::state::AppStateManager,
+        command::{CommandCallback, CommandContext},
+        models::context_keys::ContextKeyType,
+    };
+
+    fn noop(_: CommandContext, _: &AppStateManager) -> Result<Value, TauriError> {
+        Ok(Value::Null)
+    }
+
+    fn command(id: &str) -> CommandDecl {
+        CommandDecl::new(id, CommandCallback::Sync(noop))
+    }
+
+    fn context_key(key: &str) -> ContextKeyDecl {
+        ContextKeyDecl {
+            key: ReadOnlyStr::from(key),
+            ty: ContextKeyType::Boolean,
+            default: Value::Bool(false),
+            description: None,
+        }
+    }
+
+    fn theme(id: &str) -> ThemeDescriptor {
+        ThemeDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            source: format!("{id}.css"),
+        }
+    }
+
+    fn commands(registry: &ContributionRegistry) -> Vec<String> {
+        let mut commands = registry
+            .commands
+            .iter()
+            .map(|entry| entry.key().to_string())
+            .collect::<Vec<_>>();
+        commands.sort();
+
+        commands
+    }
+
+    fn keybindings(registry: &ContributionRegistry) -> Vec<String> {
+        registry
+            .keybindings
+            .keybindings()
+            .into_iter()
+            .map(|keybinding| keybinding.command.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_add_replaces_the_contributions_of_the_owner() {
+        let registry = ContributionRegistry::new();
+        let mut set = ContributionSet::default().with_command(command("addon.first"));
+        set.keybindings
+            .push(KeybindingDecl::new("ctrl+k", "addon.first"));
+        set.context_keys.push(context_key("addon.visible"));
+        set.context_keys.push(context_key("addon.active"));
+        set.themes.push(theme("addon.dark"));
+        registry.add(ContributionOwner::addon("addon"), set);
+
+        let mut set = ContributionSet::default().with_command(command("addon.second"));
+        set.context_keys.push(context_key("addon.visible"));
+        set.themes.push(theme("addon.light"));
+        registry.add(ContributionOwner::addon("addon"), set);
+
+        assert_eq!(registry.owners(), [ContributionOwner::addon("addon")]);
+        assert_eq!(commands(&registry), ["addon.second"]);
+        assert!(keybindings(&registry).is_empty());
+        assert!(registry.context_keys.contains_key("addon.visible"));
+        assert!(!registry.context_keys.contains_key("addon.active"));
+        let themes = registry
+            .themes
+            .iter()
+            .map(|theme| theme.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(themes, ["addon.light"]);
+    }
+
+    #[test]
+    fn test_remove_keeps_the_other_owners() {
+        let registry = ContributionRegistry::new();
+        for id in ["first", "second"] {
+            let mut set = ContributionSet::default().with_command(command(&format!("{id}.run")));
+            set.keybindings
+                .push(KeybindingDecl::new("ctrl+r", format!("{id}.run")));
+            set.menus.push(MenuDecl {
+                namespace: ReadOnlyStr::from("menubar.file"),
+                items: Vec::new(),
+            });
+            set.context_keys.push(context_key(&format!("{id}.visible")));
+            registry.add(ContributionOwner::addon(id), set);
+        }
+        // The owners added later take precedence.
+        assert_eq!(keybindings(&registry), ["second.run", "first.run"]);
+
+        assert!(registry.remove(&ContributionOwner::addon("first")));
+        assert!(!registry.remove(&ContributionOwner::addon("first")));
+
+        assert_eq!(registry.owners(), [ContributionOwner::addon("second")]);
+        assert_eq!(commands(&registry), ["second.run"]);
+        assert_eq!(keybindings(&registry), ["second.run"]);
+        assert!(registry.menus.contains_key("menubar.file"));
+        assert!(!registry.context_keys.contains_key("first.visible"));
+        assert!(registry.context_keys.contains_key("second.visible"));
+
+        assert!(registry.remove(&ContributionOwner::addon("second")));
+        assert!(registry.commands.is_empty());
+        assert!(registry.menus.is_empty());
+    }
+
+    #[test]
+    fn test_changes_are_notified() {
+        let registry = ContributionRegistry::new();
+        let mut changes = registry.subscribe();
+        let owner = ContributionOwner::addon("addon");
+
+        registry.add(owner.clone(), ContributionSet::default());
+        registry.remove(&owner);
+        registry.remove(&owner);
+
+        let change = changes.try_recv().unwrap();
+        assert_eq!(
+            (change.owner, change.kind),
+            (owner.clone(), ContributionChangeKind::Added)
+        );
+        let change = changes.try_recv().unwrap();
+        assert_eq!(
+            (change.owner, change.kind),
+            (owner, ContributionChangeKind::Removed)
+        );
+        assert!(matches!(changes.try_recv(), Err(TryRecvError::Empty)));
+    }
+
+    #[test]
+    fn test_runtime_context_keys_are_kept() {
+        let registry = ContributionRegistry::new();
+        let owner = ContributionOwner::addon("addon");
+        let mut set = ContributionSet::default();
+        set.context_keys.push(context_key("addon.visible"));
+        registry.add(owner.clone(), set);
+
+        // Declared again at runtime, as `ContextKeyService::declare` does.
+        let declared = Arc::new(context_key("addon.visible"));
+        registry
+            .context_keys
+            .insert(ReadOnlyStr::from("addon.visible"), Arc::clone(&declared));
+        registry.add(
+            ContributionOwner::addon("other"),
+            ContributionSet::default(),
+        );
+        registry.remove(&owner);
+
+        let current = registry.context_keys.get("addon.visible").unwrap();
+        assert!(Arc::ptr_eq(&current, &declared));
     }
 }
@@ -10,11 +10,18 @@ use crate::{
             ActionMenuItem, CommandAction, CommandActionToggle, MenuGroup, MenuItem,
             MenuItemVisibility, SubmenuMenuItem,
         },
-        constants,
+        constants::{
+            self,
+            context_key::{CONTEXT_KEY_TOGGLE_PANEL, CONTEXT_KEY_TOGGLE_SECONDARY_SIDEBAR},
+        },
     },
 };
 
 contribution_point!(LAYOUT_CONTROLS, {
+    context_keys: [
+        CONTEXT_KEY_TOGGLE_SECONDARY_SIDEBAR.decl(),
+        CONTEXT_KEY_TOGGLE_PANEL.decl(),
+    ],
     menus: [
         MenuDecl {
             namespace: constants::menu::MENU_NAMESPACE_ID_HEAD_ITEM,
@@ -1,28 +1,108 @@
+use moss_tauri::TauriError;
 use moss_text::{localize, read_only_str};
-use serde_json::Value;
 use tauri::{Emitter, EventTarget, Manager};
 
 use crate::{
-    app::state::AppStateManager,
-    command::{CommandContext, CommandDecl},
-    contribution::TreeViewGroupDecl,
+    app::{manager::AppManager, state::AppStateManager},
+    command::{CommandContext, CommandDecl, TypedCommand},
+    command_handler,
+    contribution::{MenuDecl, TreeViewGroupDecl},
     contribution_point,
+    keybinding::KeybindingDecl,
     models::{
-        application::{LocaleDescriptor, ThemeDescriptor},
-        constants,
+        actions::{
+            ActionMenuItem, CommandAction, MenuGroup, MenuItem, MenuItemVisibility, SubmenuMenuItem,
+        },
+        commands::{ChangeColorThemeArgs, ChangeLanguagePackArgs},
+        constants::{
+            self,
+            context_key::{CONTEXT_KEY_LOCALE, CONTEXT_KEY_THEME},
+            menu::{MENU_NAMESPACE_ID_MENUBAR, MENU_NAMESPACE_ID_MENUBAR_FILE},
+        },
+        context_keys::ContextScope,
         view::*,
     },
+    services::{
+        context_key_service::ContextKeyService, locale_service::LocaleService,
+        theme_service::ThemeService,
+    },
 };
 
 contribution_point!(WORKBENCH, {
     commands: [
-        CommandDecl {
-            name: read_only_str!("workbench.changeColorTheme"),
-            callback: change_color_theme,
+        CommandDecl::typed::<ChangeColorThemeCommand>()
+            .with_title(localize!("workbench.changeColorTheme.title", "Change Color Theme"))
+            .with_category(localize!("workbench.category.preferences", "Preferences"))
+            .with_icon("ColorTheme")
+            .with_args_description(localize!(
+                "workbench.changeColorTheme.args",
+                "The theme to switch to"
+            )),
+        CommandDecl::typed::<ChangeLanguagePackCommand>()
+            .with_title(localize!("workbench.changeLanguagePack.title", "Change Display Language"))
+            .with_category(localize!("workbench.category.preferences", "Preferences"))
+            .with_icon("Language")
+            .with_args_description(localize!(
+                "workbench.changeLanguagePack.args",
+                "The locale to switch to"
+            )),
+    ],
+    context_keys: [
+        CONTEXT_KEY_THEME.decl(),
+        CONTEXT_KEY_LOCALE.decl(),
+    ],
+    keybindings: [
+        // The command is registered by the application, which owns the window creation.
+        KeybindingDecl::new("mod+n", "workbench.newWindow"),
+    ],
+    menus: [
+        MenuDecl {
+            namespace: MENU_NAMESPACE_ID_MENUBAR,
+            items: vec![
+                MenuItem::Submenu(SubmenuMenuItem {
+                    submenu_id: MENU_NAMESPACE_ID_MENUBAR_FILE,
+                    default_action_id: None,
+                    title: Some(localize!("menubar.file", "File")),
+                    group: None,
+                    order: Some(1),
+                    when: None,
+                    visibility: MenuItemVisibility::Classic,
+                }),
+            ],
         },
-        CommandDecl {
-            name: read_only_str!("workbench.changeLanguagePack"),
-            callback: change_language_pack,
+        MenuDecl {
+            namespace: MENU_NAMESPACE_ID_MENUBAR_FILE,
+            items: vec![
+                // The window commands are registered by the application as well.
+                MenuItem::Action(ActionMenuItem {
+                    command: CommandAction {
+                        id: read_only_str!("workbench.newWindow"),
+                        title: localize!("workbench.newWindow.title", "New Window"),
+                        tooltip: None,
+                        description: None,
+                        icon: None,
+                        toggled: None,
+                    },
+                    group: Some(MenuGroup::ordered(1, "window")),
+                    order: Some(1),
+                    when: None,
+                    visibility: MenuItemVisibility::Classic,
+                }),
+                MenuItem::Action(ActionMenuItem {
+                    command: CommandAction {
+                        id: read_only_str!("workbench.closeWindow"),
+                        title: localize!("workbench.closeWindow.title", "Close Window"),
+                        tooltip: None,
+                        description: None,
+                        icon: None,
+                        toggled: None,
+                    },
+                    group: Some(MenuGroup::ordered(2, "close")),
+                    order: Some(1),
+                    when: None,
+                    visibility: MenuItemVisibility::Classic,
+                }),
+            ],
         },
     ],
     tree_view_groups: [
@@ -39,19 +119,34 @@ contribution_point!(WORKBENCH, {
     ]
 });
 
+#[command_handler("workbench.changeColorTheme")]
 pub fn change_color_theme(
     ctx: CommandContext,
-    app_state: &AppStateManager,
-) -> Result<Value, String> {
-    let theme_descriptor_arg = ctx.take_arg::<ThemeDescriptor>("themeDescriptor")?;
+    args: ChangeColorThemeArgs,
+) -> Result<(), TauriError> {
+    let theme_descriptor_arg = args.theme_descriptor;
 
+    let app_state = ctx.app_handle.state::<AppStateManager>();
+    let app_manager = ctx.app_handle.state::<AppManager>();
+    let previous = match app_state.preferences.theme.read().clone() {
+        Some(theme) => theme,
+        None => app_manager.service::<ThemeService>()?.default_color_theme(),
+    };
     app_state.change_color_theme(theme_descriptor_arg.clone());
+    ctx.set_inverse(
+        ChangeColorThemeCommand::ID,
+        ChangeColorThemeArgs {
+            theme_descriptor: previous,
+        },
+    );
+    app_manager.service::<ContextKeyService>()?.set(
+        &CONTEXT_KEY_THEME,
+        ContextScope::Global,
+        theme_descriptor_arg.id.clone(),
+    );
 
+    // The calling window is notified as well, as it is the one undoing or redoing the change.
     for (label, _) in ctx.app_handle.webview_windows() {
-        if ctx.window.label() == &label {
-            continue;
-        }
-
         ctx.app_handle
             .emit_to(
                 EventTarget::webview_window(label),
@@ -61,22 +156,40 @@ pub fn change_color_theme(
             .unwrap();
     }
 
-    Ok(Value::Null)
+    Ok(())
 }
 
+#[command_handler("workbench.changeLanguagePack")]
 pub fn change_language_pack(
     ctx: CommandContext,
-    app_state: &AppStateManager,
-) -> Result<Value, String> {
-    let locale_descriptor_arg = ctx.take_arg::<LocaleDescriptor>("localeDescriptor")?;
+    args: ChangeLanguagePackArgs,
+) -> Result<(), TauriError> {
+    let locale_descriptor_arg = args.locale_descriptor;
 
+    let app_state = ctx.app_handle.state::<AppStateManager>();
+    let app_manager = ctx.app_handle.state::<AppManager>();
+    let previous = match app_state.preferences.locale.read().clone() {
+        Some(locale) => locale,
+        None => app_manager
+            .service::<LocaleService>()?
+            .default_locale()
+            .clone(),
+    };
     app_state.change_language_pack(locale_descriptor_arg.clone());
+    ctx.set_inverse(
+        ChangeLanguagePackCommand::ID,
+        ChangeLanguagePackArgs {
+            locale_descriptor: previous,
+        },
+    );
+    app_manager.service::<ContextKeyService>()?.set(
+        &CONTEXT_KEY_LOCALE,
+        ContextScope::Global,
+        locale_descriptor_arg.code.clone(),
+    );
 
+    // The calling window is notified as well, as it is the one undoing or redoing the change.
     for (label, _) in ctx.app_handle.webview_windows() {
-        if ctx.window.label() == &label {
-            continue;
-        }
-
         ctx.app_handle
             .emit_to(
                 EventTarget::webview_window(label),
@@ -86,5 +199,5 @@ pub fn change_language_pack(
             .unwrap();
     }
 
-    Ok(Value::Null)
+    Ok(())
 }
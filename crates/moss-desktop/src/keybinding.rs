@@ -0,0 +1,929 @@
+use anyhow::{Context as _, Result};
+use dashmap::DashMap;
+use moss_jsonlogic::raw_rule::RawRule;
+use moss_text::ReadOnlyStr;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde_json::Value;
+use smallvec::SmallVec;
+use std::{fmt, path::Path};
+use thiserror::Error;
+
+use crate::models::keybindings::{
+    KeyEvent, KeybindingConflict, KeybindingConflictKind, KeybindingDescriptor,
+    KeybindingResolution,
+};
+
+#[derive(Error, Debug)]
+pub enum KeybindingError {
+    #[error("Invalid keybinding '{binding}': {reason}")]
+    Invalid {
+        binding: String,
+        reason: &'static str,
+    },
+
+    #[error("Unknown modifier '{modifier}' in keybinding '{binding}'")]
+    UnknownModifier { binding: String, modifier: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    MacOS,
+    Linux,
+    Windows,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOS
+        } else if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// The modifiers of a chord, as bit flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    pub const NONE: Self = Self(0);
+    pub const CTRL: Self = Self(1);
+    pub const SHIFT: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+    pub const META: Self = Self(1 << 3);
+
+    /// The modifier of the primary shortcuts: `Cmd` on macOS and `Ctrl` elsewhere.
+    pub fn primary(platform: Platform) -> Self {
+        match platform {
+            Platform::MacOS => Self::META,
+            Platform::Linux | Platform::Windows => Self::CTRL,
+        }
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    fn parse(modifier: &str, platform: Platform) -> Option<Self> {
+        match modifier {
+            "ctrl" | "control" => Some(Self::CTRL),
+            "shift" => Some(Self::SHIFT),
+            "alt" | "option" => Some(Self::ALT),
+            "meta" | "cmd" | "command" | "super" | "win" => Some(Self::META),
+            "mod" | "cmdorctrl" | "commandorcontrol" => Some(Self::primary(platform)),
+            _ => None,
+        }
+    }
+}
+
+/// A key pressed together with modifiers, e.g. `ctrl+shift+p`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: KeyModifiers,
+    /// The key, named after the physical key rather than the character it types.
+    pub key: ReadOnlyStr,
+}
+
+impl KeyChord {
+    pub fn parse(chord: &str, platform: Platform) -> Result<Self, KeybindingError> {
+        let invalid = |reason| KeybindingError::Invalid {
+            binding: chord.to_string(),
+            reason,
+        };
+
+        let lowercase = chord.trim().to_lowercase();
+        // The `+` key itself is written as the last part, e.g. `ctrl++`.
+        let (modifiers, key) = match lowercase.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => lowercase
+                .rsplit_once('+')
+                .unwrap_or(("", lowercase.as_str())),
+        };
+        if key.is_empty() {
+            return Err(invalid("the key is missing"));
+        }
+
+        let mut parsed = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            let modifier = KeyModifiers::parse(modifier.trim(), platform).ok_or_else(|| {
+                KeybindingError::UnknownModifier {
+                    binding: chord.to_string(),
+                    modifier: modifier.to_string(),
+                }
+            })?;
+            parsed.insert(modifier);
+        }
+
+        Ok(Self {
+            modifiers: parsed,
+            key: ReadOnlyStr::from(normalize_key(key)),
+        })
+    }
+
+    /// The chord of a key event, `None` for a modifier pressed alone.
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        let key = normalize_code(&event.code)?;
+        let mut modifiers = KeyModifiers::NONE;
+        for (pressed, modifier) in [
+            (event.ctrl_key, KeyModifiers::CTRL),
+            (event.shift_key, KeyModifiers::SHIFT),
+            (event.alt_key, KeyModifiers::ALT),
+            (event.meta_key, KeyModifiers::META),
+        ] {
+            if pressed {
+                modifiers.insert(modifier);
+            }
+        }
+
+        Some(Self {
+            modifiers,
+            key: ReadOnlyStr::from(key),
+        })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CTRL, "ctrl+"),
+            (KeyModifiers::SHIFT, "shift+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::META, "meta+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        f.write_str(&self.key)
+    }
+}
+
+/// The chords pressed one after another to trigger a binding, e.g. `ctrl+k ctrl+s`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(SmallVec<[KeyChord; 2]>);
+
+impl KeySequence {
+    pub fn parse(sequence: &str, platform: Platform) -> Result<Self, KeybindingError> {
+        let chords = sequence
+            .split_whitespace()
+            .map(|chord| KeyChord::parse(chord, platform))
+            .collect::<Result<SmallVec<_>, _>>()?;
+        if chords.is_empty() {
+            return Err(KeybindingError::Invalid {
+                binding: sequence.to_string(),
+                reason: "the keybinding is empty",
+            });
+        }
+
+        Ok(Self(chords))
+    }
+
+    pub fn chords(&self) -> &[KeyChord] {
+        &self.0
+    }
+
+    /// Whether the chords pressed so far are the beginning of the sequence.
+    pub fn starts_with(&self, chords: &[KeyChord]) -> bool {
+        self.0.starts_with(chords)
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, chord) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A default keybinding, contributed with the `keybindings` field of `contribution_point!`
+/// or by an addon.
+#[derive(Debug, Clone)]
+pub struct KeybindingDecl {
+    pub key: ReadOnlyStr,
+    /// Replace the key on the platform, when it differs from the other platforms.
+    pub mac: Option<ReadOnlyStr>,
+    pub linux: Option<ReadOnlyStr>,
+    pub win: Option<ReadOnlyStr>,
+    pub command: ReadOnlyStr,
+    pub args: Option<Value>,
+    /// The binding only applies while the rule holds for the context of the window.
+    pub when: Option<RawRule>,
+}
+
+impl KeybindingDecl {
+    pub fn new(key: impl Into<ReadOnlyStr>, command: impl Into<ReadOnlyStr>) -> Self {
+        Self {
+            key: key.into(),
+            mac: None,
+            linux: None,
+            win: None,
+            command: command.into(),
+            args: None,
+            when: None,
+        }
+    }
+
+    pub fn with_mac(mut self, key: impl Into<ReadOnlyStr>) -> Self {
+        self.mac = Some(key.into());
+        self
+    }
+
+    pub fn with_linux(mut self, key: impl Into<ReadOnlyStr>) -> Self {
+        self.linux = Some(key.into());
+        self
+    }
+
+    pub fn with_win(mut self, key: impl Into<ReadOnlyStr>) -> Self {
+        self.win = Some(key.into());
+        self
+    }
+
+    pub fn with_args(mut self, args: Value) -> Self {
+        self.args = Some(args);
+        self
+    }
+
+    pub fn with_when(mut self, rule: RawRule) -> Self {
+        self.when = Some(rule);
+        self
+    }
+
+    fn key_for(&self, platform: Platform) -> &ReadOnlyStr {
+        let key = match platform {
+            Platform::MacOS => self.mac.as_ref(),
+            Platform::Linux => self.linux.as_ref(),
+            Platform::Windows => self.win.as_ref(),
+        };
+
+        key.unwrap_or(&self.key)
+    }
+}
+
+/// An entry of the keybindings file of the user. A command prefixed with `-` removes
+/// the default bindings of the command to the key instead.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserKeybinding {
+    pub key: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Option<Value>,
+    #[serde(default)]
+    pub when: Option<RawRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeybindingSource {
+    Default,
+    Addon(String),
+    User,
+}
+
+impl fmt::Display for KeybindingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeybindingSource::Default => f.write_str("default"),
+            KeybindingSource::Addon(id) => write!(f, "addon:{id}"),
+            KeybindingSource::User => f.write_str("user"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybinding {
+    pub sequence: KeySequence,
+    pub command: ReadOnlyStr,
+    pub args: Option<Value>,
+    pub when: Option<RawRule>,
+    pub source: KeybindingSource,
+}
+
+impl Keybinding {
+    fn applies(&self, context: &Value) -> bool {
+        let Some(rule) = &self.when else {
+            return true;
+        };
+
+        rule.is_satisfied(context).unwrap_or_else(|err| {
+            warn!(
+                "Failed to evaluate the condition of keybinding '{}' for command {}: {err}",
+                self.sequence, self.command
+            );
+            false
+        })
+    }
+
+    /// Whether both bindings can apply in the same context, as far as it can be told
+    /// without one.
+    fn overlaps(&self, other: &Keybinding) -> bool {
+        match (&self.when, &other.when) {
+            // The rules read from the files are kept as constants, so they are compared in
+            // their JSON Logic form.
+            (Some(when), Some(other_when)) => {
+                serde_json::to_value(when).ok() == serde_json::to_value(other_when).ok()
+            }
+            _ => true,
+        }
+    }
+}
+
+/// A default binding removed by the user.
+#[derive(Debug, Clone)]
+struct KeybindingRemoval {
+    sequence: KeySequence,
+    command: ReadOnlyStr,
+}
+
+/// Resolves the key events of the windows to commands. The bindings of the user take
+/// precedence over the default ones, and among each of them the later ones over the earlier.
+pub struct KeybindingRegistry {
+    platform: Platform,
+    defaults: RwLock<Vec<Keybinding>>,
+    user: RwLock<Vec<Keybinding>>,
+    removals: RwLock<Vec<KeybindingRemoval>>,
+    /// The chords pressed so far in each window, while a sequence is being entered.
+    pending: DashMap<String, SmallVec<[KeyChord; 2]>>,
+}
+
+impl KeybindingRegistry {
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            defaults: RwLock::new(Vec::new()),
+            user: RwLock::new(Vec::new()),
+            removals: RwLock::new(Vec::new()),
+            pending: DashMap::new(),
+        }
+    }
+
+    /// Parses a default binding for the platform, none if its key cannot be parsed.
+    pub fn parse_default(
+        &self,
+        decl: KeybindingDecl,
+        source: KeybindingSource,
+    ) -> Option<Keybinding> {
+        let key = decl.key_for(self.platform);
+        match KeySequence::parse(key, self.platform) {
+            Ok(sequence) => Some(Keybinding {
+                sequence,
+                command: decl.command,
+                args: decl.args,
+                when: decl.when,
+                source,
+            }),
+            Err(err) => {
+                warn!("Skipping the keybinding of command {}: {err}", decl.command);
+                None
+            }
+        }
+    }
+
+    /// Replaces the default bindings, the later ones taking precedence.
+    pub fn set_defaults(&self, keybindings: Vec<Keybinding>) {
+        *self.defaults.write() = keybindings;
+    }
+
+    /// Loads the keybindings of the user, replacing the ones loaded before. A missing file
+    /// leaves the user with the default bindings.
+    pub fn load_user_keybindings(&self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            self.set_user_keybindings(Vec::new());
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the keybindings file {path:?}"))?;
+        let keybindings = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the keybindings file {path:?}"))?;
+        self.set_user_keybindings(keybindings);
+
+        Ok(())
+    }
+
+    pub fn set_user_keybindings(&self, keybindings: Vec<UserKeybinding>) {
+        let mut user = Vec::new();
+        let mut removals = Vec::new();
+        for keybinding in keybindings {
+            let sequence = match KeySequence::parse(&keybinding.key, self.platform) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    warn!(
+                        "Skipping the user keybinding of {}: {err}",
+                        keybinding.command
+                    );
+                    continue;
+                }
+            };
+
+            match keybinding.command.strip_prefix('-') {
+                Some(command) => removals.push(KeybindingRemoval {
+                    sequence,
+                    command: ReadOnlyStr::from(command),
+                }),
+                None => user.push(Keybinding {
+                    sequence,
+                    command: ReadOnlyStr::from(keybinding.command.as_str()),
+                    args: keybinding.args,
+                    when: keybinding.when,
+                    source: KeybindingSource::User,
+                }),
+            }
+        }
+
+        *self.user.write() = user;
+        *self.removals.write() = removals;
+    }
+
+    /// The bindings in effect, the ones that take precedence first.
+    pub fn keybindings(&self) -> Vec<Keybinding> {
+        let removals = self.removals.read();
+        let is_removed = |keybinding: &Keybinding| {
+            removals.iter().any(|removal| {
+                removal.sequence == keybinding.sequence && removal.command == keybinding.command
+            })
+        };
+
+        let user = self.user.read();
+        let defaults = self.defaults.read();
+        user.iter()
+            .rev()
+            .chain(
+                defaults
+                    .iter()
+                    .rev()
+                    .filter(|keybinding| !is_removed(keybinding)),
+            )
+            .cloned()
+            .collect()
+    }
+
+    pub fn describe(&self) -> Vec<KeybindingDescriptor> {
+        self.keybindings()
+            .into_iter()
+            .map(|keybinding| KeybindingDescriptor {
+                key: keybinding.sequence.to_string(),
+                command: keybinding.command.to_string(),
+                args: keybinding.args,
+                when: keybinding
+                    .when
+                    .and_then(|when| serde_json::to_value(when).ok()),
+                source: keybinding.source.to_string(),
+            })
+            .collect()
+    }
+
+    /// Finds the bindings that can never be triggered: the ones bound to the same keys as
+    /// a binding that takes precedence, and the ones whose keys start a longer sequence.
+    pub fn conflicts(&self) -> Vec<KeybindingConflict> {
+        let keybindings = self.keybindings();
+        let mut conflicts: Vec<KeybindingConflict> = Vec::new();
+
+        for (index, keybinding) in keybindings.iter().enumerate() {
+            let key = keybinding.sequence.to_string();
+            if conflicts.iter().any(|conflict| {
+                conflict.kind == KeybindingConflictKind::Duplicate && conflict.key == key
+            }) {
+                continue;
+            }
+
+            let duplicates = keybindings[index + 1..]
+                .iter()
+                .filter(|other| {
+                    other.sequence == keybinding.sequence
+                        && other.command != keybinding.command
+                        && other.overlaps(keybinding)
+                })
+                .map(|other| other.command.to_string())
+                .collect::<Vec<_>>();
+            if !duplicates.is_empty() {
+                conflicts.push(KeybindingConflict {
+                    kind: KeybindingConflictKind::Duplicate,
+                    key: key.clone(),
+                    commands: std::iter::once(keybinding.command.to_string())
+                        .chain(duplicates)
+                        .collect(),
+                });
+            }
+
+            let extended_by = keybindings
+                .iter()
+                .filter(|other| {
+                    other.sequence.chords().len() > keybinding.sequence.chords().len()
+                        && other.sequence.starts_with(keybinding.sequence.chords())
+                        && other.overlaps(keybinding)
+                })
+                .map(|other| other.command.to_string())
+                .collect::<Vec<_>>();
+            if !extended_by.is_empty() {
+                conflicts.push(KeybindingConflict {
+                    kind: KeybindingConflictKind::ShadowedPrefix,
+                    key,
+                    commands: std::iter::once(keybinding.command.to_string())
+                        .chain(extended_by)
+                        .collect(),
+                });
+            }
+        }
+
+        conflicts
+    }
+
+    /// Resolves a key event of the window, given the context the `when` rules are evaluated
+    /// against. A chord that starts a longer sequence is kept until the next event.
+    pub fn resolve(&self, window: &str, event: &KeyEvent, context: &Value) -> KeybindingResolution {
+        let Some(chord) = KeyChord::from_event(event) else {
+            return KeybindingResolution::NoMatch;
+        };
+
+        let mut chords = self
+            .pending
+            .remove(window)
+            .map(|(_, chords)| chords)
+            .unwrap_or_default();
+        let continues_sequence = !chords.is_empty();
+        chords.push(chord);
+
+        let candidates = self
+            .keybindings()
+            .into_iter()
+            .filter(|keybinding| keybinding.sequence.starts_with(&chords))
+            .filter(|keybinding| keybinding.applies(context))
+            .collect::<Vec<_>>();
+
+        // The longer sequences win over the ones that are complete already, so that they
+        // can be entered at all.
+        if candidates
+            .iter()
+            .any(|keybinding| keybinding.sequence.chords().len() > chords.len())
+        {
+            let pending = chords.iter().map(ToString::to_string).collect();
+            self.pending.insert(window.to_string(), chords);
+            return KeybindingResolution::Pending { chords: pending };
+        }
+
+        match candidates.into_iter().next() {
+            Some(keybinding) => KeybindingResolution::Command {
+                command: keybinding.command.to_string(),
+                args: keybinding.args,
+            },
+            None if continues_sequence => {
+                trace!("No keybinding for the sequence of window {window}, discarding it");
+                KeybindingResolution::NoMatch
+            }
+            None => KeybindingResolution::NoMatch,
+        }
+    }
+
+    /// Discards the sequence being entered in the window, e.g. when it loses the focus.
+    pub fn clear_pending(&self, window: &str) {
+        self.pending.remove(window);
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    match key {
+        // `+` is typed with the `=` key, as which `normalize_code` names it.
+        "+" => "=",
+        "esc" => "escape",
+        "return" => "enter",
+        "del" => "delete",
+        "ins" => "insert",
+        "pgup" => "pageup",
+        "pgdown" => "pagedown",
+        "arrowup" => "up",
+        "arrowdown" => "down",
+        "arrowleft" => "left",
+        "arrowright" => "right",
+        key => key,
+    }
+    .to_string()
+}
+
+/// Names the physical key of `KeyboardEvent.code` the way the keybindings do, so that
+/// the bindings do not depend on the keyboard layout.
+fn normalize_code(code: &str) -> Option<String> {
+    let key = match code {
+        "ControlLeft" | "ControlRight" | "ShiftLeft" | "ShiftRight" | "AltLeft" | "AltRight"
+        | "MetaLeft" | "MetaRight" | "OSLeft" | "OSRight" | "" => return None,
+        "Minus" => "-",
+        "Equal" => "=",
+        "BracketLeft" => "[",
+        "BracketRight" => "]",
+        "Backslash" => "\\",
+        "Semicolon" => ";",
+        "Quote" => "'",
+        "Comma" => ",",
+        "Period" => ".",
+        "Slash" => "/",
+        "Backquote" => "`",
+        code => {
+            let code = code
+                .strip_prefix("Key")
+                .or_else(|| code.strip_prefix("Digit"))
+                .or_else(|| code.strip_prefix("Arrow"))
+                .unwrap_or(code);
+
+            return Some(normalize_key(&code.to_lowercase()));
+        }
+    };
+
+    Some(key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(chord: &str) -> KeyEvent {
+        let (modifiers, code) = chord.rsplit_once('+').unwrap_or(("", chord));
+        KeyEvent {
+            code: code.to_string(),
+            ctrl_key: modifiers.contains("ctrl"),
+            shift_key: modifiers.contains("shift"),
+            alt_key: modifiers.contains("alt"),
+            meta_key: modifiers.contains("meta"),
+        }
+    }
+
+    fn registry(defaults: &[(&str, &str)]) -> KeybindingRegistry {
+        let registry = KeybindingRegistry::new(Platform::Linux);
+        let defaults = defaults
+            .iter()
+            .filter_map(|(key, command)| {
+                registry.parse_default(
+                    KeybindingDecl::new(*key, *command),
+                    KeybindingSource::Default,
+                )
+            })
+            .collect();
+        registry.set_defaults(defaults);
+
+        registry
+    }
+
+    fn resolved_command(resolution: KeybindingResolution) -> Option<String> {
+        match resolution {
+            KeybindingResolution::Command { command, .. } => Some(command),
+            KeybindingResolution::Pending { .. } | KeybindingResolution::NoMatch => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_mod_on_each_platform() {
+        for (platform, primary) in [
+            (Platform::MacOS, KeyModifiers::META),
+            (Platform::Linux, KeyModifiers::CTRL),
+            (Platform::Windows, KeyModifiers::CTRL),
+        ] {
+            let chord = KeyChord::parse("mod+shift+P", platform).unwrap();
+
+            let mut modifiers = primary;
+            modifiers.insert(KeyModifiers::SHIFT);
+            assert_eq!(chord.modifiers, modifiers);
+            assert_eq!(chord.key.as_str(), "p");
+        }
+    }
+
+    #[test]
+    fn test_parse_plus_key() {
+        let chord = KeyChord::parse("ctrl++", Platform::Linux).unwrap();
+
+        assert_eq!(chord.modifiers, KeyModifiers::CTRL);
+        assert_eq!(
+            Some(&chord),
+            KeyChord::from_event(&event("ctrl+Equal")).as_ref()
+        );
+        assert_eq!(
+            KeyChord::parse(&chord.to_string(), Platform::Linux).unwrap(),
+            chord
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_chords() {
+        assert!(matches!(
+            KeyChord::parse("hyper+k", Platform::Linux),
+            Err(KeybindingError::UnknownModifier { modifier, .. }) if modifier == "hyper"
+        ));
+        assert!(matches!(
+            KeyChord::parse("ctrl+", Platform::Linux),
+            Err(KeybindingError::Invalid { .. })
+        ));
+        assert!(KeySequence::parse(" ", Platform::Linux).is_err());
+    }
+
+    #[test]
+    fn test_resolve_sequence() {
+        let registry = registry(&[
+            ("ctrl+s", "workbench.save"),
+            ("ctrl+k ctrl+s", "workbench.saveAll"),
+        ]);
+        let context = json!({});
+
+        assert!(matches!(
+            registry.resolve("main_0", &event("ctrl+KeyK"), &context),
+            KeybindingResolution::Pending { chords } if chords == ["ctrl+k"]
+        ));
+        // The sequences are entered in each window separately.
+        assert_eq!(
+            resolved_command(registry.resolve("main_1", &event("ctrl+KeyS"), &context)),
+            Some("workbench.save".to_string())
+        );
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyS"), &context)),
+            Some("workbench.saveAll".to_string())
+        );
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyS"), &context)),
+            Some("workbench.save".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_discards_unknown_sequence() {
+        let registry = registry(&[
+            ("ctrl+s", "workbench.save"),
+            ("ctrl+k ctrl+s", "workbench.saveAll"),
+        ]);
+        let context = json!({});
+
+        registry.resolve("main_0", &event("ctrl+KeyK"), &context);
+        // A modifier pressed alone keeps the sequence going.
+        assert!(matches!(
+            registry.resolve("main_0", &event("ControlLeft"), &context),
+            KeybindingResolution::NoMatch
+        ));
+        assert!(matches!(
+            registry.resolve("main_0", &event("ctrl+KeyX"), &context),
+            KeybindingResolution::NoMatch
+        ));
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyS"), &context)),
+            Some("workbench.save".to_string())
+        );
+
+        registry.resolve("main_0", &event("ctrl+KeyK"), &context);
+        registry.clear_pending("main_0");
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyS"), &context)),
+            Some("workbench.save".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_when() {
+        let registry = KeybindingRegistry::new(Platform::Linux);
+        let keybindings = [
+            KeybindingDecl::new("ctrl+enter", "editor.run"),
+            KeybindingDecl::new("ctrl+enter", "explorer.open")
+                .with_when(RawRule::var("explorerFocus")),
+        ]
+        .into_iter()
+        .filter_map(|decl| registry.parse_default(decl, KeybindingSource::Default))
+        .collect();
+        registry.set_defaults(keybindings);
+
+        assert_eq!(
+            resolved_command(registry.resolve(
+                "main_0",
+                &event("ctrl+Enter"),
+                &json!({ "explorerFocus": true })
+            )),
+            Some("explorer.open".to_string())
+        );
+        assert_eq!(
+            resolved_command(registry.resolve(
+                "main_0",
+                &event("ctrl+Enter"),
+                &json!({ "explorerFocus": false })
+            )),
+            Some("editor.run".to_string())
+        );
+    }
+
+    #[test]
+    fn test_user_keybindings_and_removals() {
+        let registry = registry(&[
+            ("ctrl+s", "workbench.save"),
+            ("ctrl+p", "workbench.quickOpen"),
+        ]);
+        registry.set_user_keybindings(vec![
+            UserKeybinding {
+                key: "ctrl+s".to_string(),
+                command: "-workbench.save".to_string(),
+                args: None,
+                when: None,
+            },
+            UserKeybinding {
+                key: "ctrl+shift+s".to_string(),
+                command: "workbench.save".to_string(),
+                args: None,
+                when: None,
+            },
+            UserKeybinding {
+                key: "ctrl+p".to_string(),
+                command: "workbench.commandPalette".to_string(),
+                args: None,
+                when: None,
+            },
+        ]);
+
+        let keybindings = registry
+            .keybindings()
+            .into_iter()
+            .map(|keybinding| {
+                (
+                    keybinding.sequence.to_string(),
+                    keybinding.command.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keybindings,
+            [
+                ("ctrl+p", "workbench.commandPalette"),
+                ("ctrl+shift+s", "workbench.save"),
+                ("ctrl+p", "workbench.quickOpen"),
+            ]
+            .map(|(key, command)| (key.to_string(), command.to_string()))
+        );
+
+        let context = json!({});
+        assert!(matches!(
+            registry.resolve("main_0", &event("ctrl+KeyS"), &context),
+            KeybindingResolution::NoMatch
+        ));
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyP"), &context)),
+            Some("workbench.commandPalette".to_string())
+        );
+
+        // Loading the keybindings again restores the removed defaults.
+        registry.set_user_keybindings(Vec::new());
+        assert_eq!(
+            resolved_command(registry.resolve("main_0", &event("ctrl+KeyS"), &context)),
+            Some("workbench.save".to_string())
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let registry = KeybindingRegistry::new(Platform::Linux);
+        let keybindings = [
+            KeybindingDecl::new("ctrl+p", "workbench.quickOpen"),
+            KeybindingDecl::new("ctrl+p", "workbench.print"),
+            KeybindingDecl::new("ctrl+k", "workbench.clear"),
+            KeybindingDecl::new("ctrl+k ctrl+s", "workbench.saveAll"),
+            // Bindings whose rules differ can apply in different contexts.
+            KeybindingDecl::new("ctrl+enter", "editor.run").with_when(RawRule::var("editorFocus")),
+            KeybindingDecl::new("ctrl+enter", "explorer.open")
+                .with_when(RawRule::var("explorerFocus")),
+        ]
+        .into_iter()
+        .filter_map(|decl| registry.parse_default(decl, KeybindingSource::Default))
+        .collect();
+        registry.set_defaults(keybindings);
+
+        let conflicts = registry
+            .conflicts()
+            .into_iter()
+            .map(|conflict| (conflict.kind, conflict.key, conflict.commands))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            [
+                (
+                    KeybindingConflictKind::ShadowedPrefix,
+                    "ctrl+k".to_string(),
+                    vec![
+                        "workbench.clear".to_string(),
+                        "workbench.saveAll".to_string()
+                    ]
+                ),
+                (
+                    KeybindingConflictKind::Duplicate,
+                    "ctrl+p".to_string(),
+                    vec![
+                        "workbench.print".to_string(),
+                        "workbench.quickOpen".to_string()
+                    ]
+                ),
+            ]
+        );
+    }
+}
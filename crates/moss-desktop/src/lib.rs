@@ -1,16 +1,26 @@
 pub mod addon_registry;
 pub mod app;
 pub mod command;
+pub mod command_history;
+pub mod context_key;
+pub mod contribution_registry;
 pub mod contributions;
+pub mod keybinding;
 pub mod menus;
 pub mod models;
 pub mod services;
+pub mod settings;
+pub mod settings_bundle;
 
 mod contribution;
-mod contribution_registry;
 
 pub extern crate linkme;
 
+// Lets the code generated by `moss_desktop_macro` refer to this crate by its name.
+extern crate self as moss_desktop;
+
+pub use moss_desktop_macro::command_handler;
+
 #[macro_use]
 extern crate tracing;
 
@@ -1,7 +1,13 @@
 use dashmap::DashMap;
+use moss_tauri::ErrorCode;
 use moss_text::ReadOnlyStr;
+use serde_json::{json, Value};
+use thiserror::Error;
 
-use crate::models::actions::MenuItem;
+use crate::models::actions::{
+    MenuGroup, MenuItem, ResolvedActionMenuItem, ResolvedMenu, ResolvedMenuItem,
+    ResolvedSubmenuMenuItem,
+};
 
 pub struct Menus {
     state: DashMap<ReadOnlyStr, Vec<MenuItem>>,
@@ -13,3 +19,472 @@ impl Menus {
         self.state.entry(key).or_insert_with(Vec::new).push(item);
     }
 }
+
+#[derive(Error, Debug)]
+pub enum MenuError {
+    #[error("Namespace '{namespace}' not found")]
+    NamespaceNotFound { namespace: ReadOnlyStr },
+}
+
+impl ErrorCode for MenuError {
+    fn code(&self) -> &'static str {
+        match self {
+            MenuError::NamespaceNotFound { .. } => "menu.namespaceNotFound",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            MenuError::NamespaceNotFound { namespace } => {
+                Some(json!({ "namespace": namespace.as_str() }))
+            }
+        }
+    }
+}
+
+/// Resolves the menu of the namespace for the context: the items whose `when` rule does not
+/// hold are left out, the groups and the items are sorted by their order, the groups are
+/// separated, and the submenus are expanded.
+pub fn resolve_menu(
+    menus: &DashMap<ReadOnlyStr, Vec<MenuItem>>,
+    namespace: &ReadOnlyStr,
+    context: &Value,
+) -> Result<ResolvedMenu, MenuError> {
+    if !menus.contains_key(namespace) {
+        return Err(MenuError::NamespaceNotFound {
+            namespace: namespace.clone(),
+        });
+    }
+
+    let mut resolver = MenuResolver {
+        menus,
+        context,
+        stack: Vec::new(),
+        dangling_submenus: Vec::new(),
+    };
+    let items = resolver.resolve(namespace);
+
+    Ok(ResolvedMenu {
+        namespace: namespace.clone(),
+        items,
+        dangling_submenus: resolver.dangling_submenus,
+    })
+}
+
+/// Finds the submenus referring to a namespace no menu is contributed under, as pairs of
+/// the namespace of the submenu item and the id it refers to.
+pub fn dangling_submenus(
+    menus: &DashMap<ReadOnlyStr, Vec<MenuItem>>,
+) -> Vec<(ReadOnlyStr, ReadOnlyStr)> {
+    let mut dangling = menus
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .value()
+                .iter()
+                .filter_map(|item| match item {
+                    MenuItem::Submenu(submenu) if !menus.contains_key(&submenu.submenu_id) => {
+                        Some((entry.key().clone(), submenu.submenu_id.clone()))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    dangling.sort();
+    dangling.dedup();
+
+    dangling
+}
+
+struct MenuResolver<'a> {
+    menus: &'a DashMap<ReadOnlyStr, Vec<MenuItem>>,
+    context: &'a Value,
+    /// The namespaces being expanded, to stop at the submenus that contain themselves.
+    stack: Vec<ReadOnlyStr>,
+    dangling_submenus: Vec<String>,
+}
+
+impl MenuResolver<'_> {
+    fn resolve(&mut self, namespace: &ReadOnlyStr) -> Vec<ResolvedMenuItem> {
+        // The items are cloned so that no shard of the map stays locked while the submenus
+        // are expanded.
+        let Some(items) = self.menus.get(namespace).map(|items| items.clone()) else {
+            return Vec::new();
+        };
+        self.stack.push(namespace.clone());
+
+        let mut groups: Vec<(Option<&MenuGroup>, Vec<&MenuItem>)> = Vec::new();
+        for item in items.iter().filter(|item| self.is_visible(item)) {
+            let group = item_group(item);
+            match groups
+                .iter_mut()
+                .find(|(existing, _)| existing.map(MenuGroup::id) == group.map(MenuGroup::id))
+            {
+                Some((_, items)) => items.push(item),
+                None => groups.push((group, vec![item])),
+            }
+        }
+
+        // The ordered groups come first, the items without a group last.
+        groups.sort_by_key(|(group, _)| match group {
+            Some(group) => (0, group.order().is_none(), group.order()),
+            None => (1, false, None),
+        });
+
+        let mut resolved = Vec::new();
+        for (_, mut items) in groups {
+            items.sort_by_key(|item| {
+                let order = item_order(item);
+                (order.is_none(), order)
+            });
+
+            let group_items = items
+                .into_iter()
+                .filter_map(|item| self.resolve_item(namespace, item))
+                .collect::<Vec<_>>();
+            if group_items.is_empty() {
+                continue;
+            }
+            if !resolved.is_empty() {
+                resolved.push(ResolvedMenuItem::Separator);
+            }
+            resolved.extend(group_items);
+        }
+
+        self.stack.pop();
+        resolved
+    }
+
+    fn resolve_item(
+        &mut self,
+        namespace: &ReadOnlyStr,
+        item: &MenuItem,
+    ) -> Option<ResolvedMenuItem> {
+        match item {
+            MenuItem::Action(action) => {
+                let toggled = action.command.toggled.as_ref().map(|toggle| {
+                    toggle.condition.is_satisfied(self.context).unwrap_or_else(|err| {
+                        warn!(
+                            "Failed to evaluate the toggle of command {} in menu {namespace}: {err}",
+                            action.command.id
+                        );
+                        false
+                    })
+                });
+
+                Some(ResolvedMenuItem::Action(Box::new(ResolvedActionMenuItem {
+                    command: action.command.clone(),
+                    toggled,
+                    visibility: action.visibility,
+                })))
+            }
+            MenuItem::Submenu(submenu) => {
+                let id = &submenu.submenu_id;
+                if self.stack.contains(id) {
+                    warn!("Submenu {id} in menu {namespace} contains itself, leaving it out");
+                    return None;
+                }
+                if !self.menus.contains_key(id) {
+                    if !self
+                        .dangling_submenus
+                        .iter()
+                        .any(|dangling| dangling == id.as_str())
+                    {
+                        self.dangling_submenus.push(id.to_string());
+                    }
+                    return None;
+                }
+
+                let items = self.resolve(id);
+                if items.is_empty() {
+                    return None;
+                }
+
+                Some(ResolvedMenuItem::Submenu(ResolvedSubmenuMenuItem {
+                    submenu_id: id.clone(),
+                    default_action_id: submenu.default_action_id.clone(),
+                    title: submenu.title.clone(),
+                    visibility: submenu.visibility,
+                    items,
+                }))
+            }
+        }
+    }
+
+    fn is_visible(&self, item: &MenuItem) -> bool {
+        let when = match item {
+            MenuItem::Action(action) => action.when.as_ref(),
+            MenuItem::Submenu(submenu) => submenu.when.as_ref(),
+        };
+        let Some(when) = when else {
+            return true;
+        };
+
+        when.is_satisfied(self.context).unwrap_or_else(|err| {
+            warn!("Failed to evaluate the condition of a menu item: {err}");
+            false
+        })
+    }
+}
+
+fn item_group(item: &MenuItem) -> Option<&MenuGroup> {
+    match item {
+        MenuItem::Action(action) => action.group.as_ref(),
+        MenuItem::Submenu(submenu) => submenu.group.as_ref(),
+    }
+}
+
+fn item_order(item: &MenuItem) -> Option<i64> {
+    match item {
+        MenuItem::Action(action) => action.order,
+        MenuItem::Submenu(submenu) => submenu.order,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moss_jsonlogic::raw_rule::RawRule;
+    use moss_text::localize;
+
+    use crate::models::actions::{
+        ActionMenuItem, CommandAction, MenuItemVisibility, SubmenuMenuItem,
+    };
+
+    fn action(id: &str, group: Option<MenuGroup>, order: Option<i64>) -> MenuItem {
+        MenuItem::Action(ActionMenuItem {
+            command: CommandAction {
+                id: ReadOnlyStr::from(id),
+                title: localize!(id, id),
+                tooltip: None,
+                description: None,
+                icon: None,
+                toggled: None,
+            },
+            group,
+            order,
+            when: None,
+            visibility: MenuItemVisibility::Classic,
+        })
+    }
+
+    fn submenu(id: &str, group: Option<MenuGroup>, order: Option<i64>) -> MenuItem {
+        MenuItem::Submenu(SubmenuMenuItem {
+            submenu_id: ReadOnlyStr::from(id),
+            default_action_id: None,
+            title: None,
+            group,
+            order,
+            when: None,
+            visibility: MenuItemVisibility::Classic,
+        })
+    }
+
+    fn with_when(mut item: MenuItem, rule: RawRule) -> MenuItem {
+        match &mut item {
+            MenuItem::Action(action) => action.when = Some(rule),
+            MenuItem::Submenu(submenu) => submenu.when = Some(rule),
+        }
+        item
+    }
+
+    fn menus(menus: Vec<(&str, Vec<MenuItem>)>) -> DashMap<ReadOnlyStr, Vec<MenuItem>> {
+        menus
+            .into_iter()
+            .map(|(namespace, items)| (ReadOnlyStr::from(namespace), items))
+            .collect()
+    }
+
+    /// Lays the items out as text, `-` for the separators and the items of the submenus
+    /// in brackets.
+    fn layout(items: &[ResolvedMenuItem]) -> String {
+        items
+            .iter()
+            .map(|item| match item {
+                ResolvedMenuItem::Action(action) => action.command.id.to_string(),
+                ResolvedMenuItem::Submenu(submenu) => {
+                    format!("{}[{}]", submenu.submenu_id, layout(&submenu.items))
+                }
+                ResolvedMenuItem::Separator => "-".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn resolve(
+        menus: &DashMap<ReadOnlyStr, Vec<MenuItem>>,
+        namespace: &str,
+        context: Value,
+    ) -> ResolvedMenu {
+        resolve_menu(menus, &ReadOnlyStr::from(namespace), &context).unwrap()
+    }
+
+    #[test]
+    fn test_unknown_namespace() {
+        let menus = menus(vec![("file", vec![action("save", None, None)])]);
+
+        assert!(matches!(
+            resolve_menu(&menus, &ReadOnlyStr::from("edit"), &json!({})),
+            Err(MenuError::NamespaceNotFound { namespace }) if namespace.as_str() == "edit"
+        ));
+    }
+
+    #[test]
+    fn test_when_filtering() {
+        let menus = menus(vec![(
+            "file",
+            vec![
+                action("save", None, Some(1)),
+                with_when(action("revert", None, Some(2)), RawRule::var("isDirty")),
+            ],
+        )]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "file", json!({ "isDirty": true })).items),
+            "save revert"
+        );
+        assert_eq!(
+            layout(&resolve(&menus, "file", json!({ "isDirty": false })).items),
+            "save"
+        );
+    }
+
+    #[test]
+    fn test_groups_and_items_are_sorted_and_separated() {
+        let menus = menus(vec![(
+            "file",
+            vec![
+                action("exit", None, None),
+                action("closeAll", Some(MenuGroup::ordered(2, "close")), Some(2)),
+                action("share", Some(MenuGroup::unordered("share")), None),
+                action("close", Some(MenuGroup::ordered(2, "close")), Some(1)),
+                action("saveAs", Some(MenuGroup::ordered(1, "save")), None),
+                action("save", Some(MenuGroup::ordered(1, "save")), Some(1)),
+            ],
+        )]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "file", json!({})).items),
+            "save saveAs - close closeAll - share - exit"
+        );
+    }
+
+    #[test]
+    fn test_hidden_group_leaves_no_separator() {
+        let menus = menus(vec![(
+            "file",
+            vec![
+                action("save", Some(MenuGroup::ordered(1, "save")), None),
+                with_when(
+                    action("revert", Some(MenuGroup::ordered(2, "revert")), None),
+                    RawRule::var("isDirty"),
+                ),
+                action("close", Some(MenuGroup::ordered(3, "close")), None),
+            ],
+        )]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "file", json!({ "isDirty": false })).items),
+            "save - close"
+        );
+    }
+
+    #[test]
+    fn test_nested_submenus() {
+        let menus = menus(vec![
+            ("menubar", vec![submenu("file", None, Some(1))]),
+            (
+                "file",
+                vec![
+                    action("save", Some(MenuGroup::ordered(1, "save")), None),
+                    submenu("recent", Some(MenuGroup::ordered(2, "recent")), None),
+                ],
+            ),
+            ("recent", vec![action("openRecent", None, None)]),
+        ]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "menubar", json!({})).items),
+            "file[save - recent[openRecent]]"
+        );
+    }
+
+    #[test]
+    fn test_empty_submenu_is_left_out() {
+        let menus = menus(vec![
+            (
+                "menubar",
+                vec![
+                    submenu("empty", None, Some(1)),
+                    submenu("recent", None, Some(2)),
+                ],
+            ),
+            ("recent", vec![action("openRecent", None, None)]),
+            (
+                "empty",
+                vec![with_when(
+                    action("hidden", None, None),
+                    RawRule::var("never"),
+                )],
+            ),
+        ]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "menubar", json!({ "never": false })).items),
+            "recent[openRecent]"
+        );
+    }
+
+    #[test]
+    fn test_self_containing_submenu() {
+        let menus = menus(vec![
+            (
+                "file",
+                vec![
+                    action("save", None, Some(1)),
+                    submenu("more", None, Some(2)),
+                ],
+            ),
+            (
+                "more",
+                vec![
+                    action("share", None, Some(1)),
+                    submenu("file", None, Some(2)),
+                ],
+            ),
+        ]);
+
+        assert_eq!(
+            layout(&resolve(&menus, "file", json!({})).items),
+            "save more[share]"
+        );
+    }
+
+    #[test]
+    fn test_dangling_submenus() {
+        let menus = menus(vec![
+            (
+                "file",
+                vec![
+                    action("save", None, Some(1)),
+                    submenu("recent", None, Some(2)),
+                    submenu("recent", Some(MenuGroup::unordered("more")), None),
+                ],
+            ),
+            ("edit", vec![submenu("recent", None, None)]),
+        ]);
+
+        let resolved = resolve(&menus, "file", json!({}));
+        assert_eq!(layout(&resolved.items), "save");
+        assert_eq!(resolved.dangling_submenus, ["recent"]);
+
+        assert_eq!(
+            dangling_submenus(&menus),
+            [
+                (ReadOnlyStr::from("edit"), ReadOnlyStr::from("recent")),
+                (ReadOnlyStr::from("file"), ReadOnlyStr::from("recent")),
+            ]
+        );
+    }
+}
@@ -58,6 +58,14 @@ impl MenuGroup {
             description: None,
         }
     }
+
+    pub fn id(&self) -> &ReadOnlyStr {
+        &self.id
+    }
+
+    pub fn order(&self) -> Option<i64> {
+        self.order
+    }
 }
 
 #[derive(Debug, Serialize, Clone, TS)]
@@ -142,3 +150,58 @@ pub struct SubmenuMenuItem {
 
     pub visibility: MenuItemVisibility,
 }
+
+/// A menu item as shown, once its `when` rule holds and its groups are laid out.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "actions.ts")]
+pub enum ResolvedMenuItem {
+    Action(Box<ResolvedActionMenuItem>),
+    Submenu(ResolvedSubmenuMenuItem),
+    /// Separates the groups of a menu.
+    Separator,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "actions.ts")]
+pub struct ResolvedActionMenuItem {
+    pub command: CommandAction,
+
+    /// Whether the toggle of the command is on, for the commands that have one.
+    #[ts(optional)]
+    pub toggled: Option<bool>,
+
+    pub visibility: MenuItemVisibility,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "actions.ts")]
+pub struct ResolvedSubmenuMenuItem {
+    #[ts(type = "string")]
+    pub submenu_id: ActionCommandId,
+
+    #[ts(optional, type = "string")]
+    pub default_action_id: Option<ActionCommandId>,
+
+    #[ts(optional, type = "LocalizedString")]
+    pub title: Option<LocalizedString>,
+
+    pub visibility: MenuItemVisibility,
+
+    pub items: Vec<ResolvedMenuItem>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "actions.ts")]
+pub struct ResolvedMenu {
+    #[ts(type = "string")]
+    pub namespace: ReadOnlyStr,
+
+    pub items: Vec<ResolvedMenuItem>,
+
+    /// The submenus left out because no menu is contributed under their id.
+    pub dangling_submenus: Vec<String>,
+}
@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-#[derive(Deserialize, Serialize, Hash, Debug, Clone, Eq, PartialEq, TS)]
+#[derive(Deserialize, Serialize, Hash, Debug, Clone, Eq, PartialEq, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "application.ts")]
 pub struct ThemeDescriptor {
@@ -13,7 +14,7 @@ pub struct ThemeDescriptor {
     pub source: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, Eq, Hash, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "application.ts")]
 pub struct LocaleDescriptor {
@@ -0,0 +1,92 @@
+use moss_text::localized_string::LocalizedString;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use ts_rs::TS;
+
+use super::application::{LocaleDescriptor, ThemeDescriptor};
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct CommandProgress {
+    /// The execution of the command the progress is reported for.
+    pub invocation_id: String,
+    pub command: String,
+    #[ts(optional)]
+    pub message: Option<String>,
+    /// How much of the work is done, from 0 to 100.
+    #[ts(optional)]
+    pub percentage: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct ChangeColorThemeArgs {
+    pub theme_descriptor: ThemeDescriptor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct ChangeLanguagePackArgs {
+    pub locale_descriptor: LocaleDescriptor,
+}
+
+/// A command as listed in the command palette.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct CommandDescriptor {
+    pub id: String,
+    #[ts(optional, type = "LocalizedString")]
+    pub title: Option<LocalizedString>,
+    #[ts(optional, type = "LocalizedString")]
+    pub category: Option<LocalizedString>,
+    #[ts(optional)]
+    pub icon: Option<String>,
+    #[ts(optional, type = "LocalizedString")]
+    pub args_description: Option<LocalizedString>,
+    /// The JSON schema of the arguments, for the commands that declare it.
+    #[ts(optional, type = "unknown")]
+    pub args_schema: Option<Value>,
+    /// Whether the enablement rule of the command holds in the window.
+    pub enabled: bool,
+}
+
+/// A command with the arguments it is executed with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct CommandCall {
+    pub command: String,
+    #[ts(type = "Record<string, unknown>")]
+    pub args: Map<String, Value>,
+}
+
+/// A command executed in a window, as kept in the log the commands can be replayed from.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct CommandLogEntry {
+    /// Orders the entries, it grows with every executed command.
+    #[ts(type = "number")]
+    pub sequence: u64,
+    pub window: String,
+    pub call: CommandCall,
+    /// When the command finished, in milliseconds since the Unix epoch.
+    #[ts(type = "number")]
+    pub executed_at: u64,
+    /// Whether the command can be undone.
+    pub undoable: bool,
+}
+
+/// The commands that can be undone and redone in a window, the next one first.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "commands.ts")]
+pub struct CommandHistoryDescriptor {
+    pub undo: Vec<CommandCall>,
+    pub redo: Vec<CommandCall>,
+}
@@ -9,6 +9,8 @@ pub mod menu {
     pub const MENU_NAMESPACE_ID_VIEW_ITEM: ReadOnlyStr = read_only_str!("viewItem");
     pub const MENU_NAMESPACE_ID_VIEW_ITEM_CONTEXT: ReadOnlyStr = read_only_str!("viewItemContext");
     pub const MENU_NAMESPACE_ID_HEAD_ITEM: ReadOnlyStr = read_only_str!("headItem");
+    pub const MENU_NAMESPACE_ID_MENUBAR: ReadOnlyStr = read_only_str!("menubar");
+    pub const MENU_NAMESPACE_ID_MENUBAR_FILE: ReadOnlyStr = read_only_str!("menubar.file");
 
     pub const MENU_GROUP_ID_THIS: ReadOnlyStr = read_only_str!("this");
     pub const MENU_GROUP_ID_INLINE: ReadOnlyStr = read_only_str!("inline");
@@ -26,3 +28,13 @@ pub mod view {
     
     pub const VIEW_GROUP_ID_LAUNCHPAD: ReadOnlyStr = read_only_str!("workbench.group.launchpad");
 }
+
+#[rustfmt::skip]
+pub mod context_key {
+    use crate::context_key::ContextKey;
+
+    pub const CONTEXT_KEY_THEME: ContextKey<String> = ContextKey::new("theme", "The id of the color theme in use");
+    pub const CONTEXT_KEY_LOCALE: ContextKey<String> = ContextKey::new("locale", "The code of the display language");
+    pub const CONTEXT_KEY_TOGGLE_SECONDARY_SIDEBAR: ContextKey<bool> = ContextKey::new("toggleSecondarySidebar", "Whether the secondary side bar is visible");
+    pub const CONTEXT_KEY_TOGGLE_PANEL: ContextKey<bool> = ContextKey::new("togglePanel", "Whether the panel is visible");
+}
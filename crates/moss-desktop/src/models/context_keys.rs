@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "context_keys.ts")]
+pub enum ContextKeyType {
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl ContextKeyType {
+    pub fn accepts(&self, value: &Value) -> bool {
+        match self {
+            ContextKeyType::Boolean => value.is_boolean(),
+            ContextKeyType::Number => value.is_number(),
+            ContextKeyType::String => value.is_string(),
+            ContextKeyType::Array => value.is_array(),
+            ContextKeyType::Object => value.is_object(),
+        }
+    }
+}
+
+impl fmt::Display for ContextKeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContextKeyType::Boolean => "boolean",
+            ContextKeyType::Number => "number",
+            ContextKeyType::String => "string",
+            ContextKeyType::Array => "array",
+            ContextKeyType::Object => "object",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Where a context key is set. The value set in a scope overrides the ones of the scopes
+/// that contain it: a view overrides its window, a window overrides the global scope.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, export_to = "context_keys.ts")]
+pub enum ContextScope {
+    Global,
+    Window { window: String },
+    View { window: String, view: String },
+}
+
+impl ContextScope {
+    pub fn window(label: impl Into<String>) -> Self {
+        ContextScope::Window {
+            window: label.into(),
+        }
+    }
+
+    pub fn view(window: impl Into<String>, view: impl Into<String>) -> Self {
+        ContextScope::View {
+            window: window.into(),
+            view: view.into(),
+        }
+    }
+
+    /// The scope that contains this one, `None` for the global scope.
+    pub fn parent(&self) -> Option<ContextScope> {
+        match self {
+            ContextScope::Global => None,
+            ContextScope::Window { .. } => Some(ContextScope::Global),
+            ContextScope::View { window, .. } => Some(ContextScope::window(window.clone())),
+        }
+    }
+
+    pub fn window_label(&self) -> Option<&str> {
+        match self {
+            ContextScope::Global => None,
+            ContextScope::Window { window } | ContextScope::View { window, .. } => Some(window),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "context_keys.ts")]
+pub struct ContextKeyDescriptor {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub ty: ContextKeyType,
+    #[ts(type = "unknown")]
+    pub default: Value,
+    #[ts(optional)]
+    pub description: Option<String>,
+}
+
+/// Sent with the `core://context-keys-changed` event whenever a key is set or reset.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "context_keys.ts")]
+pub struct ContextKeyChange {
+    pub key: String,
+    pub scope: ContextScope,
+    /// The value set in the scope, none when it was reset.
+    #[ts(optional, type = "unknown")]
+    pub value: Option<Value>,
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Who added a set of contributions, which are added and removed together: a module of
+/// the application, by its path, or an addon.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, export_to = "contributions.ts")]
+pub enum ContributionOwner {
+    Builtin { module: String },
+    Addon { id: String },
+}
+
+impl ContributionOwner {
+    pub fn builtin(module: impl Into<String>) -> Self {
+        Self::Builtin {
+            module: module.into(),
+        }
+    }
+
+    pub fn addon(id: impl Into<String>) -> Self {
+        Self::Addon { id: id.into() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "contributions.ts")]
+pub enum ContributionChangeKind {
+    Added,
+    Removed,
+}
+
+/// Sent with the `core://contributions-changed` event whenever the contributions of an
+/// owner are added or removed.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "contributions.ts")]
+pub struct ContributionChange {
+    pub owner: ContributionOwner,
+    pub kind: ContributionChangeKind,
+}
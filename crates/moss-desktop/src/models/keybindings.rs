@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+/// A key pressed in a window, as reported by the `keydown` event.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "keybindings.ts")]
+pub struct KeyEvent {
+    /// The physical key, as in `KeyboardEvent.code`.
+    pub code: String,
+    #[serde(default)]
+    pub ctrl_key: bool,
+    #[serde(default)]
+    pub shift_key: bool,
+    #[serde(default)]
+    pub alt_key: bool,
+    #[serde(default)]
+    pub meta_key: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, export_to = "keybindings.ts")]
+pub enum KeybindingResolution {
+    /// The key completes a binding, the command is to be executed.
+    Command {
+        command: String,
+        #[ts(optional, type = "unknown")]
+        args: Option<Value>,
+    },
+    /// The key starts or continues a sequence, the next key decides.
+    Pending {
+        chords: Vec<String>,
+    },
+    NoMatch,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "keybindings.ts")]
+pub struct KeybindingDescriptor {
+    /// The chords of the binding, e.g. `ctrl+k ctrl+s`.
+    pub key: String,
+    pub command: String,
+    #[ts(optional, type = "unknown")]
+    pub args: Option<Value>,
+    /// The rule the context must satisfy, in its JSON Logic form.
+    #[ts(optional, type = "unknown")]
+    pub when: Option<Value>,
+    /// Where the binding comes from: `default`, `user` or `addon:<id>`.
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "keybindings.ts")]
+pub enum KeybindingConflictKind {
+    /// The commands are bound to the same keys, only the first one is ever executed.
+    Duplicate,
+    /// The keys of the first command start the sequences of the others, which win.
+    ShadowedPrefix,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "keybindings.ts")]
+pub struct KeybindingConflict {
+    pub kind: KeybindingConflictKind,
+    pub key: String,
+    /// The command that is shadowed or takes precedence first, then the others.
+    pub commands: Vec<String>,
+}
@@ -1,4 +1,10 @@
 pub mod actions;
 pub mod application;
+pub mod commands;
 pub mod constants;
+pub mod context_keys;
+pub mod contributions;
+pub mod keybindings;
+pub mod services;
+pub mod settings;
 pub mod view;
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "services.ts")]
+pub enum ServiceState {
+    /// Registered, but not constructed yet.
+    Pending,
+    Active,
+    /// Active with a fallback instance, after its construction failed.
+    Degraded,
+    /// The construction failed, the service is not available.
+    Failed,
+    /// Released on application exit.
+    Disposed,
+}
+
+impl ServiceState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceState::Pending => "pending",
+            ServiceState::Active => "active",
+            ServiceState::Degraded => "degraded",
+            ServiceState::Failed => "failed",
+            ServiceState::Disposed => "disposed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "services.ts")]
+pub struct ServiceDescriptor {
+    /// The type name of the service.
+    pub name: String,
+    pub state: ServiceState,
+    /// Whether the service is activated with its phase rather than on its first request.
+    pub instant: bool,
+    /// The lifecycle phase an instant service is activated in.
+    pub activation_phase: String,
+    /// When the service was activated, in milliseconds since the Unix epoch.
+    #[ts(optional, type = "number")]
+    pub activated_at: Option<u64>,
+    /// How long the construction took, including the failed attempts.
+    #[ts(optional, type = "number")]
+    pub construction_time_ms: Option<u64>,
+    /// The number of attempts made to construct the service.
+    pub attempts: u32,
+    /// The type names of the services this one depends on.
+    pub dependencies: Vec<String>,
+    /// The error of the last failed construction, kept when the service runs degraded.
+    #[ts(optional)]
+    pub last_error: Option<String>,
+}
@@ -0,0 +1,101 @@
+use moss_configuration::migration::SettingsVersion;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use ts_rs::TS;
+
+use super::application::{LocaleDescriptor, ThemeDescriptor};
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct SettingsSearchResult {
+    /// The key of the matching parameter (e.g., "window.defaultWidth").
+    pub key: String,
+    /// The display name of the parameter (e.g., "Default Width").
+    pub display_name: String,
+    /// The relevance of the match, a higher score is a better match.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct SettingsSearchGroup {
+    /// The identifier of the configuration that declares the parameters.
+    pub id: String,
+    /// The display name of the configuration (e.g., "Window").
+    #[ts(optional)]
+    pub display_name: Option<String>,
+    pub results: Vec<SettingsSearchResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct SettingsBundle {
+    /// Identifies the file as a Moss settings bundle, always "moss.settings".
+    pub format: String,
+    /// The version of the bundle format.
+    pub version: u32,
+    /// The version of the settings for every configuration that declares migrations, used to
+    /// migrate the settings exported by older releases.
+    #[ts(type = "Record<string, number>")]
+    pub settings_version: SettingsVersion,
+    /// The settings changed by the user, keyed by the parameter key.
+    #[ts(type = "Record<string, unknown>")]
+    pub settings: Map<String, JsonValue>,
+    /// The identifier of the selected color theme.
+    #[ts(optional)]
+    pub theme: Option<String>,
+    /// The language code of the selected locale.
+    #[ts(optional)]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct SettingChangeDescriptor {
+    pub key: String,
+    /// The current value, missing when the setting is added by the import.
+    #[ts(optional, type = "unknown")]
+    pub old_value: Option<JsonValue>,
+    /// The imported value, missing when the setting is removed by the import.
+    #[ts(optional, type = "unknown")]
+    pub new_value: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct RejectedSettingDescriptor {
+    pub key: String,
+    /// Explains why the value cannot be imported.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "settings.ts")]
+pub struct SettingsImportPreview {
+    /// Identifies the previewed import, which is applied with it.
+    pub token: String,
+    /// The changes to the user settings, sorted by the key.
+    pub changes: Vec<SettingChangeDescriptor>,
+    /// Keys that are not declared by any configuration, these are not imported.
+    pub unknown_keys: Vec<String>,
+    /// Settings whose values are not valid for their parameters, these are not imported.
+    pub invalid_settings: Vec<RejectedSettingDescriptor>,
+    /// The color theme selected by the import.
+    #[ts(optional)]
+    pub theme: Option<ThemeDescriptor>,
+    /// The locale selected by the import.
+    #[ts(optional)]
+    pub locale: Option<LocaleDescriptor>,
+    /// The identifier of the theme from the bundle that is not available on this machine.
+    #[ts(optional)]
+    pub unknown_theme: Option<String>,
+    /// The code of the locale from the bundle that is not available on this machine.
+    #[ts(optional)]
+    pub unknown_locale: Option<String>,
+}
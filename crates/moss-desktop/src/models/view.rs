@@ -11,7 +11,7 @@ pub struct TreeViewGroup {
     pub order: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TreeViewDescriptor {
     pub id: String,
     pub name: LocalizedString,
@@ -45,7 +45,7 @@ impl From<&TreeViewDescriptor> for TreeViewOutput {
     }
 }
 
-#[derive(Serialize, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum TreeViewGroupLocation {
     PrimaryBar,
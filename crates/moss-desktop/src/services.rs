@@ -1,5 +1,6 @@
 pub mod addon_service;
 pub mod configuration_service;
+pub mod context_key_service;
 pub mod lifecycle_service;
 pub mod locale_service;
 pub mod theme_service;
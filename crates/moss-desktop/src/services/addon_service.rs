@@ -1,9 +1,23 @@
 use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
 use moss_addon::manifest::{AddonManifest, MANIFEST_FILENAME};
-use std::path::PathBuf;
+use moss_jsonlogic::raw_rule::RawRule;
+use moss_text::localized_string::LocalizedString;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 use tauri::{AppHandle, Manager};
 
+use crate::contribution::MenuDecl;
+use crate::contribution_registry::ContributionSet;
+use crate::keybinding::KeybindingDecl;
+use crate::models::actions::{
+    ActionMenuItem, CommandAction, MenuGroup, MenuItem, MenuItemVisibility,
+};
 use crate::models::application::LocaleDescriptor;
+use crate::models::contributions::ContributionOwner;
+use crate::services::locale_service::LocaleService;
 use crate::{
     addon_registry::AddonRegistry,
     app::{service::Service, state::AppStateManager},
@@ -12,47 +26,87 @@ use crate::{
 
 pub struct AddonService {
     addon_registry: AddonRegistry,
+    installed_addons_location: PathBuf,
 }
 
 impl AddonService {
+    /// Loads the built-in addons, which provide the default theme and locale. The installed
+    /// addons are loaded later on, see [`AddonService::load_installed_addons`].
     pub fn new(
         app_handle: &AppHandle,
         builtin_addons_location: PathBuf,
-        _installed_addons_location: PathBuf,
-    ) -> Self {
-        let mut read_dir = std::fs::read_dir(&builtin_addons_location).unwrap_or_else(|err| {
-            panic!(
+        installed_addons_location: PathBuf,
+    ) -> Result<Self> {
+        let read_dir = std::fs::read_dir(&builtin_addons_location).map_err(|err| {
+            anyhow!(
                 "Failed to read the directory {:?} containing built-in addons: {err}",
                 &builtin_addons_location
-            );
-        });
-
-        let app_state = app_handle.state::<AppStateManager>();
-        while let Some(entry) = read_dir.next() {
-            let Ok(entry) = entry else {
-                warn!(
-                    "Failed to read an entry in the directory for built-in addons: {:?}",
-                    entry.err()
-                );
-                continue;
-            };
-
-            if !entry.path().is_dir() {
-                continue;
-            }
-
-            if let Err(err) = parse_addon_dir(&app_state, entry.path()) {
-                warn!("Failed to parse addon: {err}");
-                continue;
-            };
-
-            // TODO: Add addon registration once the addon entity becomes more clearly defined
-        }
+            )
+        })?;
+        load_addons(&app_handle.state::<AppStateManager>(), read_dir, "built-in");
 
-        Self {
+        Ok(Self {
             addon_registry: AddonRegistry::new(),
+            installed_addons_location,
+        })
+    }
+
+    /// Loads the installed addons. Scanning them is deferred past the first paint, their
+    /// contributions reach the windows as they are added.
+    pub fn load_installed_addons(&self, app_state: &AppStateManager) {
+        match std::fs::read_dir(&self.installed_addons_location) {
+            Ok(read_dir) => load_addons(app_state, read_dir, "installed"),
+            Err(err) => warn!(
+                "Failed to read the directory {:?} containing installed addons: {err}",
+                &self.installed_addons_location
+            ),
         }
     }
+
+    /// Loads the addon in the directory, replacing its contributions if it is loaded already.
+    /// Returns the id of the addon.
+    pub fn enable_addon(
+        &self,
+        app_state: &AppStateManager,
+        locale_service: &LocaleService,
+        addon_dir: &Path,
+    ) -> Result<String> {
+        invalidating_translations(locale_service, || load_addon(app_state, addon_dir))
+    }
+
+    /// Removes the contributions of the addon, returns `false` if it is not loaded.
+    pub fn disable_addon(
+        &self,
+        app_state: &AppStateManager,
+        locale_service: &LocaleService,
+        id: &str,
+    ) -> bool {
+        invalidating_translations(locale_service, || {
+            app_state
+                .contributions
+                .remove(&ContributionOwner::addon(id))
+        })
+    }
+}
+
+/// Runs the change of the addons, then drops the cached translations of the language packs
+/// it adds or removes.
+fn invalidating_translations<T>(locale_service: &LocaleService, change: impl FnOnce() -> T) -> T {
+    let locales = |locale_service: &LocaleService| {
+        locale_service
+            .get_locales()
+            .iter()
+            .map(|locale| locale.clone())
+            .collect::<HashSet<_>>()
+    };
+
+    let before = locales(locale_service);
+    let result = change();
+    for locale in before.symmetric_difference(&locales(locale_service)) {
+        locale_service.invalidate_translations(&locale.code);
+    }
+
+    result
 }
 
 impl Service for AddonService {
@@ -60,69 +114,157 @@ impl Service for AddonService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
-
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
-}
 
-// OPTIMIZE: This should probably be moved in the future to a separate entity responsible for loading add-ons.
-fn parse_addon_dir(app_state: &AppStateManager, addon_dir: PathBuf) -> Result<()> {
-    let mut read_dir = std::fs::read_dir(&addon_dir)
-        .map_err(|err| anyhow!("Failed to read the directory {:?}: {}", addon_dir, err))?;
+    /// Without the built-in addons the application still runs, with no addon contributions.
+    fn degraded(_error: &anyhow::Error) -> Option<Self> {
+        Some(Self {
+            addon_registry: AddonRegistry::new(),
+            installed_addons_location: PathBuf::new(),
+        })
+    }
+}
 
-    while let Some(entry) = read_dir.next() {
+fn load_addons(app_state: &AppStateManager, read_dir: std::fs::ReadDir, kind: &str) {
+    for entry in read_dir {
         let Ok(entry) = entry else {
-            info!(
-                "Failed to read an entry in the directory of addon: {:?}",
+            warn!(
+                "Failed to read an entry in the directory for {kind} addons: {:?}",
                 entry.err()
             );
             continue;
         };
 
-        let entry_path = entry.path();
-
-        if !entry_path.is_dir() && entry.file_name() == MANIFEST_FILENAME {
-            let file_content = std::fs::read_to_string(&entry_path)
-                .map_err(|err| anyhow!("Failed to read the {:?} file: {}", entry_path, err))?;
-
-            let addon_manifest: AddonManifest = toml::from_str(&file_content)
-                .map_err(|err| anyhow!("Failed to parse the {:?} file: {}", entry_path, err))?;
-
-            if let Some(themes) = addon_manifest.contributes.themes {
-                for theme_contribution in themes {
-                    let value = ThemeDescriptor {
-                        id: format!(
-                            "{}.{}", // TODO: Add the addon author identifier as the first segment for greater uniqueness
-                            addon_manifest.addon.id,
-                            theme_contribution.label.replace(" ", "")
-                        ),
-                        name: theme_contribution.label,
-                        source: addon_dir
-                            .join(theme_contribution.path)
-                            .to_string_lossy()
-                            .to_string(),
-                    };
-
-                    app_state.contributions.themes.insert(value);
-                }
-            }
-
-            if let Some(localizations) = addon_manifest.contributes.localizations {
-                for localization_contribution in localizations {
-                    app_state.contributions.locales.insert(LocaleDescriptor {
-                        code: localization_contribution.code,
-                        name: localization_contribution.name,
-                        direction: match localization_contribution.direction {
-                            Some(direction) => Some(direction),
-                            None => None,
-                        },
-                    });
-                }
-            }
+        if !entry.path().is_dir() {
+            continue;
+        }
+
+        if let Err(err) = load_addon(app_state, &entry.path()) {
+            warn!("Failed to parse addon: {err}");
+            continue;
+        };
+
+        // TODO: Add addon registration once the addon entity becomes more clearly defined
+    }
+}
+
+/// Adds the contributions of the addon in the directory to the registry, as a unit owned by
+/// the addon.
+fn load_addon(app_state: &AppStateManager, addon_dir: &Path) -> Result<String> {
+    let (id, contributions) = parse_addon_dir(addon_dir)?;
+    app_state
+        .contributions
+        .add(ContributionOwner::addon(id.clone()), contributions);
+
+    Ok(id)
+}
+
+/// Reads the manifest of the addon in the directory.
+pub fn read_addon_manifest(addon_dir: &Path) -> Result<AddonManifest> {
+    let manifest_path = addon_dir.join(MANIFEST_FILENAME);
+    if !manifest_path.is_file() {
+        return Err(anyhow!(
+            "The directory {:?} has no {MANIFEST_FILENAME} file",
+            addon_dir
+        ));
+    }
+
+    let file_content = std::fs::read_to_string(&manifest_path)
+        .map_err(|err| anyhow!("Failed to read the {:?} file: {}", manifest_path, err))?;
+
+    toml::from_str(&file_content)
+        .map_err(|err| anyhow!("Failed to parse the {:?} file: {}", manifest_path, err))
+}
+
+// OPTIMIZE: This should probably be moved in the future to a separate entity responsible for loading add-ons.
+fn parse_addon_dir(addon_dir: &Path) -> Result<(String, ContributionSet)> {
+    let addon_manifest = read_addon_manifest(addon_dir)?;
+
+    let mut contributions = ContributionSet::default();
+    if let Some(themes) = addon_manifest.contributes.themes {
+        for theme_contribution in themes {
+            contributions.themes.push(ThemeDescriptor {
+                id: format!(
+                    "{}.{}", // TODO: Add the addon author identifier as the first segment for greater uniqueness
+                    addon_manifest.addon.id,
+                    theme_contribution.label.replace(" ", "")
+                ),
+                name: theme_contribution.label,
+                source: addon_dir
+                    .join(theme_contribution.path)
+                    .to_string_lossy()
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some(localizations) = addon_manifest.contributes.localizations {
+        for localization_contribution in localizations {
+            contributions.locales.push(LocaleDescriptor {
+                code: localization_contribution.code,
+                name: localization_contribution.name,
+                direction: match localization_contribution.direction {
+                    Some(direction) => Some(direction),
+                    None => None,
+                },
+            });
+        }
+    }
+
+    if let Some(keybindings) = addon_manifest.contributes.keybindings {
+        for keybinding_contribution in keybindings {
+            contributions.keybindings.push(KeybindingDecl {
+                key: keybinding_contribution.key.into(),
+                mac: keybinding_contribution.mac.map(Into::into),
+                linux: keybinding_contribution.linux.map(Into::into),
+                win: keybinding_contribution.win.map(Into::into),
+                command: keybinding_contribution.command.into(),
+                args: keybinding_contribution.args,
+                when: keybinding_contribution.when.map(RawRule::from),
+            });
         }
     }
 
-    Ok(())
+    if let Some(menus) = addon_manifest.contributes.menus {
+        let mut items = IndexMap::<String, Vec<MenuItem>>::new();
+        for menu_contribution in menus {
+            let title = LocalizedString::new(
+                format!(
+                    "{}.{}.title",
+                    addon_manifest.addon.id, menu_contribution.command
+                ),
+                menu_contribution.title,
+                Option::<String>::None,
+            );
+
+            items
+                .entry(menu_contribution.menu)
+                .or_default()
+                .push(MenuItem::Action(ActionMenuItem {
+                    command: CommandAction {
+                        id: menu_contribution.command.into(),
+                        title,
+                        tooltip: None,
+                        description: None,
+                        icon: None,
+                        toggled: None,
+                    },
+                    group: menu_contribution.group.map(MenuGroup::unordered),
+                    order: menu_contribution.order,
+                    when: menu_contribution.when.map(RawRule::from),
+                    visibility: MenuItemVisibility::Classic,
+                }));
+        }
+
+        contributions
+            .menus
+            .extend(items.into_iter().map(|(namespace, items)| MenuDecl {
+                namespace: namespace.into(),
+                items,
+            }));
+    }
+
+    Ok((addon_manifest.addon.id, contributions))
 }
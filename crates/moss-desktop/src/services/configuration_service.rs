@@ -1,28 +1,131 @@
 use anyhow::{Context as _, Result};
-use moss_configuration::{default_configuration::DefaultConfiguration, Configuration};
+use moss_configuration::{
+    default_configuration::DefaultConfiguration,
+    file_configuration::FileConfiguration,
+    import::{plan_import, ImportPlan},
+    migration::SettingsVersion,
+    schema::generate_schema,
+    search::{SearchContext, SettingsSearchIndex},
+    Configuration,
+};
+use moss_extension_point::registry::ConfigurationRegistry;
+use parking_lot::RwLock;
 use serde::de::DeserializeOwned;
-use serde_json::Value as JsonValue;
-use std::sync::Arc;
+use serde_json::{Map, Value as JsonValue};
+use std::{collections::HashMap, sync::Arc};
 
-use crate::app::service::Service;
+use crate::{
+    app::service::{DisposeFuture, Service},
+    models::settings::{SettingsSearchGroup, SettingsSearchResult},
+    settings::Settings,
+};
 
 pub struct ConfigurationService {
+    registry: Arc<ConfigurationRegistry>,
     _default_configurations: DefaultConfiguration,
-    configuration: Configuration,
+    user_configuration: RwLock<FileConfiguration>,
+    configuration: RwLock<Configuration>,
+    search_index: SettingsSearchIndex,
 }
 
 impl ConfigurationService {
-    pub fn new(default_configurations: DefaultConfiguration) -> Self {
-        let configuration = Configuration::new(Arc::clone(default_configurations.model()));
+    pub fn new(
+        registry: Arc<ConfigurationRegistry>,
+        default_configurations: DefaultConfiguration,
+        user_configuration: FileConfiguration,
+    ) -> Self {
+        let configuration = Configuration::new(
+            Arc::clone(default_configurations.model()),
+            Arc::clone(user_configuration.model()),
+        );
 
         Self {
+            search_index: SettingsSearchIndex::new(&registry),
+            registry,
             _default_configurations: default_configurations,
-            configuration,
+            user_configuration: RwLock::new(user_configuration),
+            configuration: RwLock::new(configuration),
         }
     }
 
-    pub fn get_value(&self, key: &str) -> Option<&JsonValue> {
-        self.configuration.get_value(key)
+    /// Returns the JSON Schema of the parameters displayed in the settings UI.
+    pub fn schema(&self) -> JsonValue {
+        generate_schema(&self.registry)
+    }
+
+    /// Searches the parameters displayed in the settings UI. The `localized` text of
+    /// the parameters is keyed by the parameter key.
+    pub fn search(
+        &self,
+        query: &str,
+        localized: Option<&HashMap<String, String>>,
+    ) -> Vec<SettingsSearchGroup> {
+        let user_configuration = self.user_configuration.read();
+        let context = SearchContext {
+            modified: Some(user_configuration.model()),
+            localized,
+        };
+
+        self.search_index
+            .search(query, &context)
+            .into_iter()
+            .map(|group| SettingsSearchGroup {
+                id: group.ident.to_string(),
+                display_name: group.display_name,
+                results: group
+                    .results
+                    .into_iter()
+                    .map(|result| SettingsSearchResult {
+                        key: result.key.to_string(),
+                        display_name: result.display_name,
+                        score: result.score,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Returns the settings changed by the user, along with their settings version.
+    pub fn user_settings(&self) -> (Map<String, JsonValue>, SettingsVersion) {
+        let user_configuration = self.user_configuration.read();
+        let settings = user_configuration
+            .model()
+            .contents
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect();
+
+        (settings, user_configuration.version().clone())
+    }
+
+    /// Validates the settings to import and compares them with the current user settings.
+    pub fn plan_settings_import(
+        &self,
+        settings: Map<String, JsonValue>,
+        version: &SettingsVersion,
+    ) -> ImportPlan {
+        plan_import(
+            &self.registry,
+            self.user_configuration.read().model(),
+            settings,
+            version,
+        )
+    }
+
+    /// Replaces all the user settings with the ones accepted by the plan.
+    pub fn apply_settings_import(&self, plan: ImportPlan) -> Result<()> {
+        let mut user_configuration = self.user_configuration.write();
+        user_configuration.replace(plan.settings, plan.version)?;
+
+        self.configuration
+            .write()
+            .set_user(Arc::clone(user_configuration.model()));
+
+        Ok(())
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<JsonValue> {
+        self.configuration.read().get_value(key).cloned()
     }
 
     pub fn get_typed_value<T>(&self, key: &str) -> Result<T>
@@ -33,9 +136,25 @@ impl ConfigurationService {
             .get_value(key)
             .with_context(|| format!("Key '{}' not found in configuration", key))?;
 
-        serde_json::from_value(value.clone())
+        serde_json::from_value(value)
             .with_context(|| format!("Failed to deserialize key '{}' into target type", key))
     }
+
+    /// Returns the value of the key, or the given default if the value is missing
+    /// or cannot be deserialized into the target type.
+    pub fn get_typed_value_or<T>(&self, key: &str, default: T) -> T
+    where
+        T: DeserializeOwned,
+    {
+        self.get_typed_value(key).unwrap_or_else(|err| {
+            warn!("{err:#}, falling back to the declared default");
+            default
+        })
+    }
+
+    pub fn settings(&self) -> Settings<'_> {
+        Settings::new(self)
+    }
 }
 
 impl Service for ConfigurationService {
@@ -43,7 +162,16 @@ impl Service for ConfigurationService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
+    /// Saves the migrated user settings that could not be saved when they were loaded.
+    fn dispose(&self) -> DisposeFuture<'_> {
+        Box::pin(async move {
+            if self.user_configuration.write().save_unsaved()? {
+                debug!("Saved the migrated user settings");
+            }
+
+            Ok(())
+        })
+    }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
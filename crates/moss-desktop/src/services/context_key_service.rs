@@ -0,0 +1,156 @@
+use dashmap::DashMap;
+use moss_text::ReadOnlyStr;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use super::{locale_service::LocaleService, theme_service::ThemeService};
+use crate::{
+    app::{
+        service::{Service, ServiceError, ServiceId, ServiceResolver},
+        state::Preferences,
+    },
+    context_key::{ContextKey, ContextKeyDecl, ContextKeyError, ContextKeyStore, ContextKeyValue},
+    models::{
+        constants::context_key::{CONTEXT_KEY_LOCALE, CONTEXT_KEY_THEME},
+        context_keys::{ContextKeyChange, ContextKeyDescriptor, ContextScope},
+    },
+};
+
+/// The event every window is notified of the changes of the context keys with.
+pub const CONTEXT_KEYS_CHANGED_EVENT: &str = "core://context-keys-changed";
+
+const CHANGES_CHANNEL_CAPACITY: usize = 64;
+
+/// Owns the values the `when` rules of the commands, keybindings and menus are evaluated
+/// against, so that the backend and the frontend see the same ones.
+pub struct ContextKeyService {
+    app_handle: AppHandle,
+    store: ContextKeyStore,
+    changes: broadcast::Sender<ContextKeyChange>,
+}
+
+impl ContextKeyService {
+    /// Sets the global `theme` and `locale` keys to the preferred ones, or to the defaults
+    /// of the [`ThemeService`] and the [`LocaleService`].
+    pub fn new(
+        resolver: &ServiceResolver,
+        app_handle: AppHandle,
+        declarations: Arc<DashMap<ReadOnlyStr, Arc<ContextKeyDecl>>>,
+        preferences: &Preferences,
+    ) -> Result<Self, ServiceError> {
+        let theme_service = resolver.get::<ThemeService>()?;
+        let locale_service = resolver.get::<LocaleService>()?;
+        let service = Self {
+            app_handle,
+            store: ContextKeyStore::new(declarations),
+            changes: broadcast::channel(CHANGES_CHANNEL_CAPACITY).0,
+        };
+
+        let theme = preferences
+            .theme
+            .read()
+            .clone()
+            .unwrap_or_else(|| theme_service.default_color_theme());
+        let locale = preferences
+            .locale
+            .read()
+            .clone()
+            .unwrap_or_else(|| locale_service.default_locale().clone());
+        service.set(&CONTEXT_KEY_THEME, ContextScope::Global, theme.id);
+        service.set(&CONTEXT_KEY_LOCALE, ContextScope::Global, locale.code);
+
+        Ok(service)
+    }
+
+    /// Declares a key that is not contributed, replacing its previous declaration.
+    pub fn declare(&self, decl: ContextKeyDecl) {
+        self.store.declare(decl);
+    }
+
+    pub fn describe(&self) -> Vec<ContextKeyDescriptor> {
+        self.store.describe()
+    }
+
+    /// The value of the key in the scope, see [`ContextKeyStore::get_value`].
+    pub fn get_value(&self, key: &str, scope: &ContextScope) -> Value {
+        self.store.get_value(key, scope)
+    }
+
+    pub fn get<T: ContextKeyValue + Default>(
+        &self,
+        key: &ContextKey<T>,
+        scope: &ContextScope,
+    ) -> T {
+        T::from_value(&self.get_value(key.key, scope)).unwrap_or_default()
+    }
+
+    /// Sets the value of the key in the scope. The value of a declared key must be
+    /// of its type.
+    pub fn set_value(
+        &self,
+        key: &str,
+        scope: ContextScope,
+        value: Value,
+    ) -> Result<(), ContextKeyError> {
+        if let Some(change) = self.store.set_value(key, scope, value)? {
+            self.notify(change);
+        }
+
+        Ok(())
+    }
+
+    pub fn set<T: ContextKeyValue>(&self, key: &ContextKey<T>, scope: ContextScope, value: T) {
+        // The value is of the type of the key, so it cannot be rejected.
+        let _ = self.set_value(key.key, scope, value.into_value());
+    }
+
+    /// Removes the value of the key from the scope, the key gets the value of the scope
+    /// that contains it again.
+    pub fn reset(&self, key: &str, scope: ContextScope) {
+        if let Some(change) = self.store.reset(key, scope) {
+            self.notify(change);
+        }
+    }
+
+    /// Forgets the values set in the window and its views, once it is closed.
+    pub fn clear_window(&self, label: &str) {
+        self.store.clear_window(label);
+    }
+
+    /// The values of all the keys in the scope, see [`ContextKeyStore::context`].
+    pub fn context(&self, scope: &ContextScope) -> Value {
+        self.store.context(scope)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ContextKeyChange> {
+        self.changes.subscribe()
+    }
+
+    fn notify(&self, change: ContextKeyChange) {
+        // There may be no subscriber on the Rust side, which is not an error.
+        let _ = self.changes.send(change.clone());
+
+        if let Err(err) = self.app_handle.emit(CONTEXT_KEYS_CHANGED_EVENT, change) {
+            warn!("Failed to notify the windows of a context key change: {err}");
+        }
+    }
+}
+
+impl Service for ContextKeyService {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn dependencies() -> Vec<ServiceId> {
+        vec![
+            ServiceId::of::<ThemeService>(),
+            ServiceId::of::<LocaleService>(),
+        ]
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,24 +1,49 @@
-use std::sync::atomic::{AtomicU8, Ordering};
+use tokio::sync::watch;
 
 use crate::app::{lifecycle::LifecyclePhase, service::Service};
 
 pub struct LifecycleService {
-    phase: AtomicU8,
+    phase: watch::Sender<LifecyclePhase>,
 }
 
 impl LifecycleService {
     pub fn new() -> Self {
         Self {
-            phase: AtomicU8::new(LifecyclePhase::Starting.as_u8()),
+            phase: watch::Sender::new(LifecyclePhase::Starting),
         }
     }
 
     pub fn get_current_phase(&self) -> LifecyclePhase {
-        self.phase.load(Ordering::SeqCst).into()
+        *self.phase.borrow()
     }
 
+    /// Moves the application to the phase. The phases only move forward,
+    /// an attempt to go back to an earlier phase is ignored.
     pub fn set_current_phase(&self, value: LifecyclePhase) {
-        self.phase.store(value.as_u8(), Ordering::SeqCst);
+        let advanced = self.phase.send_if_modified(|phase| {
+            if value > *phase {
+                *phase = value;
+                true
+            } else {
+                false
+            }
+        });
+
+        if advanced {
+            info!("The application entered the {value:?} phase");
+        } else {
+            warn!(
+                "Cannot enter the {value:?} phase, the application is already in the {:?} phase",
+                self.get_current_phase()
+            );
+        }
+    }
+
+    /// Waits until the application reaches the phase, returns right away if it already has.
+    pub async fn when_phase(&self, phase: LifecyclePhase) {
+        let mut receiver = self.phase.subscribe();
+        // The sender lives as long as the service, so the wait cannot fail while it is borrowed.
+        let _ = receiver.wait_for(|current| *current >= phase).await;
     }
 }
 
@@ -27,8 +52,6 @@ impl Service for LifecycleService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
-
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
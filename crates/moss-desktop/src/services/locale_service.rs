@@ -1,109 +1,121 @@
 use crate::{
-    app::{service::Service, state::AppStateManager},
+    app::{
+        service::{DisposeFuture, Service},
+        state::AppCache,
+    },
     models::application::LocaleDescriptor,
 };
 use anyhow::{anyhow, Context as _, Result};
 use dashmap::DashSet;
 use dirs::home_dir;
-use moss_cache::{backend::moka::MokaBackend, Cache, CacheError};
+use moss_cache::{CacheKey, FileStamp};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::any::Any;
-use std::{path::PathBuf, sync::Arc};
-use tauri::{AppHandle, Manager};
-
-const CK_TRANSLATIONS: &'static str = "translations";
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct LocaleCacheEntry {
-    language: String,
     data: Value,
 }
 
+/// Translations, keyed by the path and the stamp of the translations file.
+const CK_TRANSLATIONS: CacheKey<LocaleCacheEntry, (String, FileStamp)> =
+    CacheKey::new("translations")
+        .with_namespace("locale")
+        .with_capacity(32);
+
 pub struct LocaleService {
-    app_cache: Arc<Cache<MokaBackend>>,
+    app_cache: Arc<AppCache>,
     locales: Arc<DashSet<LocaleDescriptor>>,
+    default_locale: LocaleDescriptor,
 }
 
 impl LocaleService {
-    pub fn new(app_handle: &AppHandle) -> Self {
-        let app_state = app_handle.state::<AppStateManager>();
-
+    pub fn new(
+        app_cache: Arc<AppCache>,
+        locales: Arc<DashSet<LocaleDescriptor>>,
+        default_locale: LocaleDescriptor,
+    ) -> Self {
         Self {
-            app_cache: Arc::clone(&app_state.cache),
-            locales: Arc::clone(&app_state.contributions.locales),
+            app_cache,
+            locales,
+            default_locale,
         }
     }
 
     pub fn get_locales(&self) -> &DashSet<LocaleDescriptor> {
         &self.locales
     }
+
+    /// The locale used until another one is selected.
+    pub fn default_locale(&self) -> &LocaleDescriptor {
+        &self.default_locale
+    }
+
     pub async fn get_translations(&self, language: &str, namespace: &str) -> Result<Value> {
-        match self
+        let full_path = get_locales_dir()?
+            .join(language)
+            .join(format!("{namespace}.json"));
+        let stamp = self
             .app_cache
-            .get::<LocaleCacheEntry>(&format!("{CK_TRANSLATIONS}-{namespace}"))
-        {
-            Ok(entry) => {
-                if entry.language == language {
-                    trace!(
-                        "Language Pack '{}-{}' was restored from the cache",
-                        language,
-                        namespace
-                    );
-                    return Ok(entry.data.clone());
-                } else {
-                    trace!(
-                        "Language Pack in cache does not match the requested language '{}'",
-                        language
-                    );
-                }
-            }
-            Err(CacheError::NonexistentKey { .. }) => {
-                trace!("No language pack in cache for key '{}'", CK_TRANSLATIONS);
-            }
-            Err(CacheError::TypeMismatch { key, type_name }) => {
-                warn!(
-                    "Type mismatch for key '{}': expected 'LocaleCacheEntry', found '{}'",
-                    key, type_name
+            .stamp_file(&full_path)
+            .with_context(|| format!("Failed to read file '{}'", full_path.display()))?;
+        let (language_owned, namespace_owned) = (language.to_string(), namespace.to_string());
+
+        let entry = self
+            .app_cache
+            .entry(
+                &CK_TRANSLATIONS,
+                (full_path.to_string_lossy().to_string(), stamp),
+            )
+            .with_tag(locale_tag(language))
+            .with_tag(FileStamp::tag(&full_path))
+            .get_or_load_persistent(move || async move {
+                trace!(
+                    "Loading language pack '{}-{}'",
+                    language_owned,
+                    namespace_owned
                 );
-            }
-        }
-        self.get_translations_internal(language, namespace).await
-    }
 
-    async fn get_translations_internal(&self, language: &str, namespace: &str) -> Result<Value> {
-        let translations = self
-            .read_translations_from_file(language, namespace)
+                let data = read_translations_from_file(&full_path).await?;
+                Ok::<_, anyhow::Error>(LocaleCacheEntry { data })
+            })
             .await?;
-        self.app_cache.insert(
-            &format!("{CK_TRANSLATIONS}-{namespace}"),
-            LocaleCacheEntry {
-                language: language.to_string(),
-                data: translations.clone(),
-            },
-        );
-
-        Ok(translations)
-    }
 
-    async fn read_translations_from_file(&self, language: &str, namespace: &str) -> Result<Value> {
-        let locales_dir = get_locales_dir()?;
-        let full_path = locales_dir.join(language).join(format!("{namespace}.json"));
+        Ok(entry.data.clone())
+    }
 
-        if !full_path.exists() {
-            return Err(anyhow!("File '{}' does not exist", full_path.display()));
-        }
+    /// Drops the cached translations of the language, in all the namespaces,
+    /// so that they are read again once the language pack has changed.
+    pub fn invalidate_translations(&self, language: &str) {
+        let count = self.app_cache.invalidate_tag(&locale_tag(language));
+        trace!("Invalidated {count} cached translations of '{language}'");
+    }
+}
 
-        if !full_path.is_file() {
-            return Err(anyhow!("Path '{}' is not a file", full_path.display()));
-        }
+fn locale_tag(language: &str) -> String {
+    format!("locale:{language}")
+}
 
-        let content = smol::fs::read_to_string(&full_path)
-            .await
-            .with_context(|| format!("Failed to read file '{}'", full_path.display()))?;
+async fn read_translations_from_file(full_path: &Path) -> Result<Value> {
+    if !full_path.exists() {
+        return Err(anyhow!("File '{}' does not exist", full_path.display()));
+    }
 
-        serde_json::from_str::<Value>(&content)
-            .with_context(|| format!("Failed to parse file '{}'", full_path.display()))
+    if !full_path.is_file() {
+        return Err(anyhow!("Path '{}' is not a file", full_path.display()));
     }
+
+    let content = smol::fs::read_to_string(full_path)
+        .await
+        .with_context(|| format!("Failed to read file '{}'", full_path.display()))?;
+
+    serde_json::from_str::<Value>(&content)
+        .with_context(|| format!("Failed to parse file '{}'", full_path.display()))
 }
 
 impl Service for LocaleService {
@@ -111,7 +123,16 @@ impl Service for LocaleService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
+    /// Waits for the translations being read, so that they are persisted in the cache.
+    fn dispose(&self) -> DisposeFuture<'_> {
+        Box::pin(async move {
+            self.app_cache
+                .wait_for_loads(CK_TRANSLATIONS.namespace())
+                .await;
+
+            Ok(())
+        })
+    }
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Context as _, Result};
 use dashmap::DashSet;
-use moss_cache::{backend::moka::MokaBackend, Cache, CacheError};
+use moss_cache::{CacheKey, FileStamp};
 use moss_theme::{
     conversion::{
         json_converter::JsonThemeConverter, jsonschema_validator::JsonSchemaValidator,
@@ -8,106 +8,121 @@ use moss_theme::{
     },
     schema::SCHEMA_THEME,
 };
-use std::{ops::Deref, path::PathBuf, sync::Arc};
-use tauri::{AppHandle, Manager};
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use super::configuration_service::ConfigurationService;
 use crate::{
-    app::{service::Service, state::AppStateManager},
+    app::{
+        service::{
+            DisposeFuture, Service, ServiceError, ServiceHandle, ServiceId, ServiceResolver,
+        },
+        state::AppCache,
+    },
     models::application::ThemeDescriptor,
 };
 
-const CK_COLOR_THEME: &'static str = "color_theme";
-
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct ThemeCacheEntry {
-    source: String,
     data: String,
 }
 
+/// Converted color themes, keyed by the path and the stamp of the theme file.
+const CK_COLOR_THEME: CacheKey<ThemeCacheEntry, (String, FileStamp)> = CacheKey::new("color_theme")
+    .with_namespace("theme")
+    .with_capacity(8);
+
 pub struct ThemeService {
-    app_cache: Arc<Cache<MokaBackend>>,
+    app_cache: Arc<AppCache>,
     converter: Arc<dyn ThemeConverter + Send + Sync>,
     themes: Arc<DashSet<ThemeDescriptor>>,
+    configuration: ServiceHandle<ConfigurationService>,
+    fallback_theme: ThemeDescriptor,
 }
 
 impl ThemeService {
-    pub fn new(app_handle: &AppHandle) -> Self {
-        let app_state = app_handle.state::<AppStateManager>();
+    /// The `fallback_theme` is used when the theme of the `workbench.colorTheme` setting
+    /// is not among the contributed ones.
+    pub fn new(
+        resolver: &ServiceResolver,
+        app_cache: Arc<AppCache>,
+        themes: Arc<DashSet<ThemeDescriptor>>,
+        fallback_theme: ThemeDescriptor,
+    ) -> Result<Self, ServiceError> {
         let json_schema_validator = JsonSchemaValidator::new(SCHEMA_THEME.deref());
         let converter = JsonThemeConverter::new(json_schema_validator);
 
-        // let s = SCHEMA_THEME.deref();
-
-        Self {
-            app_cache: Arc::clone(&app_state.cache),
+        Ok(Self {
+            app_cache,
             converter: Arc::new(converter),
-            themes: Arc::clone(&app_state.contributions.themes),
-        }
+            themes,
+            configuration: resolver.get::<ConfigurationService>()?,
+            fallback_theme,
+        })
     }
 
     pub fn get_color_themes(&self) -> &DashSet<ThemeDescriptor> {
         &self.themes
     }
 
-    pub async fn get_color_theme(&self, source: &str) -> Result<String> {
-        match self.app_cache.get::<ThemeCacheEntry>(CK_COLOR_THEME) {
-            Ok(entry) if entry.source == source => {
-                trace!("Color theme '{}' was restored from the cache", source);
-                return Ok(entry.data.clone());
-            }
-            Ok(_) => {
-                trace!(
-                    "Color theme in cache does not match the requested source '{}'",
-                    source
-                );
-            }
-            Err(CacheError::NonexistentKey { .. }) => {
-                trace!("No color theme found in cache for key '{}'", CK_COLOR_THEME);
-            }
-            Err(CacheError::TypeMismatch { key, type_name }) => {
-                warn!(
-                    "Type mismatch for key '{}': expected 'ThemeCacheEntry', found '{}'",
-                    key, type_name
-                );
-            }
-        }
-
-        self.get_color_theme_internal(source).await
-    }
+    /// The theme used until another one is selected.
+    pub fn default_color_theme(&self) -> ThemeDescriptor {
+        let id = self.configuration.settings().workbench().color_theme();
 
-    async fn get_color_theme_internal(&self, source: &str) -> Result<String> {
-        let json_data = self.read_color_theme_from_file(source).await?;
-        // TODO: Add merging of the global theme object with the user’s custom theme settings object.
-        let css_data = self.converter.convert_to_css(json_data)?;
-
-        self.app_cache.insert(
-            CK_COLOR_THEME,
-            ThemeCacheEntry {
-                source: source.to_string(),
-                data: css_data.clone(),
-            },
-        );
+        self.themes
+            .iter()
+            .find(|theme| theme.id == id)
+            .map(|theme| theme.clone())
+            .unwrap_or_else(|| self.fallback_theme.clone())
+    }
 
-        Ok(css_data)
+    pub async fn get_color_theme(&self, source: &str) -> Result<String> {
+        let converter = Arc::clone(&self.converter);
+        let path = source.to_string();
+        let stamp = self
+            .app_cache
+            .stamp_file(Path::new(source))
+            .with_context(|| format!("Failed to read file '{source}'"))?;
+
+        let entry = self
+            .app_cache
+            .entry(&CK_COLOR_THEME, (source.to_string(), stamp))
+            .with_tag(FileStamp::tag(source))
+            .get_or_load_persistent(move || async move {
+                trace!("Loading color theme '{}'", path);
+
+                let json_data = read_color_theme_from_file(&path).await?;
+                // TODO: Add merging of the global theme object with the user’s custom theme settings object.
+                let css_data = converter.convert_to_css(json_data)?;
+
+                Ok::<_, anyhow::Error>(ThemeCacheEntry { data: css_data })
+            })
+            .await?;
+
+        Ok(entry.data.clone())
     }
+}
 
-    async fn read_color_theme_from_file(&self, path: &str) -> Result<String> {
-        let full_path = PathBuf::from(path);
+async fn read_color_theme_from_file(path: &str) -> Result<String> {
+    let full_path = PathBuf::from(path);
 
-        if !full_path.exists() {
-            return Err(anyhow!("File '{}' does not exist", full_path.display()));
-        }
+    if !full_path.exists() {
+        return Err(anyhow!("File '{}' does not exist", full_path.display()));
+    }
 
-        if !full_path.is_file() {
-            return Err(anyhow!("Path '{}' is not a file", full_path.display()));
-        }
+    if !full_path.is_file() {
+        return Err(anyhow!("Path '{}' is not a file", full_path.display()));
+    }
 
-        let content = smol::fs::read_to_string(&full_path)
-            .await
-            .with_context(|| format!("Failed to read file '{}'", full_path.display()))?;
+    let content = smol::fs::read_to_string(&full_path)
+        .await
+        .with_context(|| format!("Failed to read file '{}'", full_path.display()))?;
 
-        Ok(content)
-    }
+    Ok(content)
 }
 
 impl Service for ThemeService {
@@ -115,7 +130,20 @@ impl Service for ThemeService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
+    fn dependencies() -> Vec<ServiceId> {
+        vec![ServiceId::of::<ConfigurationService>()]
+    }
+
+    /// Waits for the themes being converted, so that they are persisted in the cache.
+    fn dispose(&self) -> DisposeFuture<'_> {
+        Box::pin(async move {
+            self.app_cache
+                .wait_for_loads(CK_COLOR_THEME.namespace())
+                .await;
+
+            Ok(())
+        })
+    }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
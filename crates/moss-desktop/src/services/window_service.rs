@@ -27,8 +27,6 @@ impl Service for WindowService {
         std::any::type_name::<Self>()
     }
 
-    fn dispose(&self) {}
-
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
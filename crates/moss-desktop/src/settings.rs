@@ -0,0 +1,6 @@
+//! Strongly typed accessors for the parameters declared in the `contributions` directory.
+//!
+//! The accessors are generated by the build script, so renaming or retyping
+//! a parameter breaks the build instead of failing at runtime.
+
+include!(concat!(env!("OUT_DIR"), "/settings.rs"));
@@ -0,0 +1,211 @@
+//! Export and import of the user setup as a single settings bundle file.
+
+use anyhow::{anyhow, Context as _, Result};
+use dashmap::DashMap;
+use moss_configuration::import::ImportPlan;
+use std::{
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    app::state::AppStateManager,
+    models::{
+        application::{LocaleDescriptor, ThemeDescriptor},
+        settings::{
+            RejectedSettingDescriptor, SettingChangeDescriptor, SettingsBundle,
+            SettingsImportPreview,
+        },
+    },
+    services::configuration_service::ConfigurationService,
+};
+
+pub const SETTINGS_BUNDLE_FORMAT: &str = "moss.settings";
+pub const SETTINGS_BUNDLE_VERSION: u32 = 1;
+
+pub fn export_bundle(
+    configuration_service: &ConfigurationService,
+    app_state: &AppStateManager,
+) -> SettingsBundle {
+    let (settings, settings_version) = configuration_service.user_settings();
+
+    SettingsBundle {
+        format: SETTINGS_BUNDLE_FORMAT.to_string(),
+        version: SETTINGS_BUNDLE_VERSION,
+        settings_version,
+        settings,
+        theme: app_state
+            .preferences
+            .theme
+            .read()
+            .as_ref()
+            .map(|theme| theme.id.clone()),
+        locale: app_state
+            .preferences
+            .locale
+            .read()
+            .as_ref()
+            .map(|locale| locale.code.clone()),
+    }
+}
+
+pub fn write_bundle(path: &Path, bundle: &SettingsBundle) -> Result<()> {
+    let content = serde_json::to_string_pretty(bundle)?;
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write the settings bundle {}", path.display()))
+}
+
+pub fn read_bundle(path: &Path) -> Result<SettingsBundle> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the settings bundle {}", path.display()))?;
+    let bundle: SettingsBundle = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse the settings bundle {}", path.display()))?;
+
+    if bundle.format != SETTINGS_BUNDLE_FORMAT {
+        return Err(anyhow!(
+            "The file {} is not a settings bundle",
+            path.display()
+        ));
+    }
+
+    if bundle.version > SETTINGS_BUNDLE_VERSION {
+        return Err(anyhow!(
+            "The settings bundle version {} is not supported, the latest supported version is {}",
+            bundle.version,
+            SETTINGS_BUNDLE_VERSION
+        ));
+    }
+
+    Ok(bundle)
+}
+
+/// A validated import that can be previewed before it is applied.
+pub struct PreparedImport {
+    plan: ImportPlan,
+    theme: Option<ThemeDescriptor>,
+    locale: Option<LocaleDescriptor>,
+    preview: SettingsImportPreview,
+}
+
+impl PreparedImport {
+    pub fn new(
+        configuration_service: &ConfigurationService,
+        app_state: &AppStateManager,
+        bundle: SettingsBundle,
+    ) -> Self {
+        let plan =
+            configuration_service.plan_settings_import(bundle.settings, &bundle.settings_version);
+
+        let theme = bundle.theme.as_ref().and_then(|id| {
+            app_state
+                .contributions
+                .themes
+                .iter()
+                .find(|theme| &theme.id == id)
+                .map(|theme| theme.key().clone())
+        });
+        let locale = bundle.locale.as_ref().and_then(|code| {
+            app_state
+                .contributions
+                .locales
+                .iter()
+                .find(|locale| &locale.code == code)
+                .map(|locale| locale.key().clone())
+        });
+
+        let preview = SettingsImportPreview {
+            token: String::new(),
+            changes: plan
+                .changes
+                .iter()
+                .map(|change| SettingChangeDescriptor {
+                    key: change.key.clone(),
+                    old_value: change.old_value.clone(),
+                    new_value: change.new_value.clone(),
+                })
+                .collect(),
+            unknown_keys: plan.unknown_keys.clone(),
+            invalid_settings: plan
+                .invalid
+                .iter()
+                .map(|rejected| RejectedSettingDescriptor {
+                    key: rejected.key.clone(),
+                    reason: rejected.reason.clone(),
+                })
+                .collect(),
+            theme: theme.clone(),
+            locale: locale.clone(),
+            unknown_theme: bundle.theme.filter(|_| theme.is_none()),
+            unknown_locale: bundle.locale.filter(|_| locale.is_none()),
+        };
+
+        Self {
+            plan,
+            theme,
+            locale,
+            preview,
+        }
+    }
+
+    pub fn preview(&self) -> &SettingsImportPreview {
+        &self.preview
+    }
+
+    /// Saves the imported settings and only then switches the preferences,
+    /// so a failed import leaves the current setup untouched.
+    pub fn apply(
+        self,
+        configuration_service: &ConfigurationService,
+        app_state: &AppStateManager,
+    ) -> Result<SettingsImportPreview> {
+        configuration_service.apply_settings_import(self.plan)?;
+
+        if let Some(theme) = self.theme {
+            app_state.change_color_theme(theme);
+        }
+        if let Some(locale) = self.locale {
+            app_state.change_language_pack(locale);
+        }
+
+        Ok(self.preview)
+    }
+}
+
+/// The imports previewed in the windows, waiting to be applied. A window has at most one,
+/// the one it previewed last.
+#[derive(Default)]
+pub struct PendingImports {
+    imports: DashMap<String, PreparedImport>,
+    next_token: AtomicU64,
+}
+
+impl PendingImports {
+    /// Keeps the import until it is applied, returns its preview along with the token
+    /// it is applied with.
+    pub fn insert(&self, window: &str, mut prepared: PreparedImport) -> SettingsImportPreview {
+        prepared.preview.token = format!(
+            "{window}:{}",
+            self.next_token.fetch_add(1, Ordering::Relaxed)
+        );
+        let preview = prepared.preview.clone();
+        self.imports.insert(window.to_string(), prepared);
+
+        preview
+    }
+
+    /// Takes the import previewed in the window, so that exactly the previewed settings
+    /// are applied, even if the bundle has changed since.
+    pub fn take(&self, window: &str, token: &str) -> Result<PreparedImport> {
+        self.imports
+            .remove_if(window, |_, prepared| prepared.preview.token == token)
+            .map(|(_, prepared)| prepared)
+            .ok_or_else(|| {
+                anyhow!("The settings import {token} is not previewed in this window or was applied already")
+            })
+    }
+
+    /// Forgets the import previewed in the window, once it is closed.
+    pub fn clear_window(&self, window: &str) {
+        self.imports.remove(window);
+    }
+}
@@ -1,10 +1,11 @@
 use anyhow::{anyhow, Result};
 use arcstr::ArcStr;
 use hashbrown::{HashMap, HashSet};
-use moss_mel::foundations::configuration::{ConfigurationNode, Override, Parameter};
+use moss_mel::foundations::configuration::{ConfigurationNode, Migration, Override, Parameter};
 use parking_lot::Mutex;
 use serde_json::Value as JsonValue;
 use std::{path::PathBuf, sync::Arc};
+use thiserror::Error;
 
 static __EP_REGISTRY__: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
 
@@ -28,11 +29,28 @@ pub fn with_mut(f: impl FnOnce(&mut Vec<PathBuf>)) {
     f(&mut __EP_REGISTRY__.lock())
 }
 
-#[derive(Debug, Clone)]
+/// Identifies the package that contributed a configuration declaration.
+/// Declarations registered without provider info come from the built-in modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValueProviderInfo {
     pub id: String,
 }
 
+#[derive(Debug, Clone, Error)]
+pub enum RegistryDiagnostic {
+    #[error("The override of the protected parameter '{key}' provided by '{}' was rejected", provider.id)]
+    ProtectedOverrideRejected {
+        key: ArcStr,
+        provider: ValueProviderInfo,
+    },
+}
+
+#[derive(Debug)]
+pub struct ConfigurationNodeEntry {
+    pub node: Arc<ConfigurationNode>,
+    pub provider_info: Option<ValueProviderInfo>,
+}
+
 #[derive(Debug, Clone)]
 pub struct DefaultOverrideDescriptor {
     pub value: JsonValue,
@@ -47,20 +65,55 @@ pub struct DefaultOverrides {
 
 #[derive(Debug, Default)]
 pub struct ConfigurationRegistry {
-    configuration_nodes: Vec<Arc<ConfigurationNode>>,
+    configuration_nodes: Vec<ConfigurationNodeEntry>,
     known_parameters: HashMap<ArcStr, Arc<Parameter>>,
     excluded_parameters: HashMap<ArcStr, Arc<Parameter>>,
     default_overrides: HashMap<ArcStr, DefaultOverrides>,
     specific_overrides: HashMap<ArcStr, Override>,
     override_identifiers: HashSet<ArcStr>,
     decl_identifiers: HashSet<ArcStr>,
+    migrations: Vec<Arc<Migration>>,
+    diagnostics: Vec<RegistryDiagnostic>,
 }
 
 impl ConfigurationRegistry {
+    /// Parameters that are listed in the settings UI and included in the generated schema.
     pub fn parameters(&self) -> &HashMap<ArcStr, Arc<Parameter>> {
         &self.known_parameters
     }
 
+    /// Parameters that are hidden from the settings UI and the schema,
+    /// but still have a value in the configuration models.
+    pub fn excluded_parameters(&self) -> &HashMap<ArcStr, Arc<Parameter>> {
+        &self.excluded_parameters
+    }
+
+    pub fn all_parameters(&self) -> impl Iterator<Item = (&ArcStr, &Arc<Parameter>)> {
+        self.known_parameters
+            .iter()
+            .chain(self.excluded_parameters.iter())
+    }
+
+    pub fn get_parameter(&self, key: &str) -> Option<&Arc<Parameter>> {
+        self.known_parameters
+            .get(key)
+            .or_else(|| self.excluded_parameters.get(key))
+    }
+
+    /// Configuration nodes in the order they were registered.
+    pub fn configuration_nodes(&self) -> &[ConfigurationNodeEntry] {
+        &self.configuration_nodes
+    }
+
+    /// Migrations of the outdated parameter keys, ordered by their version.
+    pub fn migrations(&self) -> &[Arc<Migration>] {
+        &self.migrations
+    }
+
+    pub fn diagnostics(&self) -> &[RegistryDiagnostic] {
+        &self.diagnostics
+    }
+
     pub fn get_override(&self, key: &ArcStr) -> Option<Arc<DefaultOverrideDescriptor>> {
         self.default_overrides
             .get(key)
@@ -68,7 +121,24 @@ impl ConfigurationRegistry {
             .cloned()
     }
 
+    /// Registers configuration nodes declared by the built-in modules.
     pub fn register<I>(&mut self, nodes: I)
+    where
+        I: IntoIterator<Item = ConfigurationNode>,
+    {
+        self.register_internal(nodes, None)
+    }
+
+    /// Registers configuration nodes contributed by an addon package.
+    /// Overrides of protected parameters from such nodes are rejected.
+    pub fn register_from_provider<I>(&mut self, provider_info: ValueProviderInfo, nodes: I)
+    where
+        I: IntoIterator<Item = ConfigurationNode>,
+    {
+        self.register_internal(nodes, Some(provider_info))
+    }
+
+    fn register_internal<I>(&mut self, nodes: I, provider_info: Option<ValueProviderInfo>)
     where
         I: IntoIterator<Item = ConfigurationNode>,
     {
@@ -79,12 +149,18 @@ impl ConfigurationRegistry {
             }
 
             self.register_parameters(&node.parameters);
-            self.register_overrides(&node.overrides);
+            self.register_overrides(&node.overrides, provider_info.as_ref());
+            self.register_migrations(&node.migrations);
 
             self.override_identifiers
                 .extend(node.overrides.keys().cloned());
-            self.decl_identifiers.insert(ArcStr::clone(&node.ident));
-            self.configuration_nodes.push(Arc::new(node));
+            if !node.ident.is_empty() {
+                self.decl_identifiers.insert(ArcStr::clone(&node.ident));
+            }
+            self.configuration_nodes.push(ConfigurationNodeEntry {
+                node: Arc::new(node),
+                provider_info: provider_info.clone(),
+            });
         }
     }
 
@@ -102,13 +178,36 @@ impl ConfigurationRegistry {
             };
 
             target.insert(ArcStr::clone(key), Arc::clone(decl));
+
+            if decl.protected {
+                // Overrides might have been registered before the parameter itself
+                // was declared, so the ones coming from addons must be dropped here.
+                self.reject_provided_overrides(key);
+            }
         }
     }
 
-    fn register_overrides(&mut self, overrides: &HashMap<ArcStr, Arc<Override>>) {
+    fn register_overrides(
+        &mut self,
+        overrides: &HashMap<ArcStr, Arc<Override>>,
+        provider_info: Option<&ValueProviderInfo>,
+    ) {
         for (override_key, override_decl) in overrides {
             // TODO: validate the override key and declaration
 
+            if let Some(provider_info) = provider_info {
+                if self
+                    .get_parameter(override_key)
+                    .is_some_and(|parameter| parameter.protected)
+                {
+                    self.report(RegistryDiagnostic::ProtectedOverrideRejected {
+                        key: ArcStr::clone(override_key),
+                        provider: provider_info.clone(),
+                    });
+                    continue;
+                }
+            }
+
             if override_decl.value.is_null() {
                 warn!("The value of the '{override_key}' override is null. This override will be ignored.");
                 continue;
@@ -123,7 +222,7 @@ impl ConfigurationRegistry {
                 JsonValue::Bool(_) | JsonValue::Number(_) | JsonValue::String(_) => {
                     DefaultOverrideDescriptor {
                         value: override_decl.value.clone(),
-                        provider_info: None,
+                        provider_info: provider_info.cloned(),
                     }
                 }
                 JsonValue::Null => unreachable!("Null values are already checked earlier."),
@@ -145,8 +244,67 @@ impl ConfigurationRegistry {
         }
     }
 
+    fn register_migrations(&mut self, migrations: &[Arc<Migration>]) {
+        for migration in migrations {
+            if self.migrations.iter().any(|registered| {
+                registered.contributor == migration.contributor
+                    && registered.ident == migration.ident
+                    && registered.version == migration.version
+            }) {
+                warn!(
+                    "The migration of the parameter '{}' to version {} of '{}' has already been registered",
+                    migration.ident, migration.version, migration.contributor
+                );
+                continue;
+            }
+
+            // Migrations of the same version are applied in the order they were declared.
+            let index = self
+                .migrations
+                .partition_point(|registered| registered.version <= migration.version);
+            self.migrations.insert(index, Arc::clone(migration));
+        }
+    }
+
+    fn reject_provided_overrides(&mut self, key: &ArcStr) {
+        let Some(default_overrides) = self.default_overrides.get_mut(key) else {
+            return;
+        };
+
+        let (rejected, retained): (Vec<_>, Vec<_>) = default_overrides
+            .all
+            .drain(..)
+            .partition(|descriptor| descriptor.provider_info.is_some());
+
+        if let Some(last) = retained.last() {
+            default_overrides.consolidated = Arc::clone(last);
+            default_overrides.all = retained;
+        } else {
+            self.default_overrides.remove(key);
+        }
+
+        for descriptor in rejected {
+            if let Some(provider) = &descriptor.provider_info {
+                self.report(RegistryDiagnostic::ProtectedOverrideRejected {
+                    key: ArcStr::clone(key),
+                    provider: provider.clone(),
+                });
+            }
+        }
+    }
+
+    fn report(&mut self, diagnostic: RegistryDiagnostic) {
+        warn!("{diagnostic}");
+        self.diagnostics.push(diagnostic);
+    }
+
     fn validate_decl(&self, decl: &ConfigurationNode) -> Result<()> {
         let key = decl.ident.clone();
+        if key.is_empty() {
+            // Anonymous configurations only extend the existing ones.
+            return Ok(());
+        }
+
         if self.decl_identifiers.get(&key).is_some() {
             return Err(anyhow!(
                 "A declaration with the identifier {} already exists.",
@@ -170,7 +328,7 @@ impl ConfigurationRegistry {
     }
 
     fn validate_parameter(&self, key: &ArcStr, parameter: &Parameter) -> Result<()> {
-        if self.known_parameters.get(key).is_some() {
+        if self.get_parameter(key).is_some() {
             return Err(anyhow!("This parameter has already been registered"));
         }
 
@@ -222,3 +380,124 @@ impl Registry {
         Arc::clone(&self.configurations)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::eval::Context;
+    use moss_mel::{foundations::scope::ModuleScope, parse::parse_module_file};
+
+    fn evaluate(input: &str) -> Vec<ConfigurationNode> {
+        let mut scope = ModuleScope::new();
+        parse_module_file(input, &mut scope).unwrap();
+        scope
+            .evaluate_with_context(&mut Context::new())
+            .unwrap()
+            .into_values()
+    }
+
+    fn addon_provider() -> ValueProviderInfo {
+        ValueProviderInfo {
+            id: "theme-defaults".to_string(),
+        }
+    }
+
+    const BUILTIN: &str = r#"
+    configuration "moss.kernel.security" {
+        parameter "security.trustedOrigins" {
+            type = string
+            default = "self"
+            protected = true
+        }
+
+        parameter "security.telemetryEndpoint" {
+            type = string
+            default = "https://example.com"
+            excluded = true
+        }
+
+        parameter "editor.fontSize" {
+            type = number
+            default = 14
+        }
+    }
+    "#;
+
+    const ADDON: &str = r#"
+    configuration "theme-defaults.overrides" {
+        override "security.trustedOrigins" {
+            value = "*"
+        }
+
+        override "editor.fontSize" {
+            value = 16
+        }
+    }
+    "#;
+
+    #[test]
+    fn test_protected_parameter_rejects_addon_override() {
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(evaluate(BUILTIN));
+        registry.register_from_provider(addon_provider(), evaluate(ADDON));
+
+        assert!(registry
+            .get_override(&ArcStr::from("security.trustedOrigins"))
+            .is_none());
+        assert_eq!(
+            registry
+                .get_override(&ArcStr::from("editor.fontSize"))
+                .unwrap()
+                .value,
+            JsonValue::from(16)
+        );
+        assert!(matches!(
+            registry.diagnostics(),
+            [RegistryDiagnostic::ProtectedOverrideRejected { key, .. }] if key == "security.trustedOrigins"
+        ));
+    }
+
+    #[test]
+    fn test_protected_parameter_rejects_override_registered_earlier() {
+        let mut registry = ConfigurationRegistry::default();
+        registry.register_from_provider(addon_provider(), evaluate(ADDON));
+        registry.register(evaluate(BUILTIN));
+
+        assert!(registry
+            .get_override(&ArcStr::from("security.trustedOrigins"))
+            .is_none());
+        assert_eq!(registry.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_protected_parameter_accepts_builtin_override() {
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(evaluate(BUILTIN));
+        registry.register(evaluate(ADDON));
+
+        assert_eq!(
+            registry
+                .get_override(&ArcStr::from("security.trustedOrigins"))
+                .unwrap()
+                .value,
+            JsonValue::from("*")
+        );
+        assert!(registry.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_excluded_parameter_is_not_listed() {
+        let mut registry = ConfigurationRegistry::default();
+        registry.register(evaluate(BUILTIN));
+
+        assert!(!registry
+            .parameters()
+            .contains_key("security.telemetryEndpoint"));
+        assert!(registry
+            .excluded_parameters()
+            .contains_key("security.telemetryEndpoint"));
+        assert!(registry
+            .get_parameter("security.telemetryEndpoint")
+            .is_some());
+    }
+}
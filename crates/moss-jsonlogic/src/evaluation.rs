@@ -0,0 +1,538 @@
+use serde_json::{json, Number, Value};
+use thiserror::Error;
+
+use crate::raw_rule::RawRule;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    #[error("Unknown operator '{0}'")]
+    UnknownOperator(String),
+
+    #[error("Invalid arguments for operator '{operator}': {reason}")]
+    InvalidArguments { operator: String, reason: String },
+}
+
+impl RawRule {
+    /// Evaluates the rule against the data, following the JSON Logic semantics.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use moss_jsonlogic::raw_rule::RawRule;
+    /// use serde_json::json;
+    ///
+    /// let rule = RawRule::var("age").gt(RawRule::value(18));
+    /// assert_eq!(rule.evaluate(&json!({ "age": 21 })).unwrap(), json!(true));
+    /// ```
+    pub fn evaluate(&self, data: &Value) -> Result<Value, EvaluationError> {
+        // The rules deserialized from JSON are kept as constants, so both are evaluated
+        // from their JSON Logic form.
+        let logic = serde_json::to_value(self).unwrap_or(Value::Null);
+        evaluate(&logic, data)
+    }
+
+    /// Evaluates the rule and tells whether the result is truthy, see [`is_truthy`].
+    pub fn is_satisfied(&self, data: &Value) -> Result<bool, EvaluationError> {
+        self.evaluate(data).map(|value| is_truthy(&value))
+    }
+}
+
+/// The truthiness of JSON Logic: `null`, `false`, `0`, `""` and `[]` are falsy,
+/// everything else is truthy.
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(value) => !value.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// Evaluates a rule in its JSON Logic form against the data.
+pub fn evaluate(logic: &Value, data: &Value) -> Result<Value, EvaluationError> {
+    match logic {
+        Value::Array(values) => values
+            .iter()
+            .map(|value| evaluate(value, data))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Value::Object(map) if map.len() == 1 => {
+            let (operator, args) = map.iter().next().expect("the map has one entry");
+            let args = match args {
+                Value::Array(args) => args.as_slice(),
+                arg => std::slice::from_ref(arg),
+            };
+
+            apply(operator, args, data)
+        }
+        value => Ok(value.clone()),
+    }
+}
+
+fn apply(operator: &str, args: &[Value], data: &Value) -> Result<Value, EvaluationError> {
+    // The operators that decide which of their arguments are evaluated.
+    match operator {
+        "if" | "?:" => return evaluate_if(args, data),
+        "and" => return evaluate_and_or(args, data, false),
+        "or" => return evaluate_and_or(args, data, true),
+        "map" | "filter" | "all" | "none" | "some" | "reduce" => {
+            return evaluate_array_operation(operator, args, data)
+        }
+        _ => {}
+    }
+
+    let values = args
+        .iter()
+        .map(|arg| evaluate(arg, data))
+        .collect::<Result<Vec<_>, _>>()?;
+    let arg = |index: usize| values.get(index).unwrap_or(&Value::Null);
+
+    let result = match operator {
+        "var" => lookup(data, arg(0)).unwrap_or_else(|| arg(1).clone()),
+        "missing" => Value::Array(missing(data, &values)),
+        "missing_some" => {
+            let needed = to_number(arg(0)).unwrap_or(0.0);
+            let keys = arg(1).as_array().map(Vec::as_slice).unwrap_or_default();
+            let missing = missing(data, keys);
+            if ((keys.len() - missing.len()) as f64) < needed {
+                Value::Array(missing)
+            } else {
+                json!([])
+            }
+        }
+
+        "==" => Value::Bool(loose_eq(arg(0), arg(1))),
+        "!=" => Value::Bool(!loose_eq(arg(0), arg(1))),
+        "===" => Value::Bool(arg(0) == arg(1)),
+        "!==" => Value::Bool(arg(0) != arg(1)),
+        ">" => Value::Bool(compare(arg(0), arg(1), |a, b| a > b)),
+        ">=" => Value::Bool(compare(arg(0), arg(1), |a, b| a >= b)),
+        "<" | "<=" => {
+            let holds = |a: &Value, b: &Value| match operator {
+                "<" => compare(a, b, |a, b| a < b),
+                _ => compare(a, b, |a, b| a <= b),
+            };
+            // With three arguments, tells whether the second one is between the others.
+            Value::Bool(holds(arg(0), arg(1)) && (values.len() < 3 || holds(arg(1), arg(2))))
+        }
+        "!" => Value::Bool(!is_truthy(arg(0))),
+        "!!" => Value::Bool(is_truthy(arg(0))),
+
+        "+" => number(numbers(operator, &values)?.into_iter().sum()),
+        "*" => number(
+            numbers(operator, &values)?
+                .into_iter()
+                .fold(1.0, |product, value| product * value),
+        ),
+        "-" => match numbers(operator, &values)?.as_slice() {
+            [value] => number(-value),
+            [left, right] => number(left - right),
+            _ => return Err(invalid(operator, "expected one or two arguments")),
+        },
+        "/" | "%" => match numbers(operator, &values)?.as_slice() {
+            [_, right] if *right == 0.0 => Value::Null,
+            [left, right] if operator == "/" => number(left / right),
+            [left, right] => number(left % right),
+            _ => return Err(invalid(operator, "expected two arguments")),
+        },
+        "min" | "max" => {
+            let values = numbers(operator, &values)?;
+            let extreme = if operator == "min" {
+                values.into_iter().reduce(f64::min)
+            } else {
+                values.into_iter().reduce(f64::max)
+            };
+            extreme.map_or(Value::Null, number)
+        }
+
+        "in" => Value::Bool(match arg(1) {
+            Value::String(haystack) => haystack.contains(&stringify(arg(0))),
+            Value::Array(values) => values.iter().any(|value| loose_eq(value, arg(0))),
+            _ => false,
+        }),
+        "cat" => Value::String(values.iter().map(stringify).collect()),
+        "merge" => Value::Array(
+            values
+                .into_iter()
+                .flat_map(|value| match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                })
+                .collect(),
+        ),
+
+        operator => return Err(EvaluationError::UnknownOperator(operator.to_string())),
+    };
+
+    Ok(result)
+}
+
+fn evaluate_if(args: &[Value], data: &Value) -> Result<Value, EvaluationError> {
+    for chunk in args.chunks(2) {
+        match chunk {
+            [condition, then] => {
+                if is_truthy(&evaluate(condition, data)?) {
+                    return evaluate(then, data);
+                }
+            }
+            [otherwise] => return evaluate(otherwise, data),
+            _ => unreachable!("chunks are not empty"),
+        }
+    }
+
+    Ok(Value::Null)
+}
+
+/// Returns the first argument that decides the result, evaluating no further.
+fn evaluate_and_or(args: &[Value], data: &Value, is_or: bool) -> Result<Value, EvaluationError> {
+    let mut result = Value::Null;
+    for arg in args {
+        result = evaluate(arg, data)?;
+        if is_truthy(&result) == is_or {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Evaluates the logic given as the second argument for each item of the array,
+/// with the item as the data.
+fn evaluate_array_operation(
+    operator: &str,
+    args: &[Value],
+    data: &Value,
+) -> Result<Value, EvaluationError> {
+    let items = match args.first().map(|arg| evaluate(arg, data)).transpose()? {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    let logic = args.get(1).unwrap_or(&Value::Null);
+
+    let result = match operator {
+        "map" => Value::Array(
+            items
+                .iter()
+                .map(|item| evaluate(logic, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        "filter" => {
+            let mut filtered = Vec::new();
+            for item in items {
+                if is_truthy(&evaluate(logic, &item)?) {
+                    filtered.push(item);
+                }
+            }
+            Value::Array(filtered)
+        }
+        "all" | "none" | "some" => {
+            let mut matched = 0;
+            for item in &items {
+                if is_truthy(&evaluate(logic, item)?) {
+                    matched += 1;
+                }
+            }
+            Value::Bool(match operator {
+                "all" => !items.is_empty() && matched == items.len(),
+                "none" => matched == 0,
+                _ => matched > 0,
+            })
+        }
+        _ => {
+            let initial = args
+                .get(2)
+                .map(|arg| evaluate(arg, data))
+                .transpose()?
+                .unwrap_or(Value::Null);
+            items
+                .into_iter()
+                .try_fold(initial, |accumulator, current| {
+                    evaluate(
+                        logic,
+                        &json!({ "current": current, "accumulator": accumulator }),
+                    )
+                })?
+        }
+    };
+
+    Ok(result)
+}
+
+/// Looks up a dot-separated path in the data, `None` if any segment is missing.
+fn lookup(data: &Value, path: &Value) -> Option<Value> {
+    let path = match path {
+        Value::Null => return Some(data.clone()),
+        Value::String(path) if path.is_empty() => return Some(data.clone()),
+        Value::String(path) => path.clone(),
+        Value::Number(index) => index.to_string(),
+        _ => return None,
+    };
+
+    path.split('.')
+        .try_fold(data, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(values) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.get(index)),
+            _ => None,
+        })
+        .cloned()
+}
+
+fn missing(data: &Value, keys: &[Value]) -> Vec<Value> {
+    // The keys can also be given as a single array, e.g. the result of `merge`.
+    let keys = match keys {
+        [Value::Array(keys)] => keys.as_slice(),
+        keys => keys,
+    };
+
+    keys.iter()
+        .filter(|key| lookup(data, key).is_none_or(|value| value.is_null()))
+        .cloned()
+        .collect()
+}
+
+fn loose_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Null, Value::Null) => true,
+        (Value::Null, _) | (_, Value::Null) => false,
+        (Value::String(left), Value::String(right)) => left == right,
+        (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => {
+            left == right
+        }
+        _ => match (to_number(left), to_number(right)) {
+            (Some(left), Some(right)) => left == right,
+            _ => false,
+        },
+    }
+}
+
+fn compare(left: &Value, right: &Value, holds: impl Fn(f64, f64) -> bool) -> bool {
+    if let (Value::String(left), Value::String(right)) = (left, right) {
+        if left.parse::<f64>().is_err() || right.parse::<f64>().is_err() {
+            // Compares the strings that are not both numeric lexicographically.
+            return holds(left.cmp(right) as i8 as f64, 0.0);
+        }
+    }
+
+    match (to_number(left), to_number(right)) {
+        (Some(left), Some(right)) => holds(left, right),
+        _ => false,
+    }
+}
+
+fn to_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Null => Some(0.0),
+        Value::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+        Value::Number(number) => number.as_f64(),
+        Value::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn numbers(operator: &str, values: &[Value]) -> Result<Vec<f64>, EvaluationError> {
+    values
+        .iter()
+        .map(|value| {
+            to_number(value).ok_or_else(|| invalid(operator, &format!("{value} is not a number")))
+        })
+        .collect()
+}
+
+/// Keeps the whole numbers as integers, so that they compare equal to the integers of the data.
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(value as i64))
+    } else {
+        Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn invalid(operator: &str, reason: &str) -> EvaluationError {
+    EvaluationError::InvalidArguments {
+        operator: operator.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison_with_variables() {
+        let rule = RawRule::var("age").gte(RawRule::value(18));
+
+        assert_eq!(rule.evaluate(&json!({ "age": 18 })).unwrap(), json!(true));
+        assert_eq!(rule.evaluate(&json!({ "age": 17 })).unwrap(), json!(false));
+        // A missing variable is null, which compares as zero.
+        assert_eq!(rule.evaluate(&json!({})).unwrap(), json!(false));
+    }
+
+    #[test]
+    fn test_nested_variables_and_defaults() {
+        let data = json!({ "window": { "label": "main_0" }, "items": [10, 20] });
+
+        assert_eq!(
+            evaluate(&json!({ "var": "window.label" }), &data).unwrap(),
+            json!("main_0")
+        );
+        assert_eq!(
+            evaluate(&json!({ "var": "items.1" }), &data).unwrap(),
+            json!(20)
+        );
+        assert_eq!(
+            evaluate(&json!({ "var": ["theme", "light"] }), &data).unwrap(),
+            json!("light")
+        );
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        // The unknown operator on the right is never evaluated.
+        let logic = json!({ "or": [true, { "unknown": [] }] });
+        assert_eq!(evaluate(&logic, &json!({})).unwrap(), json!(true));
+
+        let logic = json!({ "and": [{ "var": "a" }, { "var": "b" }] });
+        assert_eq!(
+            evaluate(&logic, &json!({ "a": 1, "b": "x" })).unwrap(),
+            json!("x")
+        );
+        assert_eq!(evaluate(&logic, &json!({ "a": 0 })).unwrap(), json!(0));
+    }
+
+    #[test]
+    fn test_loose_equality() {
+        assert_eq!(
+            evaluate(&json!({ "==": [1, "1"] }), &json!({})).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            evaluate(&json!({ "===": [1, "1"] }), &json!({})).unwrap(),
+            json!(false)
+        );
+        assert_eq!(
+            evaluate(&json!({ "==": [null, 0] }), &json!({})).unwrap(),
+            json!(false)
+        );
+        assert_eq!(
+            evaluate(&json!({ "!=": ["a", "b"] }), &json!({})).unwrap(),
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_keeps_integers() {
+        let rule = (RawRule::var("x") + RawRule::var("y")).multiply(RawRule::value(2));
+
+        assert_eq!(rule.evaluate(&json!({ "x": 1, "y": 2 })).unwrap(), json!(6));
+        assert_eq!(
+            evaluate(&json!({ "/": [1, 4] }), &json!({})).unwrap(),
+            json!(0.25)
+        );
+        assert_eq!(
+            evaluate(&json!({ "/": [1, 0] }), &json!({})).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_if_chain() {
+        let logic = json!({ "if": [
+            { "<": [{ "var": "t" }, 0] }, "freezing",
+            { "<": [{ "var": "t" }, 100] }, "liquid",
+            "gas"
+        ]});
+
+        assert_eq!(
+            evaluate(&logic, &json!({ "t": -5 })).unwrap(),
+            json!("freezing")
+        );
+        assert_eq!(
+            evaluate(&logic, &json!({ "t": 20 })).unwrap(),
+            json!("liquid")
+        );
+        assert_eq!(
+            evaluate(&logic, &json!({ "t": 120 })).unwrap(),
+            json!("gas")
+        );
+    }
+
+    #[test]
+    fn test_array_operations() {
+        let data = json!({ "values": [1, 2, 3, 4] });
+
+        assert_eq!(
+            evaluate(
+                &json!({ "filter": [{ "var": "values" }, { ">": [{ "var": "" }, 2] }] }),
+                &data
+            )
+            .unwrap(),
+            json!([3, 4])
+        );
+        assert_eq!(
+            evaluate(
+                &json!({ "reduce": [
+                    { "var": "values" },
+                    { "+": [{ "var": "current" }, { "var": "accumulator" }] },
+                    0
+                ]}),
+                &data
+            )
+            .unwrap(),
+            json!(10)
+        );
+        assert_eq!(
+            evaluate(&json!({ "all": [[], { "var": "" }] }), &data).unwrap(),
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn test_in_and_missing() {
+        let data = json!({ "languages": ["en", "fr"], "name": "moss" });
+
+        assert_eq!(
+            evaluate(&json!({ "in": ["fr", { "var": "languages" }] }), &data).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            evaluate(&json!({ "in": ["os", { "var": "name" }] }), &data).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            evaluate(&json!({ "missing": ["name", "theme"] }), &data).unwrap(),
+            json!(["theme"])
+        );
+    }
+
+    #[test]
+    fn test_unknown_operator_fails() {
+        let rule = RawRule::custom("regex", vec![RawRule::value("a")]);
+
+        assert_eq!(
+            rule.evaluate(&json!({})),
+            Err(EvaluationError::UnknownOperator("regex".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_deserialized_rule_is_evaluated() {
+        let rule: RawRule = serde_json::from_value(json!({ "!": { "var": "readonly" } })).unwrap();
+
+        assert!(rule.is_satisfied(&json!({ "readonly": false })).unwrap());
+        assert!(!rule.is_satisfied(&json!({ "readonly": true })).unwrap());
+    }
+}
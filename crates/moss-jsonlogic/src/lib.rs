@@ -1,2 +1,3 @@
 pub mod checked_rule;
+pub mod evaluation;
 pub mod raw_rule;
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use arcstr::ArcStr;
 use hashbrown::{HashMap, HashSet};
 use hcl::{
@@ -32,6 +32,19 @@ pub struct OverrideBodyStmt {
     pub context: Expression,
 }
 
+#[derive(Clone, Debug)]
+pub struct MigrationDecl {
+    pub ident: ArcStr,
+    pub body: MigrationBodyStmt,
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrationBodyStmt {
+    pub to: Expression,
+    pub transform: Expression,
+    pub version: Expression,
+}
+
 #[derive(Clone, Debug)]
 pub struct ParameterDecl {
     pub ident: ArcStr,
@@ -58,6 +71,7 @@ pub struct ConfigurationBodyStmt {
     pub order: Expression,
     pub parameters: Vec<ParameterDecl>,
     pub overrides: Vec<OverrideDecl>,
+    pub migrations: Vec<MigrationDecl>,
 }
 
 #[derive(Clone, Debug)]
@@ -167,6 +181,29 @@ impl ConfigurationDecl {
             );
         }
 
+        let mut migrations = Vec::new();
+        for migration_decl in &body.migrations {
+            let version =
+                try_evaluate_to_u64(ctx, &migration_decl.body.version)?.ok_or_else(|| {
+                    anyhow!(
+                        "The migration of the parameter '{}' must specify a version",
+                        migration_decl.ident
+                    )
+                })?;
+
+            migrations.push(Arc::new(Migration {
+                contributor: self.ident().unwrap_or_default(),
+                ident: ArcStr::clone(&migration_decl.ident),
+                to: try_evaluate_to_string(ctx, &migration_decl.body.to)?.map(ArcStr::from),
+                transform: if is_null_expression(&migration_decl.body.transform) {
+                    None
+                } else {
+                    Some(migration_decl.body.transform.clone())
+                },
+                version,
+            }));
+        }
+
         Ok(ConfigurationNode {
             ident: self.ident().unwrap_or_default(),
             parent_ident: self.parent_ident(),
@@ -175,6 +212,7 @@ impl ConfigurationDecl {
             order: try_evaluate_to_u64(ctx, &body.order)?,
             parameters,
             overrides,
+            migrations,
         })
     }
 }
@@ -191,7 +229,7 @@ fn try_evaluate_to_bool(ctx: &Context, expr: &Expression) -> Result<Option<bool>
     Ok(expr.evaluate(ctx)?.as_bool())
 }
 
-#[derive(Debug, Default, StrumEnumString)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, StrumEnumString)]
 pub enum ParameterScope {
     APPLICATION,
     #[default]
@@ -210,6 +248,7 @@ pub struct ConfigurationNode {
     pub order: Option<u64>,
     pub parameters: HashMap<ArcStr, Arc<Parameter>>,
     pub overrides: HashMap<ArcStr, Arc<Override>>,
+    pub migrations: Vec<Arc<Migration>>,
 }
 
 #[derive(Debug)]
@@ -235,3 +274,37 @@ pub struct Override {
     pub value: JsonValue,
     pub context: Option<HashSet<String>>,
 }
+
+/// Describes how a value stored under an outdated key is carried over to the current one.
+#[derive(Debug)]
+pub struct Migration {
+    /// The configuration declaring the migration. Every configuration numbers the versions
+    /// of its migrations on its own.
+    pub contributor: ArcStr,
+    /// The key the value is stored under in the outdated settings.
+    pub ident: ArcStr,
+    /// The new key of the parameter. When missing, the value stays under the same key.
+    pub to: Option<ArcStr>,
+    /// The expression converting the outdated value, which is available as `value`.
+    /// It is evaluated only when the migration is applied.
+    pub transform: Option<Expression>,
+    /// The version of the contributor's settings introduced by this migration.
+    pub version: u64,
+}
+
+impl Migration {
+    pub fn target(&self) -> &ArcStr {
+        self.to.as_ref().unwrap_or(&self.ident)
+    }
+
+    pub fn apply(&self, value: &JsonValue) -> Result<JsonValue> {
+        let Some(transform) = &self.transform else {
+            return Ok(value.clone());
+        };
+
+        let mut ctx = Context::new();
+        ctx.declare_var("value", hcl::to_value(value)?);
+
+        Ok(serde_json::to_value(transform.evaluate(&ctx)?)?)
+    }
+}
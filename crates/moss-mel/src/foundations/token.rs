@@ -4,6 +4,7 @@ pub(crate) const CONFIGURATION_LIT: &'static str = "configuration";
 pub(crate) const PARAMETER_LIT: &'static str = "parameter";
 pub(crate) const OVERRIDE_LIT: &'static str = "override";
 pub(crate) const LOCALS_LIT: &'static str = "locals";
+pub(crate) const MIGRATE_LIT: &str = "migrate";
 
 // FIXME: We should use the same token for declaring and accessing local variables
 pub(crate) const EXTEND_LIT: &'static str = "extends";
@@ -16,4 +17,5 @@ pub(crate) static RESERVED_WORDS: Set<&'static str> = phf_set! {
     "locals",
     "local",
     "extends",
+    "migrate",
 };
@@ -6,7 +6,9 @@ use hcl::{
     Block, Body, Expression, Object, ObjectKey,
 };
 
-use crate::foundations::configuration::{OverrideBodyStmt, ParameterBodyStmt};
+use crate::foundations::configuration::{
+    MigrationBodyStmt, MigrationDecl, OverrideBodyStmt, ParameterBodyStmt,
+};
 use crate::foundations::{
     configuration::{ConfigurationBodyStmt, ConfigurationDecl, OverrideDecl, ParameterDecl},
     scope::ModuleScope,
@@ -101,6 +103,7 @@ fn parse_configuration_body(block: Block) -> Result<ConfigurationBodyStmt> {
         order: Expression::Null,
         parameters: Vec::new(),
         overrides: Vec::new(),
+        migrations: Vec::new(),
     };
 
     for attribute in block.body.clone().into_attributes() {
@@ -198,6 +201,44 @@ fn parse_configuration_body(block: Block) -> Result<ConfigurationBodyStmt> {
 
                 result.parameters.push(parameter_decl);
             }
+
+            MIGRATE_LIT => {
+                let ident = if let Some(label) = block
+                    .labels()
+                    .first()
+                    .map(|label| ArcStr::from(label.as_str()))
+                {
+                    label
+                } else {
+                    // TODO: Add logging for encountering an unknown parameter
+                    continue;
+                };
+
+                let mut migration_decl = MigrationDecl {
+                    ident,
+                    body: MigrationBodyStmt {
+                        to: Expression::Null,
+                        transform: Expression::Null,
+                        version: Expression::Null,
+                    },
+                };
+
+                for attribute in block.body.into_attributes() {
+                    match attribute.key() {
+                        "to" => migration_decl.body.to = attribute.expr,
+                        "transform" => migration_decl.body.transform = attribute.expr,
+                        "version" => migration_decl.body.version = attribute.expr,
+                        key => {
+                            return Err(anyhow!(
+                                "Unknown attribute '{key}' in migration {}",
+                                migration_decl.ident
+                            ))
+                        }
+                    }
+                }
+
+                result.migrations.push(migration_decl);
+            }
             _ => {}
         }
     }
@@ -330,6 +371,55 @@ mod tests {
         // dbg!(resolved);
     }
 
+    #[test]
+    fn test_migration() {
+        let input = r#"
+        configuration "moss.kernel.editor" {
+            migrate "editor.fontSizePx" {
+                to = "editor.fontSize"
+                transform = value < 10 ? 10 : value
+                version = 2
+            }
+        }
+        "#;
+
+        let mut scope = ModuleScope::new();
+        parse_module_file(input, &mut scope).unwrap();
+        let node = scope
+            .evaluate_with_context(&mut hcl::eval::Context::new())
+            .unwrap()
+            .into_values()
+            .pop()
+            .unwrap();
+
+        let migration = &node.migrations[0];
+        assert_eq!(migration.target(), "editor.fontSize");
+        assert_eq!(migration.version, 2);
+        assert_eq!(
+            migration.apply(&serde_json::json!(8)).unwrap(),
+            serde_json::json!(10)
+        );
+        assert_eq!(
+            migration.apply(&serde_json::json!(12)).unwrap(),
+            serde_json::json!(12)
+        );
+    }
+
+    #[test]
+    fn test_migration_unknown_attribute() {
+        let input = r#"
+        configuration "moss.kernel.editor" {
+            migrate "editor.fontSizePx" {
+                to = "editor.fontSize"
+                from = "editor.size"
+            }
+        }
+        "#;
+
+        let mut scope = ModuleScope::new();
+        assert!(parse_module_file(input, &mut scope).is_err());
+    }
+
     #[test]
     fn test_extend_normal() {
         let input = r#"
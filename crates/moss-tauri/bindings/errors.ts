@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The error every Tauri command fails with, so that the frontend can tell the errors apart
+ * by their code rather than by their message.
+ */
+export type TauriError = {
+  /**
+   * Identifies the kind of the error, e.g. `command.argumentMissing`.
+   */
+  code: string;
+  message: string;
+  /**
+   * The key of the translated message, in the `errors` namespace.
+   */
+  localizedKey?: string;
+  /**
+   * Data specific to the kind of the error, such as the name of the missing argument.
+   */
+  details?: unknown;
+  /**
+   * The messages of the errors that caused this one, the closest first.
+   */
+  causes: Array<string>;
+};
@@ -0,0 +1 @@
+export * from "./bindings/errors";
@@ -1,18 +1,113 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
 
-#[derive(Debug, Serialize)]
-#[serde(transparent)]
-pub struct TauriError(String);
+/// The code of the errors that do not describe themselves with an [`ErrorCode`].
+pub const INTERNAL_ERROR_CODE: &str = "internal";
+
+/// The error every Tauri command fails with, so that the frontend can tell the errors apart
+/// by their code rather than by their message.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "errors.ts")]
+pub struct TauriError {
+    /// Identifies the kind of the error, e.g. `command.argumentMissing`.
+    pub code: String,
+    pub message: String,
+    /// The key of the translated message, in the `errors` namespace.
+    #[ts(optional)]
+    pub localized_key: Option<String>,
+    /// Data specific to the kind of the error, such as the name of the missing argument.
+    #[ts(optional, type = "unknown")]
+    pub details: Option<Box<Value>>,
+    /// The messages of the errors that caused this one, the closest first.
+    pub causes: Vec<String>,
+}
+
+impl TauriError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            localized_key: None,
+            details: None,
+            causes: Vec::new(),
+        }
+    }
+
+    pub fn with_localized_key(mut self, localized_key: impl Into<String>) -> Self {
+        self.localized_key = Some(localized_key.into());
+        self
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(Box::new(details));
+        self
+    }
+
+    /// Records the sources of the error as the causes.
+    pub fn with_causes(mut self, error: &dyn std::error::Error) -> Self {
+        let mut source = error.source();
+        while let Some(cause) = source {
+            self.causes.push(cause.to_string());
+            source = cause.source();
+        }
+
+        self
+    }
+}
+
+/// An error that describes itself to the frontend.
+pub trait ErrorCode: std::error::Error {
+    fn code(&self) -> &'static str;
+
+    fn localized_key(&self) -> Option<String> {
+        Some(self.code().to_string())
+    }
+
+    fn details(&self) -> Option<Value> {
+        None
+    }
+}
+
+impl<E: ErrorCode> From<E> for TauriError {
+    fn from(e: E) -> Self {
+        let mut error = TauriError::new(e.code(), e.to_string()).with_causes(&e);
+        error.localized_key = e.localized_key();
+        error.details = e.details().map(Box::new);
+
+        error
+    }
+}
 
 impl std::fmt::Display for TauriError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.message)
     }
 }
 
+impl std::error::Error for TauriError {}
+
 impl From<anyhow::Error> for TauriError {
     fn from(e: anyhow::Error) -> Self {
-        TauriError(e.to_string())
+        // The file system errors are common enough to be told apart wherever they come from.
+        let io_error = e
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<std::io::Error>());
+        let code = match io_error.map(|err| err.kind()) {
+            Some(std::io::ErrorKind::NotFound) => "io.notFound",
+            Some(std::io::ErrorKind::PermissionDenied) => "io.permissionDenied",
+            Some(_) => "io.failed",
+            None => INTERNAL_ERROR_CODE,
+        };
+
+        let mut error = TauriError::new(code, e.to_string());
+        error.causes = e.chain().skip(1).map(|cause| cause.to_string()).collect();
+        if code != INTERNAL_ERROR_CODE {
+            error.localized_key = Some(code.to_string());
+        }
+
+        error
     }
 }
 
@@ -87,6 +87,16 @@ impl LocalizedString {
             description: description.map(Into::into),
         }
     }
+
+    /// Returns the localization key.
+    pub fn key(&self) -> &ReadOnlyStr {
+        &self.key
+    }
+
+    /// Returns the original text, shown where the string cannot be localized.
+    pub fn origin(&self) -> &BStringForFrontend {
+        &self.origin
+    }
 }
 
 impl Serialize for LocalizedString {
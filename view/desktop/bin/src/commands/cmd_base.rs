@@ -1,6 +1,16 @@
-use moss_desktop::models::actions::MenuItem;
+use moss_desktop::{
+    app::manager::AppManager,
+    menus::{self, MenuError},
+    models::{
+        actions::{MenuItem, ResolvedMenu},
+        context_keys::ContextScope,
+    },
+    services::context_key_service::ContextKeyService,
+};
+use moss_tauri::TauriResult;
 use moss_text::ReadOnlyStr;
-use tauri::State;
+use tauri::{State, Window};
+use tracing::instrument;
 
 use crate::AppStateManager;
 
@@ -8,11 +18,32 @@ use crate::AppStateManager;
 pub fn get_menu_items_by_namespace(
     state: State<'_, AppStateManager>,
     namespace: ReadOnlyStr,
-) -> Result<Vec<MenuItem>, String> {
+) -> TauriResult<Vec<MenuItem>> {
     if let Some(menu_items_ref) = state.contributions.menus.get(&namespace) {
         let menu_items = menu_items_ref.clone();
         Ok(menu_items)
     } else {
-        Err(format!("Namespace '{}' not found", namespace))
+        Err(MenuError::NamespaceNotFound { namespace }.into())
     }
 }
+
+/// Resolves the menu of the namespace against the context of the scope, the window
+/// the command is called from by default.
+#[tauri::command]
+#[instrument(level = "trace", skip(state, app_manager), fields(window = window.label()))]
+pub fn resolve_menu(
+    state: State<'_, AppStateManager>,
+    app_manager: State<'_, AppManager>,
+    window: Window,
+    namespace: ReadOnlyStr,
+    scope: Option<ContextScope>,
+) -> TauriResult<ResolvedMenu> {
+    let scope = scope.unwrap_or_else(|| ContextScope::window(window.label()));
+    let context = app_manager.service::<ContextKeyService>()?.context(&scope);
+
+    Ok(menus::resolve_menu(
+        &state.contributions.menus,
+        &namespace,
+        &context,
+    )?)
+}
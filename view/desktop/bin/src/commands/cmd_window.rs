@@ -1,45 +1,376 @@
-use anyhow::Result;
 use hashbrown::HashMap;
+use moss_configuration::search::collect_localized_text;
 use moss_desktop::{
-    app::manager::AppManager,
-    command::CommandContext,
-    models::application::{AppState, Defaults, LocaleDescriptor, Preferences, ThemeDescriptor},
-    services::{locale_service::LocaleService, theme_service::ThemeService},
+    app::{lifecycle::LifecyclePhase, manager::AppManager},
+    command::{CommandContext, CommandError, CommandFuture},
+    models::{
+        application::{AppState, Defaults, LocaleDescriptor, Preferences, ThemeDescriptor},
+        commands::{CommandCall, CommandDescriptor, CommandHistoryDescriptor, CommandLogEntry},
+        context_keys::{ContextKeyDescriptor, ContextScope},
+        keybindings::{KeyEvent, KeybindingConflict, KeybindingDescriptor, KeybindingResolution},
+        services::ServiceDescriptor,
+        settings::{SettingsImportPreview, SettingsSearchGroup},
+    },
+    services::{
+        addon_service::AddonService, configuration_service::ConfigurationService,
+        context_key_service::ContextKeyService, locale_service::LocaleService,
+        theme_service::ThemeService,
+    },
+    settings_bundle::{self, PreparedImport},
 };
 use moss_tauri::TauriResult;
-use moss_text::{quote, ReadOnlyStr};
+use moss_text::ReadOnlyStr;
 use serde_json::Value;
+use std::path::PathBuf;
 use tracing::instrument;
 
-use crate::{create_child_window, menu, AppStateManager};
-use tauri::{AppHandle, State, Window};
+use crate::{create_child_window, enter_phase, AppStateManager};
+use tauri::{AppHandle, Manager, State, Window};
+
+const SETTINGS_TRANSLATIONS_NAMESPACE: &str = "settings";
 
 // According to https://docs.rs/tauri/2.1.1/tauri/webview/struct.WebviewWindowBuilder.html
 // We should call WebviewWindowBuilder from async commands
 #[tauri::command]
 #[instrument(level = "trace", skip(app_handle))]
 pub async fn create_new_window(app_handle: AppHandle) -> TauriResult<()> {
-    let webview_window = create_child_window(&app_handle, "/")?;
-    webview_window.on_menu_event(move |window, event| menu::handle_event(window, &event));
+    create_child_window(&app_handle, "/")?;
     Ok(())
 }
 
-#[tauri::command]
-#[instrument(level = "trace", skip(app_handle, app_state), fields(window = window.label()))]
-pub fn execute_command(
+/// Opens a new window, the `workbench.newWindow` command.
+pub fn new_window(ctx: CommandContext) -> CommandFuture {
+    Box::pin(async move {
+        create_new_window(ctx.app_handle).await?;
+        Ok(Value::Null)
+    })
+}
+
+/// Closes the window the command is called from, the `workbench.closeWindow` command.
+pub fn close_window(ctx: CommandContext) -> CommandFuture {
+    Box::pin(async move {
+        ctx.window.close().map_err(anyhow::Error::from)?;
+        Ok(Value::Null)
+    })
+}
+
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_handle), fields(window = window.label()))]
+pub async fn execute_command(
+    app_handle: AppHandle,
+    window: Window,
+    cmd: ReadOnlyStr,
+    args: HashMap<String, Value>,
+    invocation_id: Option<String>,
+) -> TauriResult<Value> {
+    dispatch_command(app_handle, window, cmd, args, invocation_id).await
+}
+
+/// Runs the command in the window, once its enablement holds in the context of the window,
+/// and records it in the history. Every way of executing a command, from the frontend or
+/// from the native menus, goes here.
+pub async fn dispatch_command(
+    app_handle: AppHandle,
+    window: Window,
+    cmd: ReadOnlyStr,
+    args: HashMap<String, Value>,
+    invocation_id: Option<String>,
+) -> TauriResult<Value> {
+    let label = window.label().to_string();
+    let call = CommandCall {
+        command: cmd.to_string(),
+        args: args.clone().into_iter().collect(),
+    };
+    let (value, inverse) =
+        run_command(app_handle.clone(), window, cmd, args, invocation_id).await?;
+    app_handle
+        .state::<AppStateManager>()
+        .history
+        .record(&label, call, inverse);
+
+    Ok(value)
+}
+
+/// Runs the command without recording it, returning its result and the command that
+/// reverts it, if its handler set one.
+async fn run_command(
     app_handle: AppHandle,
-    app_state: State<'_, AppStateManager>,
     window: Window,
     cmd: ReadOnlyStr,
     args: HashMap<String, Value>,
-) -> Result<Value, String> {
-    if let Some(command_handler) = app_state.get_command(&cmd) {
-        command_handler(CommandContext::new(app_handle, window, args), &app_state)
-    } else {
-        Err(format!("command with id {} is not found", quote!(cmd)))
+    invocation_id: Option<String>,
+) -> TauriResult<(Value, Option<CommandCall>)> {
+    let app_state = app_handle.state::<AppStateManager>();
+    let Some(command) = app_state.get_command(&cmd) else {
+        return Err(CommandError::NotFound { command: cmd }.into());
+    };
+    let context = app_handle
+        .state::<AppManager>()
+        .service::<ContextKeyService>()?
+        .context(&ContextScope::window(window.label()));
+    if !command.is_enabled(&cmd, &context) {
+        return Err(CommandError::Disabled { command: cmd }.into());
+    }
+
+    let invocation = app_state
+        .invocations
+        .begin(cmd.clone(), window.label(), invocation_id)?;
+    let token = invocation.token.clone();
+    let future = (command.handler)(CommandContext::new(
+        app_handle.clone(),
+        window,
+        args,
+        invocation.clone(),
+    ));
+
+    // Dropping the future stops the handlers that do not watch for the cancellation themselves.
+    let result = tokio::select! {
+        result = future => result,
+        _ = token.cancelled() => Err(CommandError::Cancelled { command: cmd }.into()),
+    };
+    app_state.invocations.finish(&invocation);
+
+    result.map(|value| (value, invocation.take_inverse()))
+}
+
+/// Runs the command of the call, the way it is recorded in the history.
+async fn run_call(
+    app_handle: AppHandle,
+    window: Window,
+    call: &CommandCall,
+) -> TauriResult<Option<CommandCall>> {
+    let args = call.args.clone().into_iter().collect();
+    let (_, inverse) = run_command(
+        app_handle,
+        window,
+        ReadOnlyStr::from(call.command.as_str()),
+        args,
+        None,
+    )
+    .await?;
+
+    Ok(inverse)
+}
+
+/// Undoes the command executed last in the window by running its inverse. Returns the
+/// undone command, none if there is nothing to undo.
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_handle), fields(window = window.label()))]
+pub async fn undo_command(
+    app_handle: AppHandle,
+    window: Window,
+) -> TauriResult<Option<CommandCall>> {
+    let label = window.label().to_string();
+    let app_state = app_handle.state::<AppStateManager>();
+    let _step = app_state.history.lock_steps(&label).await;
+    let Some(entry) = app_state.history.take_undo(&label) else {
+        return Ok(None);
+    };
+
+    match run_call(app_handle.clone(), window, &entry.inverse).await {
+        Ok(_) => {
+            let call = entry.call.clone();
+            app_state.history.undone(&label, entry);
+            Ok(Some(call))
+        }
+        Err(err) => {
+            app_state.history.restore_undo(&label, entry);
+            Err(err)
+        }
+    }
+}
+
+/// Executes again the command undone last in the window. Returns the redone command, none
+/// if there is nothing to redo.
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_handle), fields(window = window.label()))]
+pub async fn redo_command(
+    app_handle: AppHandle,
+    window: Window,
+) -> TauriResult<Option<CommandCall>> {
+    let label = window.label().to_string();
+    let app_state = app_handle.state::<AppStateManager>();
+    let _step = app_state.history.lock_steps(&label).await;
+    let Some(mut entry) = app_state.history.take_redo(&label) else {
+        return Ok(None);
+    };
+
+    match run_call(app_handle.clone(), window, &entry.call).await {
+        Ok(inverse) => {
+            // The state the command is executed in again may differ, so may its inverse.
+            if let Some(inverse) = inverse {
+                entry.inverse = inverse;
+            }
+            let call = entry.call.clone();
+            app_state.history.redone(&label, entry);
+            Ok(Some(call))
+        }
+        Err(err) => {
+            app_state.history.restore_redo(&label, entry);
+            Err(err)
+        }
     }
 }
 
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state), fields(window = window.label()))]
+pub fn get_command_history(
+    app_state: State<'_, AppStateManager>,
+    window: Window,
+) -> CommandHistoryDescriptor {
+    app_state.history.describe(window.label())
+}
+
+/// The executed commands, the oldest first, of the given window or of all of them.
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state))]
+pub fn get_command_log(
+    app_state: State<'_, AppStateManager>,
+    window: Option<String>,
+) -> Vec<CommandLogEntry> {
+    app_state.history.log(window.as_deref())
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state))]
+pub fn get_recent_commands(app_state: State<'_, AppStateManager>) -> Vec<String> {
+    app_state.history.recent_commands()
+}
+
+/// Executes the commands one after another in the window, e.g. the entries of the log.
+/// Stops at the first command that fails.
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_handle, calls), fields(window = window.label()))]
+pub async fn replay_commands(
+    app_handle: AppHandle,
+    window: Window,
+    calls: Vec<CommandCall>,
+) -> TauriResult<Vec<Value>> {
+    let mut results = Vec::with_capacity(calls.len());
+    for call in calls {
+        let args = call.args.into_iter().collect();
+        let result = dispatch_command(
+            app_handle.clone(),
+            window.clone(),
+            ReadOnlyStr::from(call.command),
+            args,
+            None,
+        )
+        .await?;
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state, app_manager), fields(window = window.label()))]
+pub fn list_commands(
+    app_state: State<'_, AppStateManager>,
+    app_manager: State<'_, AppManager>,
+    window: Window,
+) -> TauriResult<Vec<CommandDescriptor>> {
+    let context = app_manager
+        .service::<ContextKeyService>()?
+        .context(&ContextScope::window(window.label()));
+
+    Ok(app_state.list_commands(&context))
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state, app_manager), fields(window = window.label()))]
+pub fn resolve_keybinding(
+    app_state: State<'_, AppStateManager>,
+    app_manager: State<'_, AppManager>,
+    window: Window,
+    event: KeyEvent,
+) -> TauriResult<KeybindingResolution> {
+    let context = app_manager
+        .service::<ContextKeyService>()?
+        .context(&ContextScope::window(window.label()));
+
+    Ok(app_state
+        .contributions
+        .keybindings
+        .resolve(window.label(), &event, &context))
+}
+
+/// The context of the scope the `when` rules are evaluated against, the calling window
+/// by default.
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager), fields(window = window.label()))]
+pub fn get_context(
+    app_manager: State<'_, AppManager>,
+    window: Window,
+    scope: Option<ContextScope>,
+) -> TauriResult<Value> {
+    let scope = scope.unwrap_or_else(|| ContextScope::window(window.label()));
+
+    Ok(app_manager.service::<ContextKeyService>()?.context(&scope))
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager))]
+pub fn get_context_keys(
+    app_manager: State<'_, AppManager>,
+) -> TauriResult<Vec<ContextKeyDescriptor>> {
+    Ok(app_manager.service::<ContextKeyService>()?.describe())
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager), fields(window = window.label()))]
+pub fn set_context_key(
+    app_manager: State<'_, AppManager>,
+    window: Window,
+    key: String,
+    value: Value,
+    scope: Option<ContextScope>,
+) -> TauriResult<()> {
+    let scope = scope.unwrap_or_else(|| ContextScope::window(window.label()));
+
+    Ok(app_manager
+        .service::<ContextKeyService>()?
+        .set_value(&key, scope, value)?)
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager), fields(window = window.label()))]
+pub fn reset_context_key(
+    app_manager: State<'_, AppManager>,
+    window: Window,
+    key: String,
+    scope: Option<ContextScope>,
+) -> TauriResult<()> {
+    let scope = scope.unwrap_or_else(|| ContextScope::window(window.label()));
+    app_manager
+        .service::<ContextKeyService>()?
+        .reset(&key, scope);
+
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state))]
+pub fn get_keybindings(app_state: State<'_, AppStateManager>) -> Vec<KeybindingDescriptor> {
+    app_state.contributions.keybindings.describe()
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state))]
+pub fn get_keybinding_conflicts(app_state: State<'_, AppStateManager>) -> Vec<KeybindingConflict> {
+    app_state.contributions.keybindings.conflicts()
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_state, window))]
+pub fn cancel_command(
+    app_state: State<'_, AppStateManager>,
+    window: Window,
+    invocation_id: String,
+) -> bool {
+    app_state.invocations.cancel(&invocation_id, window.label())
+}
+
 #[tauri::command(async)]
 #[instrument(level = "trace", skip(app_manager))]
 pub async fn get_color_theme(
@@ -65,16 +396,22 @@ pub async fn get_translations(
 }
 
 #[tauri::command(async)]
-#[instrument(level = "trace", skip(state_manager))]
-pub fn get_state(state_manager: State<'_, AppStateManager>) -> Result<AppState, String> {
+#[instrument(level = "trace", skip(app_manager, state_manager))]
+pub fn get_state(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+) -> TauriResult<AppState> {
     Ok(AppState {
         preferences: Preferences {
             theme: state_manager.preferences.theme.read().clone(),
             locale: state_manager.preferences.locale.read().clone(),
         },
         defaults: Defaults {
-            theme: state_manager.defaults.theme.clone(),
-            locale: state_manager.defaults.locale.clone(),
+            theme: app_manager.service::<ThemeService>()?.default_color_theme(),
+            locale: app_manager
+                .service::<LocaleService>()?
+                .default_locale()
+                .clone(),
         },
     })
 }
@@ -98,3 +435,152 @@ pub async fn get_locales(app_manager: State<'_, AppManager>) -> TauriResult<Vec<
 
     Ok(locale_service.get_locales().clone().into_iter().collect())
 }
+
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager))]
+pub async fn get_configuration_schema(app_manager: State<'_, AppManager>) -> TauriResult<Value> {
+    let configuration_service = app_manager.service::<ConfigurationService>()?;
+
+    Ok(configuration_service.schema())
+}
+
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager, state_manager))]
+pub async fn search_settings(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    query: String,
+) -> TauriResult<Vec<SettingsSearchGroup>> {
+    let configuration_service = app_manager.service::<ConfigurationService>()?;
+    let locale_service = app_manager.service::<LocaleService>()?;
+
+    let language = state_manager
+        .preferences
+        .locale
+        .read()
+        .as_ref()
+        .unwrap_or(locale_service.default_locale())
+        .code
+        .clone();
+    let localized = match locale_service
+        .get_translations(&language, SETTINGS_TRANSLATIONS_NAMESPACE)
+        .await
+    {
+        Ok(translations) => Some(collect_localized_text(&translations)),
+        Err(err) => {
+            trace!("Searching the settings without the localized text: {err:#}");
+            None
+        }
+    };
+
+    Ok(configuration_service.search(&query, localized.as_ref()))
+}
+
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager, state_manager))]
+pub async fn export_settings_bundle(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    path: PathBuf,
+) -> TauriResult<()> {
+    let configuration_service = app_manager.service::<ConfigurationService>()?;
+    let bundle = settings_bundle::export_bundle(&configuration_service, &state_manager);
+
+    Ok(settings_bundle::write_bundle(&path, &bundle)?)
+}
+
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager, state_manager, window))]
+pub async fn preview_settings_import(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    window: Window,
+    path: PathBuf,
+) -> TauriResult<SettingsImportPreview> {
+    let configuration_service = app_manager.service::<ConfigurationService>()?;
+    let bundle = settings_bundle::read_bundle(&path)?;
+    let prepared = PreparedImport::new(&configuration_service, &state_manager, bundle);
+
+    Ok(state_manager
+        .settings_imports
+        .insert(window.label(), prepared))
+}
+
+/// Applies the import previewed in the window, rather than reading the bundle again.
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager, state_manager, window))]
+pub async fn import_settings_bundle(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    window: Window,
+    token: String,
+) -> TauriResult<SettingsImportPreview> {
+    let configuration_service = app_manager.service::<ConfigurationService>()?;
+    let prepared = state_manager
+        .settings_imports
+        .take(window.label(), &token)?;
+
+    Ok(prepared.apply(&configuration_service, &state_manager)?)
+}
+
+/// Loads the addon in the directory, replacing its contributions if it is loaded already.
+/// Returns the id of the addon.
+///
+/// The addon is loaded until the application exits, the path is not remembered. Its settings
+/// are not registered either, as the configuration is read once at startup: an addon that
+/// declares settings is to be placed in the installed addons directory, where it is loaded
+/// at every start along with its configuration.
+#[tauri::command(async)]
+#[instrument(level = "trace", skip(app_manager, state_manager))]
+pub async fn install_addon(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    path: PathBuf,
+) -> TauriResult<String> {
+    let addon_service = app_manager.service::<AddonService>()?;
+    let locale_service = app_manager.service::<LocaleService>()?;
+
+    Ok(addon_service.enable_addon(&state_manager, &locale_service, &path)?)
+}
+
+/// Removes the contributions of the addon, returns `false` if it is not loaded.
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager, state_manager))]
+pub fn disable_addon(
+    app_manager: State<'_, AppManager>,
+    state_manager: State<'_, AppStateManager>,
+    id: String,
+) -> TauriResult<bool> {
+    let addon_service = app_manager.service::<AddonService>()?;
+    let locale_service = app_manager.service::<LocaleService>()?;
+
+    Ok(addon_service.disable_addon(&state_manager, &locale_service, &id))
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager))]
+pub fn get_services(app_manager: State<'_, AppManager>) -> Vec<ServiceDescriptor> {
+    app_manager.describe_services()
+}
+
+#[tauri::command]
+#[instrument(level = "trace", skip(app_manager))]
+pub fn get_services_report(app_manager: State<'_, AppManager>) -> String {
+    app_manager.services_report()
+}
+
+/// Signals that the window has restored its state. The first window to do so moves
+/// the application to the `Restored` phase.
+#[tauri::command]
+#[instrument(level = "trace", skip(app_handle))]
+pub fn notify_window_restored(app_handle: AppHandle) {
+    enter_phase(&app_handle, LifecyclePhase::Restored);
+}
+
+/// Signals that the window has been idle since it restored its state. The first window
+/// to do so moves the application to the `Settled` phase.
+#[tauri::command]
+#[instrument(level = "trace", skip(app_handle))]
+pub fn notify_window_idle(app_handle: AppHandle) {
+    enter_phase(&app_handle, LifecyclePhase::Settled);
+}
@@ -10,6 +10,14 @@ pub const MIN_WINDOW_HEIGHT: f64 = 600.0;
 pub const DEFAULT_WINDOW_WIDTH: f64 = 1160.0;
 pub const DEFAULT_WINDOW_HEIGHT: f64 = 720.0;
 
+/// How long each service may take to dispose on application exit, in milliseconds.
+pub static SERVICE_DISPOSAL_TIMEOUT_MS: Lazy<u64> =
+    lazy_env_parse!("MOSS_SERVICE_DISPOSAL_TIMEOUT_MS", u64, 5000);
+
+/// Whether the state of the services is printed to the standard error once the application
+/// settles and on exit.
+pub static DUMP_SERVICES: Lazy<bool> = lazy_env_parse!("MOSS_DUMP_SERVICES", bool, false);
+
 pub const RUNTIME_MAX_BLOCKING_THREADS: Lazy<usize> =
     lazy_env_parse!("MOSS_RUNTIME_MAX_BLOCKING_THREADS", usize, 512);
 
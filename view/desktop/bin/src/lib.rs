@@ -6,14 +6,18 @@ mod plugins;
 mod utl;
 mod window;
 
-use anyhow::Result;
-use moss_configuration::default_configuration::DefaultConfiguration;
+use anyhow::{anyhow, Result};
+use dirs::home_dir;
+use moss_configuration::{
+    default_configuration::DefaultConfiguration, file_configuration::FileConfiguration,
+};
 use moss_desktop::services::configuration_service::ConfigurationService;
-use moss_extension_point::registry::{self, ConfigurationRegistry, Registry};
+use moss_extension_point::registry::{self, ConfigurationRegistry, Registry, ValueProviderInfo};
 use rand::random;
-use tauri::{AppHandle, Manager, RunEvent, WebviewWindow, WindowEvent};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, WebviewWindow, WindowEvent};
 use tauri_plugin_os;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::layer::SubscriberExt;
@@ -23,13 +27,22 @@ use window::{create_window, CreateWindowInput};
 use moss_addon::{BUILTIN_ADDONS_DIR, INSTALLED_ADDONS_DIR};
 use moss_desktop::app::manager::AppManager;
 use moss_desktop::app::state::AppStateManager;
-use moss_desktop::services::addon_service::AddonService;
+use moss_desktop::command::{CommandCallback, CommandDecl};
+use moss_desktop::contribution_registry::{ContributionSet, CONTRIBUTIONS_CHANGED_EVENT};
+use moss_desktop::menus;
+use moss_desktop::models::constants::context_key::CONTEXT_KEY_THEME;
+use moss_desktop::models::context_keys::ContextScope;
+use moss_desktop::models::contributions::ContributionOwner;
+use moss_desktop::services::addon_service::{read_addon_manifest, AddonService};
+use moss_desktop::services::context_key_service::ContextKeyService;
 use moss_desktop::services::theme_service::ThemeService;
 use moss_desktop::services::window_service::WindowService;
 use moss_desktop::{
-    app::instantiation::InstantiationType, services::lifecycle_service::LifecycleService,
+    app::{instantiation::InstantiationType, lifecycle::LifecyclePhase},
+    services::lifecycle_service::LifecycleService,
 };
 use moss_mel::loader::Loader;
+use moss_text::localize;
 
 use crate::commands::*;
 use crate::plugins::*;
@@ -39,6 +52,67 @@ use moss_desktop::services::locale_service::LocaleService;
 #[macro_use]
 extern crate tracing;
 
+/// Registers the configuration declared by the built-in modules, then the one declared by the
+/// addons, whose overrides of the protected parameters are rejected.
+fn load_configuration_registry() -> Result<ConfigurationRegistry> {
+    let mut configuration_registry = ConfigurationRegistry::default();
+
+    let mut loader = Loader::new();
+    loader.load(
+        std::env::var("CARGO_WORKSPACE_ROOT_DIR")?.into(),
+        registry::take(),
+    )?;
+    for result in loader.resolve() {
+        configuration_registry.register(result?.into_values());
+    }
+
+    for addons_dir in [&*BUILTIN_ADDONS_DIR, &*INSTALLED_ADDONS_DIR] {
+        let Ok(read_dir) = std::fs::read_dir(addons_dir) else {
+            continue;
+        };
+
+        for addon_dir in read_dir.flatten().map(|entry| entry.path()) {
+            if !addon_dir.is_dir() {
+                continue;
+            }
+
+            let manifest = match read_addon_manifest(&addon_dir) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    warn!("Skipping the configuration of the addon in {addon_dir:?}: {err}");
+                    continue;
+                }
+            };
+
+            // A malformed configuration of an addon leaves it out, not the application.
+            let mut loader = Loader::new();
+            if let Err(err) = loader.load(addon_dir.clone(), vec![PathBuf::new()]) {
+                warn!(
+                    "Skipping the configuration of the addon {}: {err}",
+                    manifest.addon.id
+                );
+                continue;
+            }
+            for result in loader.resolve() {
+                match result {
+                    Ok(resolved) => configuration_registry.register_from_provider(
+                        ValueProviderInfo {
+                            id: manifest.addon.id.clone(),
+                        },
+                        resolved.into_values(),
+                    ),
+                    Err(err) => warn!(
+                        "Skipping a configuration of the addon {}: {err}",
+                        manifest.addon.id
+                    ),
+                }
+            }
+        }
+    }
+
+    Ok(configuration_registry)
+}
+
 pub fn run() {
     #[allow(unused_mut)]
     let mut builder = tauri::Builder::default()
@@ -80,82 +154,120 @@ pub fn run() {
             let app_handle = app.app_handle();
 
             let app_state = AppStateManager::new();
+            // The window commands are contributed by the application, which owns the windows.
+            app_state.contributions.add(
+                ContributionOwner::builtin(module_path!()),
+                ContributionSet::default()
+                    .with_command(
+                        CommandDecl::new(
+                            "workbench.newWindow",
+                            CommandCallback::Async(cmd_window::new_window),
+                        )
+                        .with_title(localize!("workbench.newWindow.title", "New Window"))
+                        .with_category(localize!("workbench.category.window", "Window")),
+                    )
+                    .with_command(
+                        CommandDecl::new(
+                            "workbench.closeWindow",
+                            CommandCallback::Async(cmd_window::close_window),
+                        )
+                        .with_title(localize!("workbench.closeWindow.title", "Close Window"))
+                        .with_category(localize!("workbench.category.window", "Window")),
+                    ),
+            );
+            if let Err(err) = get_user_keybindings_file().and_then(|path| {
+                app_state
+                    .contributions
+                    .keybindings
+                    .load_user_keybindings(&path)
+            }) {
+                error!("Failed to load the user keybindings: {err:#}");
+            }
+            let app_cache = Arc::clone(&app_state.cache);
+            let themes = Arc::clone(&app_state.contributions.themes);
+            let default_theme = app_state.defaults.theme.clone();
+            let locales = Arc::clone(&app_state.contributions.locales);
+            let default_locale = app_state.defaults.locale.clone();
             app_handle.manage(app_state);
 
             // ---------------------------------------
             // TODO: Will be moved to a more appropriate place in the future.
 
-            let mut loader = Loader::new();
-            loader
-                .load(
-                    std::env::var("CARGO_WORKSPACE_ROOT_DIR").unwrap().into(),
-                    registry::take(),
-                )
-                .unwrap();
-
-            let configuration_registry = {
-                let mut registry = ConfigurationRegistry::default();
-                for result in loader.resolve() {
-                    registry.register(result.unwrap().into_values());
-                }
-
-                registry
-            };
-
-            let registry = Registry::new(configuration_registry);
+            let registry = Registry::new(load_configuration_registry()?);
+            let configuration_registry = registry.configuration_registry();
             let default_configurations =
-                DefaultConfiguration::new(registry.configuration_registry());
+                DefaultConfiguration::new(Arc::clone(&configuration_registry));
+            let user_configuration = get_user_settings_file()
+                .and_then(|path| FileConfiguration::load(path, &configuration_registry, true))
+                .unwrap_or_else(|err| {
+                    error!("Failed to load the user settings: {err:#}");
+                    FileConfiguration::empty(PathBuf::new())
+                });
 
             // ---------------------------------------
 
-            let app_manager = AppManager::new(app_handle.clone(), registry)
+            let addon_app_handle = app_handle.clone();
+            let context_key_app_handle = app_handle.clone();
+            let theme_app_cache = Arc::clone(&app_cache);
+
+            let app_manager = AppManager::new(registry)
                 .with_service(|_| LifecycleService::new(), InstantiationType::Instant)
                 .with_service(
-                    |_| ConfigurationService::new(default_configurations),
+                    |_| {
+                        ConfigurationService::new(
+                            configuration_registry,
+                            default_configurations,
+                            user_configuration,
+                        )
+                    },
                     InstantiationType::Instant,
                 )
-                .with_service(
-                    |app_handle| {
-                        AddonService::new(
-                            app_handle,
-                            BUILTIN_ADDONS_DIR.to_path_buf(),
-                            INSTALLED_ADDONS_DIR.to_path_buf(),
-                        )
+                .with_async_service(
+                    move |_| {
+                        let app_handle = addon_app_handle.clone();
+                        async move {
+                            AddonService::new(
+                                &app_handle,
+                                BUILTIN_ADDONS_DIR.to_path_buf(),
+                                INSTALLED_ADDONS_DIR.to_path_buf(),
+                            )
+                        }
+                    },
+                    InstantiationType::Instant,
+                )
+                .with_async_service(
+                    move |resolver| {
+                        let app_state = context_key_app_handle.state::<AppStateManager>();
+                        let service = ContextKeyService::new(
+                            &resolver,
+                            context_key_app_handle.clone(),
+                            Arc::clone(&app_state.contributions.context_keys),
+                            &app_state.preferences,
+                        );
+                        async move { Ok(service?) }
                     },
                     InstantiationType::Instant,
                 )
                 .with_service(|_| WindowService::new(), InstantiationType::Delayed)
-                .with_service(
-                    |app_handle| ThemeService::new(app_handle),
+                .with_async_service(
+                    move |resolver| {
+                        let service = ThemeService::new(
+                            &resolver,
+                            Arc::clone(&theme_app_cache),
+                            Arc::clone(&themes),
+                            default_theme.clone(),
+                        );
+                        async move { Ok(service?) }
+                    },
                     InstantiationType::Delayed,
                 )
                 .with_service(
-                    |app_handle| LocaleService::new(app_handle),
+                    move |_| LocaleService::new(app_cache, locales, default_locale),
                     InstantiationType::Delayed,
-                );
+                )
+                .activate()?;
             app_handle.manage(app_manager);
 
-            let ctrl_n_shortcut = Shortcut::new(Some(Modifiers::CONTROL), Code::KeyN);
-
-            app.handle().plugin(
-                tauri_plugin_global_shortcut::Builder::new()
-                    .with_handler(move |app, shortcut, event| {
-                        println!("{:?}", shortcut);
-                        if shortcut == &ctrl_n_shortcut {
-                            match event.state() {
-                                ShortcutState::Pressed => {
-                                    tauri::async_runtime::spawn(cmd_window::create_new_window(
-                                        app.clone(),
-                                    ));
-                                }
-                                ShortcutState::Released => {}
-                            }
-                        }
-                    })
-                    .build(),
-            )?;
-            app.global_shortcut().register(ctrl_n_shortcut)?;
-
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -164,11 +276,38 @@ pub fn run() {
             cmd_window::get_themes,
             cmd_window::create_new_window,
             cmd_window::execute_command,
+            cmd_window::cancel_command,
+            cmd_window::list_commands,
+            cmd_window::undo_command,
+            cmd_window::redo_command,
+            cmd_window::get_command_history,
+            cmd_window::get_command_log,
+            cmd_window::get_recent_commands,
+            cmd_window::replay_commands,
+            cmd_window::resolve_keybinding,
+            cmd_window::get_keybindings,
+            cmd_window::get_keybinding_conflicts,
+            cmd_window::get_context,
+            cmd_window::get_context_keys,
+            cmd_window::set_context_key,
+            cmd_window::reset_context_key,
             cmd_base::get_menu_items_by_namespace,
-            cmd_window::execute_command,
+            cmd_base::resolve_menu,
             cmd_window::get_color_theme,
             cmd_window::get_state,
+            cmd_window::get_configuration_schema,
+            cmd_window::search_settings,
+            cmd_window::export_settings_bundle,
+            cmd_window::preview_settings_import,
+            cmd_window::import_settings_bundle,
+            cmd_window::install_addon,
+            cmd_window::disable_addon,
+            cmd_window::get_services,
+            cmd_window::get_services_report,
+            cmd_window::notify_window_restored,
+            cmd_window::notify_window_idle,
         ])
+        .on_menu_event(menu::handle_event)
         .on_window_event(|window, event| match event {
             #[cfg(target_os = "macos")]
             WindowEvent::CloseRequested { api, .. } => {
@@ -177,7 +316,31 @@ pub fn run() {
                     api.prevent_close();
                 }
             }
-            WindowEvent::Focused(_) => { /* call updates, git fetch, etc. */ }
+            WindowEvent::Focused(false) => {
+                // A sequence of chords cannot be finished in another window.
+                window
+                    .app_handle()
+                    .state::<AppStateManager>()
+                    .contributions
+                    .keybindings
+                    .clear_pending(window.label());
+            }
+            WindowEvent::Focused(true) => {
+                // The menu bar follows the context of the focused window.
+                menu::refresh_app_menu(window.app_handle());
+            }
+            WindowEvent::Destroyed => {
+                if let Ok(context_keys) = window
+                    .app_handle()
+                    .state::<AppManager>()
+                    .service::<ContextKeyService>()
+                {
+                    context_keys.clear_window(window.label());
+                }
+                let app_state = window.app_handle().state::<AppStateManager>();
+                app_state.history.clear_window(window.label());
+                app_state.settings_imports.clear_window(window.label());
+            }
 
             _ => (),
         })
@@ -185,9 +348,13 @@ pub fn run() {
         .expect("failed to run")
         .run(|app_handle, event| match event {
             RunEvent::Ready => {
-                let webview_window = create_main_window(&app_handle, "/");
-                webview_window
-                    .on_menu_event(move |window, event| menu::handle_event(window, &event));
+                menu::refresh_app_menu(app_handle);
+                menu::watch_context_keys(app_handle);
+                watch_contributions(app_handle);
+                create_main_window(&app_handle, "/");
+                enter_phase(app_handle, LifecyclePhase::Ready);
+                load_installed_addons_when_restored(app_handle);
+                report_when_settled(app_handle);
             }
 
             #[cfg(target_os = "macos")]
@@ -196,32 +363,199 @@ pub fn run() {
                 api.prevent_exit();
             }
 
+            RunEvent::Exit => {
+                app_handle.state::<AppStateManager>().log_cache_stats();
+                dump_services(app_handle);
+                dispose_services(app_handle);
+            }
+
             _ => {}
         });
 }
 
+/// Moves the application to the phase unless it has already reached it. The `Ready` phase
+/// is entered once the main window is created, the later ones are signaled by the windows.
+/// The services deferred to the later phases are activated off the main thread, so they do
+/// not hold up the first paint.
+fn enter_phase(app_handle: &AppHandle, phase: LifecyclePhase) {
+    let app_handle = app_handle.clone();
+    let enter = move || {
+        let app_manager = app_handle.state::<AppManager>();
+        let reached = app_manager
+            .service::<LifecycleService>()
+            .map(|lifecycle_service| lifecycle_service.get_current_phase() >= phase);
+
+        match reached {
+            Ok(true) => {}
+            Ok(false) => {
+                if let Err(err) = app_manager.advance_phase(phase) {
+                    error!("Failed to enter the {phase:?} phase: {err:#}");
+                }
+            }
+            Err(err) => error!("Failed to enter the {phase:?} phase: {err:#}"),
+        }
+    };
+
+    if phase == LifecyclePhase::Ready {
+        enter();
+    } else {
+        tauri::async_runtime::spawn_blocking(enter);
+    }
+}
+
+/// Loads the installed addons once the state of the windows is restored.
+fn load_installed_addons_when_restored(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let app_manager = app_handle.state::<AppManager>();
+        if let Err(err) = app_manager.when_phase(LifecyclePhase::Restored).await {
+            error!("Failed to wait for the restored phase: {err:#}");
+            return;
+        }
+
+        let app_state = app_handle.state::<AppStateManager>();
+        match app_manager.service::<AddonService>() {
+            Ok(addon_service) => addon_service.load_installed_addons(&app_state),
+            Err(err) => {
+                error!("Failed to load the installed addons: {err:#}");
+                return;
+            }
+        }
+
+        // The configured theme can be contributed by an installed addon, in place of the
+        // fallback the windows were given so far.
+        if app_state.preferences.theme.read().is_none() {
+            if let Err(err) = refresh_default_theme(&app_handle) {
+                error!("Failed to apply the default theme: {err:#}");
+            }
+        }
+    });
+}
+
+fn refresh_default_theme(app_handle: &AppHandle) -> Result<()> {
+    let app_manager = app_handle.state::<AppManager>();
+    let theme = app_manager.service::<ThemeService>()?.default_color_theme();
+    let context_key_service = app_manager.service::<ContextKeyService>()?;
+    if context_key_service.get(&CONTEXT_KEY_THEME, &ContextScope::Global) == theme.id {
+        return Ok(());
+    }
+
+    context_key_service.set(&CONTEXT_KEY_THEME, ContextScope::Global, theme.id.clone());
+    app_handle.emit("core://color-theme-changed", theme)?;
+
+    Ok(())
+}
+
+/// Reports the state of the application once it has settled.
+fn report_when_settled(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let app_manager = app_handle.state::<AppManager>();
+        if let Err(err) = app_manager.when_phase(LifecyclePhase::Settled).await {
+            error!("Failed to wait for the settled phase: {err:#}");
+            return;
+        }
+
+        dump_services(&app_handle);
+        log_keybinding_conflicts(&app_handle);
+        log_dangling_submenus(&app_handle);
+    });
+}
+
+/// Notifies the windows of the contributions added and removed at runtime, e.g. by enabling
+/// or disabling an addon, and rebuilds the menu bar from them.
+fn watch_contributions(app_handle: &AppHandle) {
+    let mut changes = app_handle
+        .state::<AppStateManager>()
+        .contributions
+        .subscribe();
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let change = match changes.recv().await {
+                Ok(change) => Some(change),
+                Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => break,
+            };
+
+            if let Some(change) = change {
+                if let Err(err) = app_handle.emit(CONTRIBUTIONS_CHANGED_EVENT, change) {
+                    warn!("Failed to notify the windows of a contribution change: {err}");
+                }
+            }
+            menu::refresh_app_menu(&app_handle);
+        }
+    });
+}
+
+/// Reports the keybindings that can never be triggered, once the addons have contributed
+/// theirs.
+fn log_keybinding_conflicts(app_handle: &AppHandle) {
+    let app_state = app_handle.state::<AppStateManager>();
+    for conflict in app_state.contributions.keybindings.conflicts() {
+        warn!(
+            "Keybinding conflict ({:?}) on '{}' between commands {}",
+            conflict.kind,
+            conflict.key,
+            conflict.commands.join(", ")
+        );
+    }
+}
+
+/// Reports the submenus that refer to a menu nobody contributes, once the addons have
+/// contributed theirs.
+fn log_dangling_submenus(app_handle: &AppHandle) {
+    let app_state = app_handle.state::<AppStateManager>();
+    for (namespace, submenu_id) in menus::dangling_submenus(&app_state.contributions.menus) {
+        warn!("Submenu {submenu_id} in menu {namespace} refers to no contributed menu");
+    }
+}
+
+fn dump_services(app_handle: &AppHandle) {
+    if *DUMP_SERVICES {
+        eprint!("{}", app_handle.state::<AppManager>().services_report());
+    }
+}
+
+fn dispose_services(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    let timeout = Duration::from_millis(*SERVICE_DISPOSAL_TIMEOUT_MS);
+
+    // The exit event is delivered on a thread that is already driven by the runtime,
+    // so the disposal is awaited on a thread of its own.
+    let result = std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            app_handle.state::<AppManager>().dispose(timeout).await;
+        })
+    })
+    .join();
+
+    if result.is_err() {
+        error!("The disposal of the services panicked");
+    }
+}
+
 fn create_main_window(app_handle: &AppHandle, url: &str) -> WebviewWindow {
     let app_manager = app_handle.state::<AppManager>();
     let configuration_service = app_manager
         .service::<ConfigurationService>()
         .expect("The configuration service must already be available at this point");
 
-    let window_inner_height = configuration_service
-        .get_typed_value("window.defaultHeight")
-        .unwrap();
-
-    let window_inner_width = configuration_service
-        .get_typed_value("window.defaultWidth")
-        .unwrap();
-
-    dbg!(&window_inner_height, &window_inner_width);
+    let settings = configuration_service.settings();
+    let window_settings = settings.window();
 
     let label = format!("{MAIN_WINDOW_PREFIX}{}", 0);
     let config = CreateWindowInput {
         url,
         label: label.as_str(),
         title: "Moss Studio",
-        inner_size: (window_inner_height, window_inner_width),
+        inner_size: (
+            window_settings.default_width().into(),
+            window_settings.default_height().into(),
+        ),
         position: (
             100.0 + random::<f64>() * 20.0,
             100.0 + random::<f64>() * 20.0,
@@ -247,3 +581,18 @@ fn create_child_window(app_handle: &AppHandle, url: &str) -> Result<WebviewWindo
 
     Ok(create_window(app_handle, config))
 }
+
+fn get_user_settings_file() -> Result<PathBuf> {
+    Ok(get_user_config_dir()?.join("settings.json"))
+}
+
+fn get_user_keybindings_file() -> Result<PathBuf> {
+    Ok(get_user_config_dir()?.join("keybindings.json"))
+}
+
+fn get_user_config_dir() -> Result<PathBuf> {
+    Ok(home_dir()
+        .ok_or(anyhow!("Couldn't get the home directory"))?
+        .join(".config")
+        .join("moss"))
+}
@@ -1,93 +1,292 @@
-use strum::{AsRefStr as StrumAsRefStr, Display as StrumDisplay, EnumString as StrumEnumString};
+use hashbrown::HashMap;
+use moss_desktop::{
+    app::{manager::AppManager, state::AppStateManager},
+    menus::{self, MenuError},
+    models::{
+        actions::{ResolvedMenuItem, ResolvedSubmenuMenuItem},
+        constants::menu::MENU_NAMESPACE_ID_MENUBAR,
+        context_keys::ContextScope,
+    },
+    services::context_key_service::ContextKeyService,
+};
+use moss_text::{localized_string::LocalizedString, ReadOnlyStr};
+use serde_json::Value;
 use tauri::{
-    menu::{Menu, MenuEvent, PredefinedMenuItem},
-    AppHandle, Manager, Window, Wry,
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    AppHandle, Manager, WebviewWindow, Wry,
 };
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::commands::cmd_window;
+
+/// Runs the command of the clicked menu item in the focused window. The items are
+/// identified by the id of their command.
+pub fn handle_event(app_handle: &AppHandle, event: MenuEvent) {
+    let command = ReadOnlyStr::from(event.id().as_ref());
+    let app_state = app_handle.state::<AppStateManager>();
+    if app_state.get_command(&command).is_none() {
+        // The predefined items and the submenus are handled natively.
+        return;
+    }
 
-use crate::create_child_window;
+    // The commands apply to the window the user works in, e.g. `workbench.closeWindow`,
+    // so they are not run in another one when none has the focus.
+    let Some(window) = focused_window(app_handle) else {
+        warn!("No focused window to run the command {command} of the menu in");
+        return;
+    };
 
-#[derive(Debug, StrumEnumString, StrumDisplay, StrumAsRefStr)]
-pub enum BuiltInMenuEvent {
-    #[strum(serialize = "file.newWindow")]
-    NewWindow,
-    #[strum(serialize = "file.closeWindow")]
-    CloseWindow,
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let window = window.as_ref().window();
+        if let Err(err) =
+            cmd_window::dispatch_command(app_handle, window, command.clone(), HashMap::new(), None)
+                .await
+        {
+            error!("Failed to run the command {command} of the menu: {err}");
+        }
+    });
 }
 
-pub fn handle_event(_window: &Window, event: &MenuEvent) {
-    let event_id = event.id().0.as_str();
-    let app_handle = _window.app_handle().clone();
-    match event_id {
-        "file.newWindow" => handle_new_window(app_handle),
-        _ => {}
+/// Rebuilds the menu bar against the context of the focused window.
+pub fn refresh_app_menu(app_handle: &AppHandle) {
+    if let Err(err) = app_menu(app_handle).and_then(|menu| app_handle.set_menu(menu)) {
+        error!("Failed to refresh the application menu: {err}");
     }
 }
 
-fn handle_new_window(app_handle: tauri::AppHandle) {
+/// Refreshes the menu bar whenever a context key changes, for the `when` rules and the
+/// toggles of its items to hold the current values.
+pub fn watch_context_keys(app_handle: &AppHandle) {
+    let context_keys = match app_handle
+        .state::<AppManager>()
+        .service::<ContextKeyService>()
+    {
+        Ok(context_keys) => context_keys,
+        Err(err) => {
+            error!("Failed to watch the context keys for the application menu: {err}");
+            return;
+        }
+    };
+    let mut changes = context_keys.subscribe();
+
+    let app_handle = app_handle.clone();
     tauri::async_runtime::spawn(async move {
-        match create_child_window(&app_handle, "/") {
-            Ok(webview_window) => {
-                webview_window.on_menu_event(move |window, event| handle_event(window, &event));
-            }
-            Err(err) => {
-                eprintln!("Failed to open a new window: {}", err);
-                return;
-            }
+        while let Ok(_) | Err(RecvError::Lagged(_)) = changes.recv().await {
+            // A change rarely comes alone, the menu is rebuilt once for all of them.
+            while changes.try_recv().is_ok() {}
+            refresh_app_menu(&app_handle);
         }
     });
 }
 
+/// Builds the menu bar from the menus contributed under the `menubar` namespace, each of
+/// its submenus being a menu of the bar. On macOS, the application, Edit and Window menus
+/// of the system are kept around them.
 pub fn app_menu(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
-    #[cfg(not(target_os = "macos"))]
-    {
-        Menu::new(app_handle)
+    let menu = Menu::new(app_handle)?;
+
+    #[cfg(target_os = "macos")]
+    menu.append(&macos_app_menu(app_handle)?)?;
+
+    let app_state = app_handle.state::<AppStateManager>();
+    let context = menu_context(app_handle);
+    let items = match menus::resolve_menu(
+        &app_state.contributions.menus,
+        &MENU_NAMESPACE_ID_MENUBAR,
+        &context,
+    ) {
+        Ok(resolved) => resolved.items,
+        Err(MenuError::NamespaceNotFound { .. }) => Vec::new(),
+    };
+
+    // The Edit menu follows the File menu, or the application menu without one.
+    #[cfg(target_os = "macos")]
+    if !items.iter().any(is_file_menu) {
+        menu.append(&macos_edit_menu(app_handle)?)?;
+    }
+
+    let builder = NativeMenuBuilder {
+        app_handle,
+        app_state: &app_state,
+        context: &context,
+    };
+    for item in &items {
+        match item {
+            ResolvedMenuItem::Submenu(submenu) => menu.append(&builder.submenu(submenu)?)?,
+            ResolvedMenuItem::Action(action) => warn!(
+                "Leaving the command {} out of the menu bar, which only holds menus",
+                action.command.id
+            ),
+            ResolvedMenuItem::Separator => {}
+        }
+
+        #[cfg(target_os = "macos")]
+        if is_file_menu(item) {
+            menu.append(&macos_edit_menu(app_handle)?)?;
+        }
     }
 
     #[cfg(target_os = "macos")]
-    {
-        use tauri::menu::{AboutMetadataBuilder, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+    menu.append(&macos_window_menu(app_handle)?)?;
 
-        unsafe {
-            macos_trampoline::set_app_name(&"Moss Studio".into());
+    Ok(menu)
+}
+
+#[cfg(target_os = "macos")]
+fn is_file_menu(item: &ResolvedMenuItem) -> bool {
+    use moss_desktop::models::constants::menu::MENU_NAMESPACE_ID_MENUBAR_FILE;
+
+    matches!(item, ResolvedMenuItem::Submenu(submenu) if submenu.submenu_id == MENU_NAMESPACE_ID_MENUBAR_FILE)
+}
+
+struct NativeMenuBuilder<'a> {
+    app_handle: &'a AppHandle,
+    app_state: &'a AppStateManager,
+    context: &'a Value,
+}
+
+impl NativeMenuBuilder<'_> {
+    fn submenu(&self, resolved: &ResolvedSubmenuMenuItem) -> tauri::Result<Submenu<Wry>> {
+        let title = resolved
+            .title
+            .as_ref()
+            .map(label)
+            .unwrap_or_else(|| resolved.submenu_id.to_string());
+        let submenu = Submenu::with_id(self.app_handle, resolved.submenu_id.as_str(), title, true)?;
+
+        for item in &resolved.items {
+            match item {
+                ResolvedMenuItem::Action(action) => {
+                    let id = action.command.id.as_str();
+                    let title = label(&action.command.title);
+                    // The items of the commands that are not registered or not enabled
+                    // are shown, but cannot be clicked.
+                    let enabled =
+                        self.app_state
+                            .get_command(&action.command.id)
+                            .is_some_and(|command| {
+                                command.is_enabled(&action.command.id, self.context)
+                            });
+
+                    match action.toggled {
+                        Some(checked) => submenu.append(&CheckMenuItem::with_id(
+                            self.app_handle,
+                            id,
+                            title,
+                            enabled,
+                            checked,
+                            None::<&str>,
+                        )?)?,
+                        None => submenu.append(&MenuItem::with_id(
+                            self.app_handle,
+                            id,
+                            title,
+                            enabled,
+                            None::<&str>,
+                        )?)?,
+                    }
+                }
+                ResolvedMenuItem::Submenu(nested) => submenu.append(&self.submenu(nested)?)?,
+                ResolvedMenuItem::Separator => {
+                    submenu.append(&PredefinedMenuItem::separator(self.app_handle)?)?
+                }
+            }
         }
 
-        let app_menu = SubmenuBuilder::new(app_handle, "Moss")
-            .item(&PredefinedMenuItem::about(
-                app_handle,
-                Some("About Moss Studio"),
-                Some(
-                    AboutMetadataBuilder::new()
-                        .license(Some(env!("CARGO_PKG_VERSION")))
-                        .version(Some(env!("CARGO_PKG_VERSION")))
-                        // TODO: .website(Some("https://mossland.dev/"))
-                        // TODO: .website_label(Some("mossland.dev.com"))
-                        .build(),
-                ),
-            )?)
-            .separator()
-            .item(&PredefinedMenuItem::hide(
-                app_handle,
-                Some("Hide Moss Studio"),
-            )?)
-            .hide_others()
-            .show_all()
-            .separator()
-            .quit()
-            .build()?;
-
-        let window_menu = SubmenuBuilder::new(app_handle, "Window")
-            .minimize()
-            .item(
-                &MenuItemBuilder::with_id(BuiltInMenuEvent::NewWindow, "New Window")
-                    .build(app_handle)?,
-            )
-            .build()?;
-
-        let menu = MenuBuilder::new(app_handle)
-            .item(&app_menu)
-            .item(&window_menu)
-            .build()?;
-
-        Ok(menu)
+        Ok(submenu)
+    }
+}
+
+/// The context of the focused window, which the menu bar applies to.
+fn menu_context(app_handle: &AppHandle) -> Value {
+    let scope = focused_window(app_handle)
+        .map(|window| ContextScope::window(window.label()))
+        .unwrap_or(ContextScope::Global);
+
+    match app_handle
+        .state::<AppManager>()
+        .service::<ContextKeyService>()
+    {
+        Ok(context_keys) => context_keys.context(&scope),
+        Err(err) => {
+            warn!("Building the application menu without the context keys: {err}");
+            Value::Null
+        }
     }
 }
+
+/// The window that has the focus, none while the application is in the background.
+fn focused_window(app_handle: &AppHandle) -> Option<WebviewWindow> {
+    let windows = app_handle.webview_windows();
+    windows
+        .values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .cloned()
+}
+
+/// The native menus cannot be localized by the frontend, so they show the original text.
+/// An `&` would otherwise mark the mnemonic of the item.
+fn label(text: &LocalizedString) -> String {
+    String::from_utf8_lossy(text.origin().as_ref()).replace('&', "&&")
+}
+
+#[cfg(target_os = "macos")]
+fn macos_app_menu(app_handle: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    use tauri::menu::{AboutMetadataBuilder, SubmenuBuilder};
+
+    unsafe {
+        macos_trampoline::set_app_name(&"Moss Studio".into());
+    }
+
+    SubmenuBuilder::new(app_handle, "Moss")
+        .item(&PredefinedMenuItem::about(
+            app_handle,
+            Some("About Moss Studio"),
+            Some(
+                AboutMetadataBuilder::new()
+                    .license(Some(env!("CARGO_PKG_VERSION")))
+                    .version(Some(env!("CARGO_PKG_VERSION")))
+                    // TODO: .website(Some("https://mossland.dev/"))
+                    // TODO: .website_label(Some("mossland.dev.com"))
+                    .build(),
+            ),
+        )?)
+        .separator()
+        .item(&PredefinedMenuItem::hide(
+            app_handle,
+            Some("Hide Moss Studio"),
+        )?)
+        .hide_others()
+        .show_all()
+        .separator()
+        .quit()
+        .build()
+}
+
+/// The native editing items, which the text fields of the webviews rely on for their
+/// shortcuts on macOS.
+#[cfg(target_os = "macos")]
+fn macos_edit_menu(app_handle: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    use tauri::menu::SubmenuBuilder;
+
+    SubmenuBuilder::new(app_handle, "Edit")
+        .undo()
+        .redo()
+        .separator()
+        .cut()
+        .copy()
+        .paste()
+        .select_all()
+        .build()
+}
+
+#[cfg(target_os = "macos")]
+fn macos_window_menu(app_handle: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    use tauri::menu::SubmenuBuilder;
+
+    SubmenuBuilder::new(app_handle, "Window")
+        .minimize()
+        .maximize()
+        .build()
+}
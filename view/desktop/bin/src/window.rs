@@ -1,6 +1,6 @@
 use tauri::{AppHandle, WebviewUrl, WebviewWindow};
 
-use crate::{menu, MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH};
+use crate::{MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH};
 
 #[derive(Debug)]
 pub struct CreateWindowInput<'a> {
@@ -13,12 +13,6 @@ pub struct CreateWindowInput<'a> {
 
 #[instrument(level = "debug", skip(app_handle))]
 pub fn create_window(app_handle: &AppHandle, input: CreateWindowInput<'_>) -> WebviewWindow {
-    #[cfg(target_os = "macos")]
-    {
-        let menu = menu::app_menu(app_handle).unwrap();
-        app_handle.set_menu(menu).expect("Failed to set app menu");
-    }
-
     let mut win_builder = tauri::WebviewWindowBuilder::new(
         app_handle,
         input.label,
@@ -0,0 +1,12 @@
+import { invokeTauriIpc, IpcResult } from "@/lib/backend/tauri";
+import type { TauriError } from "@repo/moss-tauri";
+
+// Addons
+
+export const installAddon = async (path: string): Promise<IpcResult<string, TauriError>> => {
+  return await invokeTauriIpc("install_addon", { path });
+};
+
+export const disableAddon = async (id: string): Promise<IpcResult<boolean, TauriError>> => {
+  return await invokeTauriIpc("disable_addon", { id });
+};
@@ -1,5 +1,5 @@
 import { invokeTauriIpc, IpcResult } from "@/lib/backend/tauri";
-import { AppState, LocaleDescriptor, MenuItem, ThemeDescriptor } from "@repo/moss-desktop";
+import { AppState, LocaleDescriptor, MenuItem, ResolvedMenu, ThemeDescriptor } from "@repo/moss-desktop";
 import { invoke } from "@tauri-apps/api/core";
 
 // App state
@@ -31,3 +31,7 @@ export const getLanguagePacks = async (): Promise<LocaleDescriptor[]> => {
 export const getAllActivities = async (): Promise<IpcResult<MenuItem[], Error>> => {
   return await invokeTauriIpc("get_menu_items_by_namespace", { namespace: "headItem" }); // this here should be a type
 };
+
+export const resolveMenu = async (namespace: string): Promise<IpcResult<ResolvedMenu, Error>> => {
+  return await invokeTauriIpc("resolve_menu", { namespace });
+};
@@ -0,0 +1,11 @@
+import { invokeTauriIpc, IpcResult } from "@/lib/backend/tauri";
+
+// Lifecycle
+
+export const notifyWindowRestored = async (): Promise<IpcResult<void, Error>> => {
+  return await invokeTauriIpc("notify_window_restored");
+};
+
+export const notifyWindowIdle = async (): Promise<IpcResult<void, Error>> => {
+  return await invokeTauriIpc("notify_window_idle");
+};
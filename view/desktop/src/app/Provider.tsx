@@ -1,5 +1,6 @@
-import { ReactNode, useEffect } from "react";
+import { ReactNode, useEffect, useRef } from "react";
 
+import { notifyWindowIdle, notifyWindowRestored } from "@/api/lifecycle";
 import { useGetAppState } from "@/hooks/useGetAppState";
 import { applyLanguagePack } from "@/utils/applyLanguagePack";
 import { applyTheme } from "@/utils/applyTheme";
@@ -19,16 +20,34 @@ const Provider = ({ children }: { children: ReactNode }) => {
 
 const useInitializeAppState = () => {
   const { data } = useGetAppState();
+  const isRestored = useRef(false);
 
   useEffect(() => {
     if (data) {
       const theme = data.preferences?.theme ?? data.defaults.theme;
       const languagePack = data.preferences?.locale ?? data.defaults.locale;
 
-      applyTheme(theme);
+      const themeApplied = applyTheme(theme);
       applyLanguagePack(languagePack);
+
+      // The window is restored once it shows the preferred theme and language for the first time.
+      if (!isRestored.current) {
+        isRestored.current = true;
+        themeApplied.then(signalRestored);
+      }
     }
   }, [data]);
 };
 
+// Tells the backend that the window is restored, then that it is idle once it has nothing left to do.
+const signalRestored = async () => {
+  await notifyWindowRestored();
+
+  if ("requestIdleCallback" in window) {
+    requestIdleCallback(() => notifyWindowIdle());
+  } else {
+    setTimeout(() => notifyWindowIdle());
+  }
+};
+
 export default Provider;
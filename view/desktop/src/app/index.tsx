@@ -1,12 +1,16 @@
 import "@/app/i18n";
 
 import { AppLayout, RootLayout } from "@/components";
+import { useContributionChanges } from "@/hooks/useContributionChanges";
+import { useKeybindings } from "@/hooks/useKeybindings";
 import { usePrepareWindow } from "@/hooks/usePrepareWindow";
 
 import { PageLoader } from "../components/PageLoader";
 
 const App = () => {
   const { isPreparing } = usePrepareWindow();
+  useKeybindings();
+  useContributionChanges();
 
   if (isPreparing) {
     return <PageLoader />;
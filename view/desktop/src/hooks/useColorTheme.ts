@@ -1,5 +1,5 @@
 import { getColorThemes } from "@/api/appearance";
-import { invokeMossCommand } from "@/lib/backend/platfrom";
+import { invokeTypedMossCommand } from "@/lib/backend/platfrom";
 import { ThemeDescriptor } from "@repo/moss-desktop";
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 
@@ -11,7 +11,7 @@ export const useGetColorThemes = () => {
 };
 
 const changeTheme = async (themeDescriptor: ThemeDescriptor): Promise<void> => {
-  await invokeMossCommand("workbench.changeColorTheme", {
+  await invokeTypedMossCommand("workbench.changeColorTheme", {
     themeDescriptor,
   });
 };
@@ -0,0 +1,44 @@
+import { useEffect } from "react";
+
+import { invokeTauriIpc, listenTauriIpc } from "@/lib/backend/tauri";
+import { ContextKeyChange, ContextScope } from "@repo/moss-desktop";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+
+const getContext = async (scope?: ContextScope): Promise<Record<string, unknown>> => {
+  const result = await invokeTauriIpc<Record<string, unknown>>("get_context", { scope });
+  if (result.status === "error") throw new Error(result.error.message);
+
+  return result.data;
+};
+
+// The context the `when` rules are evaluated against, kept in sync with the backend.
+export const useRuleContext = (scope?: ContextScope) => {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    const unlisten = listenTauriIpc<ContextKeyChange>("core://context-keys-changed", () => {
+      queryClient.invalidateQueries({ queryKey: ["getContext"] });
+    });
+
+    return () => {
+      unlisten();
+    };
+  }, [queryClient]);
+
+  return useQuery<Record<string, unknown>, Error>({
+    queryKey: ["getContext", scope],
+    queryFn: () => getContext(scope),
+  });
+};
+
+type SetContextKeyInput = { key: string; value: unknown; scope?: ContextScope };
+
+export const useSetContextKey = () => {
+  return useMutation<void, Error, SetContextKeyInput>({
+    mutationKey: ["setContextKey"],
+    mutationFn: async ({ key, value, scope }) => {
+      const result = await invokeTauriIpc<void>("set_context_key", { key, value, scope });
+      if (result.status === "error") throw new Error(result.error.message);
+    },
+  });
+};
@@ -0,0 +1,21 @@
+import { useEffect } from "react";
+
+import { listenTauriIpc } from "@/lib/backend/tauri";
+import { ContributionChange } from "@repo/moss-desktop";
+import { useQueryClient } from "@tanstack/react-query";
+
+// Refetches what the window shows from the contributions, e.g. the themes and the
+// locales, once an addon is enabled or disabled.
+export const useContributionChanges = () => {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    const unlisten = listenTauriIpc<ContributionChange>("core://contributions-changed", () => {
+      queryClient.invalidateQueries();
+    });
+
+    return () => {
+      unlisten();
+    };
+  }, [queryClient]);
+};
@@ -0,0 +1,98 @@
+import { useEffect, useRef } from "react";
+
+import { invokeMossCommand } from "@/lib/backend/platfrom";
+import { invokeTauriIpc } from "@/lib/backend/tauri";
+import { KeybindingDescriptor, KeybindingResolution } from "@repo/moss-desktop";
+import { useQuery } from "@tanstack/react-query";
+
+const MODIFIER_CODES = new Set([
+  "ControlLeft",
+  "ControlRight",
+  "ShiftLeft",
+  "ShiftRight",
+  "AltLeft",
+  "AltRight",
+  "MetaLeft",
+  "MetaRight",
+  "OSLeft",
+  "OSRight",
+]);
+
+const PUNCTUATION_CODES: Record<string, string> = {
+  Minus: "-",
+  Equal: "=",
+  BracketLeft: "[",
+  BracketRight: "]",
+  Backslash: "\\",
+  Semicolon: ";",
+  Quote: "'",
+  Comma: ",",
+  Period: ".",
+  Slash: "/",
+  Backquote: "`",
+};
+
+// Names the chord of the event the way the keybinding registry prints it, e.g. `ctrl+shift+p`.
+const chordOf = (event: KeyboardEvent): string | undefined => {
+  if (event.code === "" || MODIFIER_CODES.has(event.code)) return undefined;
+
+  const key = PUNCTUATION_CODES[event.code] ?? event.code.replace(/^(Key|Digit|Arrow)/, "").toLowerCase();
+  const modifiers = [
+    event.ctrlKey && "ctrl+",
+    event.shiftKey && "shift+",
+    event.altKey && "alt+",
+    event.metaKey && "meta+",
+  ].filter(Boolean);
+
+  return modifiers.join("") + key;
+};
+
+const getKeybindings = async (): Promise<KeybindingDescriptor[]> => {
+  const result = await invokeTauriIpc<KeybindingDescriptor[]>("get_keybindings");
+  if (result.status === "error") throw new Error(result.error.message);
+
+  return result.data;
+};
+
+// Sends the keys pressed in the window to the keybinding registry, and executes
+// the commands they are bound to. The keys that start a binding, or continue the
+// sequence being entered, are kept from the webview before the registry is asked.
+export const useKeybindings = () => {
+  const { data: keybindings } = useQuery<KeybindingDescriptor[], Error>({
+    queryKey: ["getKeybindings"],
+    queryFn: getKeybindings,
+  });
+  const prefixes = useRef(new Set<string>());
+  const pending = useRef(false);
+
+  useEffect(() => {
+    prefixes.current = new Set((keybindings ?? []).map((keybinding) => keybinding.key.split(" ")[0]));
+  }, [keybindings]);
+
+  useEffect(() => {
+    const handleKeyDown = async (event: KeyboardEvent) => {
+      if (event.repeat) return;
+
+      const chord = chordOf(event);
+      if (chord === undefined || (!pending.current && !prefixes.current.has(chord))) return;
+      event.preventDefault();
+
+      const result = await invokeTauriIpc<KeybindingResolution>("resolve_keybinding", {
+        event: {
+          code: event.code,
+          ctrlKey: event.ctrlKey,
+          shiftKey: event.shiftKey,
+          altKey: event.altKey,
+          metaKey: event.metaKey,
+        },
+      });
+      pending.current = result.status === "ok" && result.data.kind === "pending";
+      if (result.status !== "ok" || result.data.kind !== "command") return;
+
+      await invokeMossCommand(result.data.command, result.data.args ?? {});
+    };
+
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, []);
+};
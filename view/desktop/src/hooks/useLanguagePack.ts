@@ -1,5 +1,5 @@
 import { getLanguagePacks } from "@/api/appearance";
-import { invokeMossCommand } from "@/lib/backend/platfrom";
+import { invokeTypedMossCommand } from "@/lib/backend/platfrom";
 import { LocaleDescriptor } from "@repo/moss-desktop";
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 
@@ -11,7 +11,7 @@ export const useGetLanguagePacks = () => {
 };
 
 const changeLanguagePack = async (localeDescriptor: LocaleDescriptor): Promise<void> => {
-  await invokeMossCommand("workbench.changeLanguagePack", {
+  await invokeTypedMossCommand("workbench.changeLanguagePack", {
     localeDescriptor,
   });
 };
@@ -1,10 +1,30 @@
+import { MossCommandMap } from "@repo/moss-desktop";
 import { InvokeArgs } from "@tauri-apps/api/core";
 
 import { invokeTauriIpc, IpcResult } from "./tauri";
 
-export const invokeMossCommand = async <T, E>(cmd: string, args?: InvokeArgs): Promise<IpcResult<T, E>> => {
+export const invokeMossCommand = async <T, E>(
+  cmd: string,
+  args?: InvokeArgs,
+  invocationId?: string
+): Promise<IpcResult<T, E>> => {
   return await invokeTauriIpc<T, E>("execute_command", {
     cmd,
     args,
+    invocationId,
   });
 };
+
+export const cancelMossCommand = async (invocationId: string): Promise<IpcResult<boolean, unknown>> => {
+  return await invokeTauriIpc<boolean>("cancel_command", {
+    invocationId,
+  });
+};
+
+export const invokeTypedMossCommand = async <K extends keyof MossCommandMap, E = unknown>(
+  cmd: K,
+  args: MossCommandMap[K]["args"],
+  invocationId?: string
+): Promise<IpcResult<MossCommandMap[K]["result"], E>> => {
+  return await invokeMossCommand<MossCommandMap[K]["result"], E>(cmd, args, invocationId);
+};
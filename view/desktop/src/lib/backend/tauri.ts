@@ -1,18 +1,37 @@
 import { InvokeArgs, invoke as invokeTauri } from "@tauri-apps/api/core";
 import { listen as listenTauri } from "@tauri-apps/api/event";
 import type { EventCallback, EventName } from "@tauri-apps/api/event";
+import type { TauriError } from "@repo/moss-tauri";
 
 // Define all possible Tauri IPC commands as string literals
 export type TauriIpcCommand =
   | "execute_command"
+  | "cancel_command"
+  | "list_commands"
+  | "undo_command"
+  | "redo_command"
+  | "get_command_history"
+  | "get_command_log"
+  | "get_recent_commands"
+  | "replay_commands"
+  | "resolve_keybinding"
+  | "get_keybindings"
+  | "get_context"
+  | "set_context_key"
+  | "reset_context_key"
   | "get_translations"
   | "get_color_theme"
   | "create_new_window"
   | "sidebar_get_all_activities"
   | "get_menu_items_by_namespace"
+  | "resolve_menu"
   | "get_locales"
   | "get_state"
-  | "get_themes";
+  | "get_themes"
+  | "install_addon"
+  | "disable_addon"
+  | "notify_window_restored"
+  | "notify_window_idle";
 
 export type IpcResult<T, E> = { status: "ok"; data: T } | { status: "error"; error: E };
 
@@ -22,7 +41,7 @@ export const handleTauriIpcError = (cmd: TauriIpcCommand, error: unknown) => {
   // TODO: dispatch to a global error handler or show user notifications
 };
 
-export const invokeTauriIpc = async <T, E = unknown>(
+export const invokeTauriIpc = async <T, E = TauriError>(
   cmd: TauriIpcCommand,
   args?: InvokeArgs
 ): Promise<IpcResult<T, E>> => {